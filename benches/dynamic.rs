@@ -0,0 +1,115 @@
+//! Benchmarks of the lookup of units by their string key in dynamic mode
+//! (`#[mauzi(dynamic)]`), which uses a perfect hash table.
+
+#![feature(proc_macro, test)]
+
+extern crate mauzi;
+extern crate test;
+
+
+mod dict {
+    use mauzi::mauzi;
+
+    mauzi! {
+        #[mauzi(dynamic)]
+        enum Locale { De, En }
+
+        unit cat {
+            De => "Katze",
+            En => "cat",
+        }
+
+        unit dog {
+            De => "Hund",
+            En => "dog",
+        }
+
+        unit greet(name: &str) {
+            De => "Hallo {name}!",
+            En => "Hello {name}!",
+        }
+
+        mod errors {
+            unit not_found {
+                De => "Nicht gefunden",
+                En => "Not found",
+            }
+
+            unit no_permission {
+                De => "Keine Berechtigung",
+                En => "Permission denied",
+            }
+
+            mod io {
+                unit disk_full {
+                    De => "Die Festplatte ist voll",
+                    En => "The disk is full",
+                }
+            }
+        }
+
+        mod menu {
+            unit open {
+                De => "Öffnen",
+                En => "Open",
+            }
+
+            unit save {
+                De => "Speichern",
+                En => "Save",
+            }
+
+            unit quit {
+                De => "Beenden",
+                En => "Quit",
+            }
+        }
+    }
+}
+
+use test::{black_box, Bencher};
+use dict::{Locale, TranslationKey};
+
+const KEYS: &[&str] = &[
+    "cat",
+    "dog",
+    "greet",
+    "errors.not_found",
+    "errors.no_permission",
+    "errors.io.disk_full",
+    "menu.open",
+    "menu.save",
+    "menu.quit",
+];
+
+#[bench]
+fn from_key_hit(b: &mut Bencher) {
+    b.iter(|| {
+        for key in KEYS {
+            black_box(TranslationKey::from_key(black_box(key)));
+        }
+    });
+}
+
+#[bench]
+fn from_key_miss(b: &mut Bencher) {
+    b.iter(|| black_box(TranslationKey::from_key(black_box("errors.io.not_a_unit"))));
+}
+
+/// The baseline: comparing the key with all keys.
+#[bench]
+fn linear_search(b: &mut Bencher) {
+    let all = TranslationKey::all().iter().map(|k| (k.to_string(), *k)).collect::<Vec<_>>();
+    b.iter(|| {
+        for key in KEYS {
+            let key = black_box(key);
+            black_box(all.iter().find(|&&(ref name, _)| name == key).map(|&(_, k)| k));
+        }
+    });
+}
+
+#[bench]
+fn get(b: &mut Bencher) {
+    let dict = dict::new(Locale::De);
+    b.iter(|| black_box(dict.get(black_box("errors.io.disk_full"))));
+}
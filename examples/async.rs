@@ -0,0 +1,99 @@
+#![feature(proc_macro)]
+#![cfg_attr(feature = "try_from", feature(try_from))]
+
+extern crate mauzi;
+
+
+// Units marked with `#[async]` return futures (this requires the `async`
+// feature of mauzi). This is useful if some translations aren't part of the
+// binary, but have to be fetched, e.g. from a translation service.
+pub mod dict {
+    use mauzi::mauzi;
+    use mauzi::futures::Future;
+    use remote;
+
+    mauzi! {
+        enum Locale {
+            De,
+            En { Gb, Us },
+            Fr,
+            It,
+        }
+
+        // String arms return futures which are ready immediately. All other
+        // locales are fetched by the raw body of the wildcard arm, which
+        // returns the future of the request.
+        #[async]
+        unit tip(topic: &str) {
+            De => "Ein Tipp zu {topic}",
+            En => "A tip about {topic}",
+            _ => { remote::fetch("tip", self.locale, topic) }
+        }
+
+        // Units returning a `Result` work, too. The value of the future is
+        // the `Result`, the error of the future is only used for the request.
+        #[async]
+        unit tip_count -> Result<String, ()> {
+            De => "Drei Tipps",
+            En => "Three tips",
+            _ => { remote::fetch("tip_count", self.locale, "").map(Ok) }
+        }
+    }
+}
+
+// A pretend translation service. A real client would return the future of
+// an HTTP request, which completes once the response arrived.
+pub mod remote {
+    use std::io;
+    use mauzi::futures::{future, Future};
+    use dict::Locale;
+
+    pub fn fetch(unit: &str, locale: Locale, arg: &str)
+        -> Box<Future<Item = String, Error = io::Error>>
+    {
+        let (unit, arg) = (unit.to_string(), arg.to_string());
+        match locale {
+            Locale::Fr if unit == "tip" => {
+                Box::new(future::lazy(move || Ok(format!("Une astuce sur {}", arg))))
+            }
+            _ => {
+                let msg = format!("no translation of '{}' for {}", unit, locale);
+                Box::new(future::err(io::Error::new(io::ErrorKind::NotFound, msg)))
+            }
+        }
+    }
+}
+
+fn main() {
+    // Mauzi doesn't depend on a specific runtime: the futures can be spawned
+    // on any executor. Here, we simply block until they're done.
+    use mauzi::futures::Future;
+    use dict::{Locale, EnRegion};
+
+    let de = dict::new(Locale::De);
+    assert_eq!(de.tip("Rust").wait().unwrap(), "Ein Tipp zu Rust");
+    assert_eq!(de.tip_count().wait().unwrap(), Ok("Drei Tipps".to_string()));
+
+    let en = dict::new(Locale::En(EnRegion::Gb));
+    assert_eq!(en.tip("Rust").wait().unwrap(), "A tip about Rust");
+
+    // The French translation is fetched from the service.
+    let fr = dict::new(Locale::Fr);
+    assert_eq!(fr.tip("Rust").wait().unwrap(), "Une astuce sur Rust");
+    assert_eq!(
+        fr.tip_count().wait().unwrap_err().to_string(),
+        "no translation of 'tip_count' for fr",
+    );
+
+    // Errors of the service are errors of the future.
+    let it = dict::new(Locale::It);
+    assert_eq!(
+        it.tip("Rust").wait().unwrap_err().to_string(),
+        "no translation of 'tip' for it",
+    );
+
+    // Creating the future doesn't do anything: the request is only made when
+    // it's polled.
+    let future = fr.tip("Futures");
+    assert_eq!(future.wait().unwrap(), "Une astuce sur Futures");
+}
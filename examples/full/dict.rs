@@ -0,0 +1,398 @@
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use mauzi::{mauzi, PluralCategory};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Admin,
+    User,
+}
+
+/// How often the body of `banner` was evaluated.
+pub static BANNER_RENDERS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+mauzi! {
+    // The first thing in the macro invocation is the Locale definition.
+    // Here you define which languages and regions your dictionary
+    // supports. Attributes (like additional derives, possibly via
+    // `cfg_attr`) are added to the generated `Locale` and region enums.
+    // With `global`, a global dictionary is generated as well. The schema
+    // version is written into the exports (see `MAUZI_EXPORT_DIR`). With
+    // `hash`, all `Dict` types implement `Hash` and `Eq`. With `pseudo`,
+    // the locale `Pseudo` is added for testing the UI.
+    #[mauzi(global, schema_version = 1, hash, pseudo)]
+    #[derive(Hash, PartialEq, Eq)]
+    enum Locale {
+        // You can have languages without distinguishing between regions...
+        De,
+        // ... but you can have regions for a given language, too. You can
+        // optionally give each language or region a human readable name.
+        // Regions can be grouped, to match all regions of a group at once.
+        // The `default` region is used if only the language is known.
+        // Numeric UN M49 codes can be given for use with `from_numeric()`.
+        // Locales can be compared with language tags, ignoring the case. A
+        // locale with region only equals its full tag: `En(Us) == "en-US"`,
+        // but `En(Us) != "en"`.
+        En {
+            default Gb = 826 as "British English",
+            Us = 840 as "American English",
+            Au = 36,
+        } groups { Commonwealth: [Gb, Au] },
+    }
+
+    // The base language is the one the texts are written in first. It's
+    // used for missing translations and in exports. By default, it's the
+    // first language.
+    base En;
+
+    // A simple translation unit: it returns a string depending on the
+    // locale. The optional note is meant for translators and only shows
+    // up in exports (see `MAUZI_EXPORT_DIR`).
+    #[note = "asked when creating a new account"]
+    unit fav_color {
+        De => "Was ist deine Lieblingsfarbe?",
+        En(Commonwealth) => "What is your favourite colour?",
+        En(Us) => "What is your favorite color?",
+    }
+
+    // The type of a parameter can be omitted, it's `i64` then.
+    unit lives(n) {
+        De => "{n} Leben übrig",
+        En => "{n} lives left",
+    }
+
+    // With `#[dedent]`, multi-line strings can be indented like the code
+    // around them. The common indentation and the line breaks around the
+    // text are removed.
+    #[dedent]
+    unit help_text {
+        De => "
+            Benutzung: mauzi [OPTIONEN]
+              -h  Zeigt diese Hilfe an
+        ",
+        En => "
+            Usage: mauzi [OPTIONS]
+              -h  Shows this help
+        ",
+    }
+
+    // Arms can be enabled conditionally with `#[cfg]`, e.g. depending on
+    // a cargo feature. The other arms still have to handle all locales.
+    unit build_info {
+        #[cfg(debug_assertions)]
+        En => "debug build",
+        De => "Release-Build",
+        En => "release build",
+    }
+
+    // Translations have to fit into the given number of characters
+    // (without placeholders), e.g. for buttons. The category groups units
+    // in the exports for translators, without affecting the code.
+    #[max_len = 12]
+    #[category = "buttons"]
+    unit save_button {
+        De => "Speichern",
+        En => "Save",
+    }
+
+    // The key identifies the unit in the exports. It defaults to the
+    // module path and name (here: `open_file`), but can be set to any
+    // string, e.g. the key used by an existing translation tool.
+    #[key = "menu.file.open"]
+    unit open_file {
+        De => "Datei öffnen",
+        En => "Open file",
+    }
+
+    // Not translated yet: German falls back to the base language. Compile
+    // with `MAUZI_REPORT_MISSING=1` to get a warning for each unit with
+    // missing translations.
+    unit beta_notice {
+        En => "This feature is still in beta.",
+    }
+
+    // Units with the same name can be distinguished by a context (like
+    // gettext's `msgctxt`). The method name is suffixed with the context:
+    // `post_noun()` and `post_verb()`.
+    #[context = "noun"]
+    unit post {
+        De => "Beitrag",
+        En => "Post",
+    }
+    #[context = "verb"]
+    unit post {
+        De => "Veröffentlichen",
+        En => "Post",
+    }
+
+    // Translation units can take parameters. Those are declared in a pair
+    // of parenthesis, just like parameters for a Rust function.
+    //
+    // You can then use the parameter in the string with the `{param}`
+    // syntax.
+    unit greet(name: &str) {
+        En(Au) => "G'day {name}!",
+        // A group only matches the regions which weren't matched by
+        // earlier arms. Here: `Gb`.
+        En(Commonwealth) => "Hi {name}! Are you all right, mate?",
+        En(Us) => "Hi {name}! How are you, buddy?",
+        De => "Hallo {name}, wie geht's dir?",
+    }
+
+    // A specific region can be followed by a wildcard, which handles all
+    // other locales (here: `De`, `En(Us)` and `En(Au)`).
+    unit drink {
+        En(Gb) => "Tea",
+        _ => "Coffee",
+    }
+
+    // Instead of `En(Us)`, patterns can be written as language tags.
+    // Both styles can be mixed.
+    unit color_word {
+        "en-US" => "color",
+        En => "colour",
+        "de" => "Farbe",
+    }
+
+    // Units with the same parameters can share them via a template. The
+    // unit can have more parameters after the template's ones.
+    params Visitor(name: &str);
+
+    unit welcome_back uses Visitor {
+        De => "Willkommen zurück, {name}!",
+        En => "Welcome back, {name}!",
+    }
+
+    unit visits uses Visitor (count: u32) {
+        De => "{name} war {count} Mal hier",
+        En => "{name} visited {count} times",
+    }
+
+    // Empty placeholders refer to the parameters in order. An arm using
+    // them needs exactly one per parameter.
+    unit route(from: &str, to: &str) {
+        De => "Von {} nach {}",
+        En => "From {} to {}",
+    }
+
+    // Parameters can also be referred to by index, e.g. to use one twice.
+    unit round_trip(from: &str, to: &str) {
+        De => "Von {0} nach {1} und zurück nach {0}",
+        En => "From {0} to {1} and back to {0}",
+    }
+
+    // Parameters can have any type, including references with named
+    // lifetimes.
+    unit signed(buf: &mut String, author: &'a str) {
+        De => { buf.push_str(" -- "); buf.push_str(author); buf.clone() }
+        En => { buf.push_str(" by "); buf.push_str(author); buf.clone() }
+    }
+
+    // If the text contains a lot of braces, you can choose different
+    // placeholder delimiters. Braces are then normal characters.
+    #[placeholders = "%{ }%"]
+    unit code_snippet(name: &str) {
+        _ => "fn %{name}%() {}",
+    }
+
+    // A placeholder of the form `{list | "separator"}` joins all elements
+    // of a slice with the given separator.
+    unit favorite_foods(foods: &[&str]) {
+        De => r#"Meine Lieblingsessen: {foods | ", "}"#,
+        En => r#"My favorite foods: {foods | " and "}"#,
+    }
+
+    // Instead of simple strings, you can specify your own Rust code which
+    // will generate a string instead. Note that you can't use the fancy
+    // `{param}` syntax as above.
+    unit new_emails(count: u32) {
+        // Note that the region is omitted here. You can do that if the
+        // region doesn't matter. This is equivalent to `En(_)`.
+        En => {
+            match count {
+                1 => "You have one new email".to_string(),
+                _ => format!("You have {} new emails", count),
+            }
+        }
+        De => {
+            match count {
+                1 => "Sie haben eine neue E-Mail".to_string(),
+                _ => format!("Sie haben {} neue E-Mails", count),
+            }
+        }
+    }
+
+    // The same with string bodies: a condition after a comma is checked
+    // before all other arms. Each locale needs an arm without condition.
+    unit unread_emails(count: u32) {
+        De, count == 1 => "Eine ungelesene E-Mail",
+        De => "{count} ungelesene E-Mails",
+        En, count == 1 => "One unread email",
+        En => "{count} unread emails",
+    }
+
+    // Raw bodies can use the plural rules of the locale.
+    unit new_messages(count: u64) {
+        De => {
+            match self.locale.plural_category(count) {
+                PluralCategory::One => "Eine neue Nachricht".to_string(),
+                _ => format!("{} neue Nachrichten", count),
+            }
+        }
+        En => {
+            match self.locale.plural_category(count) {
+                PluralCategory::One => "One new message".to_string(),
+                _ => format!("{} new messages", count),
+            }
+        }
+    }
+
+    // If the language is not a known language, `lang(region)` matches
+    // every locale and binds the language tag and the optional region
+    // tag, e.g. `"en"` and `Some("GB")`.
+    unit locale_info {
+        De => "Deutsch",
+        lang(region) => {
+            format!("language: {}, region: {}", lang, region.unwrap_or("-"))
+        }
+    }
+
+    // In raw bodies, `self.render()` turns `format_args!()` into a
+    // `String`.
+    unit points(count: u32) {
+        De => { self.render(format_args!("{} Punkte", count)) }
+        En => { self.render(format_args!("{} points", count)) }
+    }
+
+    // You can also specify custom return types. However, this requires you
+    // to specify raw bodies. Custom return types are mostly useful for
+    // preformatted HTML, like the `maud::Markup` type.
+    unit number_of_umlauts -> u32 {
+        De => { 3 },
+        En => { 0 },
+    }
+
+    // Raw bodies of units returning `Result<String, E>` can use `?`.
+    unit age(input: &str) -> Result<String, ::std::num::ParseIntError> {
+        De => { Ok(format!("{} Jahre", input.parse::<u32>()?)) }
+        En => { Ok(format!("{} years", input.parse::<u32>()?)) }
+    }
+
+    // Their string bodies can use `?` at the end of a placeholder, too.
+    // (With text after the `?`, it's a fallback for `Option`s instead.)
+    unit height(input: &str) -> Result<String, ::std::num::ParseIntError> {
+        De => "{input.parse::<u32>()?} cm groß",
+        En => "{input.parse::<u32>()?} cm tall",
+    }
+
+    // With `{expr:n}`, numbers are formatted according to the locale
+    // (e.g. `1.234,5` in German). An arm can use the conventions of
+    // another language instead, here: US account statements.
+    unit balance(amount: f64) {
+        De #[format_as = "En"] => "Kontostand: {amount:n} USD",
+        En => "Balance: {amount:n} USD",
+    }
+
+    // `{expr?text}` renders an `Option<&str>`, showing the text after `?`
+    // for `None`.
+    unit reviewed_by(name: Option<&str>) {
+        De => "Geprüft von: {name?niemandem}",
+        En => "Reviewed by: {name?nobody}",
+    }
+
+    // Raw bodies can contain arbitrary statements, including loops and
+    // early `return`s. Every `return` and the trailing expression have to
+    // be of the unit's return type (`String` by default).
+    unit countdown(from: u32) {
+        _ => {
+            if from == 0 {
+                return "Liftoff!".to_string();
+            }
+
+            let mut out = String::new();
+            for i in (1..from + 1).rev() {
+                out.push_str(&format!("{}... ", i));
+            }
+            out
+        }
+    }
+
+    // Units which shouldn't be translated (like brand names) can be
+    // excluded from exports.
+    #[no_translate]
+    unit app_name {
+        _ => "Mauzi",
+    }
+
+    // Placeholders starting with `@env:` are replaced by the value of
+    // the environment variable at compile time.
+    unit version {
+        De => "Version {@env:CARGO_PKG_VERSION}",
+        En => "version {@env:CARGO_PKG_VERSION}",
+    }
+
+    // Outside of placeholders, `@` is a normal character. Only the start of
+    // a body is reserved for sigils, so a literal `@` is written as `@@`
+    // there (a single `@` is an error).
+    unit contact {
+        De => "Fragen? Schreib an help@example.com",
+        En => "@@mauzi on Twitter or help@example.com",
+    }
+
+    // Arms can have guards, e.g. to match on a parameter. An arm with a
+    // guard doesn't count as handling its locale, so another arm is
+    // needed for the case the guard doesn't hold.
+    unit welcome(role: Role) {
+        De if role == Role::Admin => "Willkommen zurück, Chef!",
+        De => "Willkommen zurück!",
+        En if role == Role::Admin => "Welcome back, boss!",
+        En => "Welcome back!",
+    }
+
+    // Raw bodies can use other units via `self`, which is the `Dict` of
+    // the current module.
+    unit farewell(name: &str) {
+        _ => { format!("{} ({})", self.goodbye(name), self.app_name()) }
+    }
+
+    // String bodies can insert other units of the same module with
+    // `{@unit(args)}`. The locale of the dictionary is used for those.
+    unit welcome_visitor(name: &str) {
+        De => "{@greet(name)} Schön, dass du da bist.",
+        En => "{@greet(name)} Nice to have you here.",
+    }
+
+    // Functions needed by several raw bodies can be defined in a
+    // `helpers` block. They are visible to all units.
+    helpers {
+        fn shout(s: &str) -> String {
+            format!("{}!", s.to_uppercase())
+        }
+    }
+
+    unit warning {
+        De => { shout("Achtung") }
+        En => { shout("Attention") }
+    }
+
+    // With `#[cache]`, the result is computed once per dictionary and
+    // arguments and stored in the dictionary for later calls. That's
+    // useful for expensive raw bodies. The parameters have to implement
+    // `Hash`, `PartialEq` and `Clone` (or `ToOwned` for references) and
+    // the return type `Clone`.
+    #[cache]
+    unit banner(width: usize) {
+        De => {
+            BANNER_RENDERS.fetch_add(1, Ordering::SeqCst);
+            format!("{:=^1$}", " Willkommen ", width)
+        }
+        En => {
+            BANNER_RENDERS.fetch_add(1, Ordering::SeqCst);
+            format!("{:=^1$}", " Welcome ", width)
+        }
+    }
+
+    unit goodbye(name: &str) {
+        De => { shout(&format!("Tschüss, {}", name)) }
+        En => { shout(&format!("Bye, {}", name)) }
+    }
+}
@@ -0,0 +1,46 @@
+// Several languages can share the same regions via a region set. They use
+// the same region type (here: `EuRegion`). With `dynamic`, units can be
+// looked up and rendered by their key at runtime. With `warn_todo`, strings
+// containing `TODO` or `FIXME` are reported as warnings while compiling. Each
+// dictionary of a crate needs its own `export_name`, so that their exports
+// don't overwrite each other.
+
+use mauzi::mauzi;
+
+mauzi! {
+    #[mauzi(dynamic, warn_todo, export_name = "eu")]
+    #[derive(PartialEq)]
+    enum Locale {
+        regions Eu { default Be as "Belgium", Ch as "Switzerland" },
+        Fr(Eu),
+        De(Eu),
+    }
+
+    unit hello {
+        Fr(Ch) => "Salut",
+        Fr => "Bonjour",
+        De(Ch) => "Grüezi",
+        De => "Hallo",
+    }
+
+    unit welcome(name: &str, count: u32) {
+        Fr => "Bienvenue, {name} ! Vous avez {count:n} messages.",
+        De => "Willkommen, {name}! Du hast {count:n} Nachrichten.",
+    }
+
+    // With `#[mauzi(const)]`, the method would be a `const fn` returning
+    // a `&'static str` (and `new()` a `const fn`, too), which can be used
+    // in constants. That requires `#![feature(const_fn)]` and a compiler
+    // which allows `match` in a `const fn`, so it isn't used here.
+    unit yes {
+        Fr => "Oui",
+        De => "Ja",
+    }
+
+    // This emits a warning until the Swiss German text is done.
+    unit goodbye {
+        Fr => "Au revoir",
+        De(Ch) => "TODO: Ade",
+        De => "Tschüss",
+    }
+}
@@ -0,0 +1,24 @@
+// With `format_macro`, string bodies use the given macro instead of
+// `format!()`, e.g. to escape interpolated values for HTML. The macro has to
+// be in scope at the `mauzi!` invocation.
+
+use mauzi::mauzi;
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+macro_rules! html_format {
+    ($fmt:expr $(, $arg:expr)*) => {
+        format!($fmt $(, escape(&$arg.to_string()))*)
+    };
+}
+
+mauzi! {
+    #[mauzi(format_macro = "html_format", export_name = "html")]
+    enum Locale { En }
+
+    unit greet(name: &str) {
+        En => "<b>Hello {name}!</b>",
+    }
+}
@@ -0,0 +1,49 @@
+#![feature(proc_macro)]
+#![cfg_attr(feature = "try_from", feature(try_from))]
+
+extern crate mauzi;
+
+
+// The `mauzi` macro is usually invoked in a `dict` submodule, which lives in
+// its own file. All features are shown in `dict.rs`, the other dictionaries
+// show the configurations which apply to a whole dictionary. The behavior of
+// all of them is checked in `tests/full.rs`.
+pub mod dict;
+pub mod eu_dict;
+pub mod html_dict;
+
+fn main() {
+    use dict::{EnRegion, Locale};
+
+    let locales = [
+        Locale::De,
+        Locale::En(EnRegion::Gb),
+        Locale::En(EnRegion::Us),
+        Locale::En(EnRegion::Au),
+    ];
+
+    for &locale in &locales {
+        println!("--- for {} ({}) ---", locale.display_name(), locale);
+        let dict = dict::new(locale);
+
+        // All translation keys are simple functions. You can access it like
+        // calling a function.
+        println!("greet       => {}", dict.greet("Ferris"));
+        println!("fav_color   => {}", dict.fav_color());
+        println!("new_emails  => {}", dict.new_emails(3));
+        println!("balance     => {}", dict.balance(12345.5));
+        println!("umlauts     => {}", dict.number_of_umlauts());
+        println!("farewell    => {}", dict.farewell("Ferris"));
+    }
+
+    // Units can be rendered by key with arguments only known at runtime.
+    let eu = eu_dict::new(eu_dict::Locale::De(eu_dict::EuRegion::Ch));
+    println!("--- by key for {} ---", eu_dict::Locale::De(eu_dict::EuRegion::Ch));
+    println!("hello       => {}", eu.get("hello").unwrap());
+    println!("welcome     => {}", eu.render_key("welcome", &[&"Ferris", &1200]).unwrap());
+
+    // Only the interpolated name is escaped, not the markup of the string.
+    let html = html_dict::new(html_dict::Locale::En);
+    println!("--- escaped HTML ---");
+    println!("greet       => {}", html.greet("<script>"));
+}
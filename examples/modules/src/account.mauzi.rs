@@ -0,0 +1,4 @@
+unit welcome_back {
+    De => "Willkommen zurück, {user.name}!",
+    En => "Welcome back, {user.name}!",
+}
@@ -0,0 +1,15 @@
+unit yes {
+    De => "Ja",
+    En => "Yes",
+}
+
+unit no {
+    De => "Nein",
+    En => "No",
+}
+
+// Units of a flattened module can call the units of the parent module, too.
+unit no_cat {
+    De => "{@no()}, keine {@cat()}",
+    En => "{@no()}, no {@cat()}",
+}
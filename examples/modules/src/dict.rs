@@ -1,17 +1,39 @@
 use mauzi::mauzi;
 
+use locale::{Language, Locale};
+
 
 mauzi! {
-    enum Locale {
-        De,
-        En,
-    }
+    // The locale types are defined once in `locale.rs` (loaded from a file
+    // relative to `src/`), so we only `use` them here.
+    use locale::Locale;
 
     mod foo;
     mod bar;
 
+    // The units of a flattened module are part of this module, e.g.
+    // `dict.yes()` instead of `dict.common.yes()`.
+    #[flatten]
+    mod common;
+
+    // Units can also be loaded from a CSV file with a `key` column and one
+    // column per locale.
+    mod strings from csv("strings.csv");
+
     unit cat {
         De => "Katze",
         En => "cat",
     }
+
+    // Long texts can be stored in files relative to `src/` (or to the file
+    // of the module). They can contain placeholders, too.
+    unit welcome_email(name: &str) {
+        De => file("emails/welcome_de.txt"),
+        En => file("emails/welcome_en.txt"),
+    }
+
+    // Units of submodules are reachable via the fields of `self`.
+    unit greet_cat {
+        _ => { self.foo.greet(&self.cat()) }
+    }
 }
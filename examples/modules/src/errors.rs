@@ -0,0 +1,26 @@
+use mauzi::mauzi;
+
+// The dictionaries refer to the shared locale types, so those have to be in
+// scope.
+use locale::{Language, Locale};
+
+
+mauzi! {
+    // With `use locale::Locale;`, the locale types of `mauzi_locale!` are used
+    // instead of generating them again. Using a missing translation is
+    // reported to the hook set via `mauzi::set_missing_hook()` (see
+    // `tests/modules.rs`).
+    #[mauzi(on_missing = "log")]
+    use locale::Locale;
+
+    unit not_found(path: &str) {
+        De => "Die Datei '{path}' wurde nicht gefunden",
+        En => "The file '{path}' was not found",
+    }
+
+    // Not translated yet: English falls back to German (the base language)
+    // and the hook is called.
+    unit disk_full {
+        De => "Die Festplatte ist voll",
+    }
+}
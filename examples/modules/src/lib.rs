@@ -0,0 +1,13 @@
+//! The dictionaries of this example, which are split into several modules
+//! and files. They are used by `main.rs` and checked in `tests/`.
+
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+
+pub mod locale;
+pub mod dict;
+pub mod errors;
+pub mod nesting;
+pub mod profile;
@@ -0,0 +1,14 @@
+use mauzi::mauzi_locale;
+
+
+// The locale types are defined once and shared by all dictionaries of this
+// crate (`dict` and `errors`).
+mauzi_locale! {
+    // Generates `Locale::from_id()` and the C functions
+    // `modules_locale_from_code()` and `modules_locale_to_code()`, so that C
+    // code can select a locale via its stable id.
+    #[mauzi(ffi = "modules")]
+    // Compared in the assertions of `main.rs`.
+    #[derive(PartialEq)]
+    locales from "locales.toml";
+}
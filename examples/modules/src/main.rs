@@ -1,25 +1,27 @@
-#![feature(proc_macro)]
+extern crate modules_example;
 
-extern crate mauzi;
+use std::rc::Rc;
 
+use modules_example::{dict, errors, profile};
+use modules_example::locale::Locale;
+use modules_example::profile::User;
 
-mod dict;
 
 fn main() {
-    use dict::Locale;
+    // The user is given once and used by the units of all modules.
+    let user = Rc::new(User { name: "Lukas".into() });
 
-    let locales = [
-        Locale::De,
-        Locale::En,
-    ];
-
-    for &locale in &locales {
+    for &locale in Locale::all() {
         println!("--- for {:?} ---", locale);
         let dict = dict::new(locale);
 
-        println!("cat              => {}", dict.cat());
-        println!("foo::greet       => {}", dict.foo.greet("Lukas"));
-        println!("bar::hello_world => {}", dict.bar.hello_world());
-        println!("baz::bye_world   => {}", dict.bar.baz.bye_world());
+        println!("cat                   => {}", dict.cat());
+        println!("foo::greet            => {}", dict.foo.greet("Lukas"));
+        println!("bar::hello_world      => {}", dict.bar.hello_world());
+        println!("baz::bye_world        => {}", dict.bar.baz.bye_world());
+        println!("strings::apples       => {}", dict.strings.apples(3));
+        println!("errors::not_found     => {}", errors::new(locale).not_found("a.txt"));
+        let profile = profile::new(locale, user.clone());
+        println!("profile::signed_in_as => {}", profile.signed_in_as());
     }
 }
@@ -0,0 +1,4 @@
+// Level 1 of the nesting test in `nesting.rs`. Each level only contains the
+// next one.
+
+mod n;
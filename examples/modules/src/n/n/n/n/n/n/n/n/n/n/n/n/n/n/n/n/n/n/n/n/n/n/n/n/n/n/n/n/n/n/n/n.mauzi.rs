@@ -0,0 +1,5 @@
+// Level 32, the deepest nesting allowed.
+unit bottom {
+    De => "Ganz unten",
+    En => "At the bottom",
+}
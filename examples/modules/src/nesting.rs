@@ -0,0 +1,12 @@
+use mauzi::mauzi;
+
+use locale::{Language, Locale};
+
+
+// A stress test: modules can be nested up to 32 levels deep, here via
+// `n/n/.../n.mauzi.rs`. Deeper nesting is an error.
+mauzi! {
+    use locale::Locale;
+
+    mod n;
+}
@@ -0,0 +1,25 @@
+use std::rc::Rc;
+
+use mauzi::mauzi;
+
+use locale::{Language, Locale};
+
+
+pub struct User {
+    pub name: String,
+}
+
+mauzi! {
+    use locale::Locale;
+
+    // The context is passed to `profile::new()` and handed down to all
+    // submodules, so all units can use it without declaring a parameter.
+    context(user: Rc<User>);
+
+    mod account;
+
+    unit signed_in_as {
+        De => "Angemeldet als {user.name}",
+        En => "Signed in as {user.name}",
+    }
+}
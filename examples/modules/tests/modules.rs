@@ -0,0 +1,137 @@
+//! Tests of the dictionaries of this example, which are split into several
+//! modules and files.
+
+extern crate mauzi;
+extern crate modules_example;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use modules_example::{dict, errors, nesting, profile};
+use modules_example::dict::TranslationKey;
+use modules_example::locale::Locale;
+use modules_example::profile::User;
+
+
+thread_local! {
+    /// The missing translations reported to the hook.
+    static MISSING: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+#[test]
+fn units() {
+    // The user is given once and used by the units of all modules.
+    let user = Rc::new(User { name: "Lukas".into() });
+
+    let dict = dict::new(Locale::De);
+    assert_eq!(dict.cat(), "Katze");
+    assert_eq!(dict.greet_cat(), "Hallo Katze!");
+    assert_eq!(
+        dict.welcome_email("Lukas"),
+        "Hallo Lukas,\n\nwillkommen bei Mauzi! Wir freuen uns, dass du da bist.\n\nViele Grüße",
+    );
+    assert_eq!((dict.yes(), dict.no()), ("Ja".into(), "Nein".into()));
+    assert_eq!(dict.no_cat(), "Nein, keine Katze");
+    assert_eq!(dict.foo.greet("Lukas"), "Hallo Lukas!");
+    assert_eq!(dict.bar.hello_world(), "Hallo Welt");
+    assert_eq!(dict.bar.baz.bye_world(), "Tschüss Welt");
+    assert_eq!(dict.strings.apples(3), "3 Äpfel");
+    assert_eq!(dict.strings.quote("Lukas"), "Sie sagte: \"Hallo, Lukas!\"");
+    assert_eq!(dict.strings.downloads(12345), "12.345 Downloads (genau 12345)");
+    let errs = errors::new(Locale::De);
+    assert_eq!(errs.not_found("a.txt"), "Die Datei 'a.txt' wurde nicht gefunden");
+    assert_eq!(errs.disk_full(), "Die Festplatte ist voll");
+    let profile = profile::new(Locale::De, user.clone());
+    assert_eq!(profile.signed_in_as(), "Angemeldet als Lukas");
+    assert_eq!(profile.account.welcome_back(), "Willkommen zurück, Lukas!");
+
+    let dict = dict::new(Locale::En);
+    assert_eq!(dict.cat(), "cat");
+    assert_eq!(dict.greet_cat(), "Hi cat!");
+    assert_eq!(
+        dict.welcome_email("Lukas"),
+        "Hi Lukas,\n\nwelcome to Mauzi! We are happy to have you.\n\nBest regards",
+    );
+    assert_eq!((dict.yes(), dict.no()), ("Yes".into(), "No".into()));
+    assert_eq!(dict.no_cat(), "No, no cat");
+    assert_eq!(dict.foo.greet("Lukas"), "Hi Lukas!");
+    assert_eq!(dict.bar.hello_world(), "Hello world");
+    assert_eq!(dict.bar.baz.bye_world(), "Bye world");
+    assert_eq!(dict.strings.apples(3), "3 apples");
+    assert_eq!(dict.strings.quote("Lukas"), "She said: \"Hello, Lukas!\"");
+    assert_eq!(dict.strings.downloads(12345), "12,345 downloads (exactly 12345)");
+    assert_eq!(errors::new(Locale::En).not_found("a.txt"), "The file 'a.txt' was not found");
+    let profile = profile::new(Locale::En, user.clone());
+    assert_eq!(profile.signed_in_as(), "Signed in as Lukas");
+    assert_eq!(profile.account.welcome_back(), "Welcome back, Lukas!");
+}
+
+#[test]
+fn missing_hook() {
+    // Called for each missing translation in `errors`.
+    mauzi::set_missing_hook(|unit, locale| {
+        MISSING.with(|m| m.borrow_mut().push(format!("{} {}", unit, locale)));
+    });
+
+    // `disk_full` isn't translated to English, so the German text is used
+    // and the hook is called.
+    assert!(MISSING.with(|m| m.borrow().is_empty()));
+    assert_eq!(errors::new(Locale::En).disk_full(), "Die Festplatte ist voll");
+    assert_eq!(MISSING.with(|m| m.borrow().clone()), vec!["disk_full En"]);
+}
+
+#[test]
+fn keys_and_maps() {
+    for &locale in Locale::all() {
+        let dict = dict::new(locale);
+
+        // Each unit knows for which locales it has translations. Wildcard
+        // arms (like the one of `greet_cat`) don't count.
+        assert_eq!(dict.strings.only_german_available_locales(), vec![Locale::De]);
+        assert_eq!(dict.cat_available_locales(), vec![Locale::De, Locale::En]);
+        assert!(dict.greet_cat_available_locales().is_empty());
+
+        // All units without parameters can be collected into a map. The keys
+        // are the paths of the units.
+        let expected = match locale {
+            Locale::De => [
+                ("bar.baz.bye_world", "Tschüss Welt"),
+                ("bar.hello_world", "Hallo Welt"),
+                ("cat", "Katze"),
+                ("greet_cat", "Hallo Katze!"),
+                ("no", "Nein"),
+                ("no_cat", "Nein, keine Katze"),
+                ("strings.only_german", "Nur auf Deutsch"),
+                ("yes", "Ja"),
+            ],
+            Locale::En => [
+                ("bar.baz.bye_world", "Bye world"),
+                ("bar.hello_world", "Hello world"),
+                ("cat", "cat"),
+                ("greet_cat", "Hi cat!"),
+                ("no", "No"),
+                ("no_cat", "No, no cat"),
+                ("strings.only_german", "Nur auf Deutsch"),
+                ("yes", "Yes"),
+            ],
+        };
+        let expected = expected.iter()
+            .map(|&(key, value)| (key, value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(dict.as_map(), expected);
+
+        // All units are listed in the `TranslationKey` enum. Units with
+        // parameters can't be called via their key.
+        assert_eq!(TranslationKey::all().len(), 13);
+        for &key in TranslationKey::all() {
+            assert_eq!(dict.get_by_key(key), expected.get(key.to_string().as_str()).cloned());
+        }
+
+        // The unit of the most deeply nested module is reachable, too.
+        let nesting = nesting::new(locale);
+        let key = nesting::TranslationKey::all()[0];
+        assert_eq!(key.to_string(), format!("{}bottom", "n.".repeat(32)));
+        assert!(nesting.get_by_key(key).is_some());
+    }
+}
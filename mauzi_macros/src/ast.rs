@@ -16,7 +16,9 @@
 
 
 use std::fmt;
+use std::mem;
 use std::ops::Deref;
+use std::path::PathBuf;
 use proc_macro::{Span, Term, TokenNode, TokenStream, TokenTree};
 
 use util::Spanned;
@@ -25,9 +27,19 @@ use util::Spanned;
 /// A dictionary, consisting of zero or more *translation units*.
 #[derive(Debug, Clone)]
 pub struct Dict {
+    pub config: Config,
     pub locale_def: LocaleDef,
     pub modules: Vec<Mod>,
     pub trans_units: Vec<TransUnit>,
+
+    /// The contents of all `helpers { ... }` blocks: items which can be used
+    /// in all raw bodies.
+    pub helpers: Vec<Spanned<TokenStream>>,
+
+    /// Values shared by all units, declared via `context(user: Rc<User>);`
+    /// after the locale definition. They are passed to `Dict::new()`, stored
+    /// in the `Dict` of every module and can be used in all units by name.
+    pub context: Vec<UnitParam>,
 }
 
 impl Dict {
@@ -37,6 +49,173 @@ impl Dict {
             modules: self.modules.iter().collect(),
         }
     }
+
+    /// Returns all units together with their full key, e.g.
+    /// `bar.baz.bye_world`. The last part of the key is the method name of
+    /// the unit, all other parts are module names.
+    pub fn units_with_keys(&self) -> Vec<(String, &TransUnit)> {
+        fn collect<'a>(
+            trans_units: &'a [TransUnit],
+            modules: &'a [Mod],
+            prefix: &str,
+            out: &mut Vec<(String, &'a TransUnit)>,
+        ) {
+            for unit in trans_units {
+                out.push((format!("{}{}", prefix, unit.method_name()), unit));
+            }
+
+            for module in modules {
+                let prefix = format!("{}{}.", prefix, module.name);
+                collect(&module.trans_units, &module.modules, &prefix, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(&self.trans_units, &self.modules, "", &mut out);
+        out
+    }
+
+    /// Moves the units and submodules of all modules marked with
+    /// `#[flatten]` into their parent module. Name collisions are checked in
+    /// `check`, so this has to be called afterwards.
+    pub fn flatten_modules(&mut self) {
+        fn flatten(trans_units: &mut Vec<TransUnit>, modules: &mut Vec<Mod>) {
+            for mut module in mem::replace(modules, Vec::new()) {
+                flatten(&mut module.trans_units, &mut module.modules);
+                if module.flatten {
+                    trans_units.extend(module.trans_units);
+                    modules.extend(module.modules);
+                } else {
+                    modules.push(module);
+                }
+            }
+        }
+
+        flatten(&mut self.trans_units, &mut self.modules);
+    }
+}
+
+/// Options for the whole dictionary, specified via `#[mauzi(...)]` in front
+/// of the locale definition.
+///
+/// # Example
+///
+/// ```
+/// #[mauzi(crate = "my_i18n", strict)]
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Path to the `mauzi` crate, used by the generated code to refer to
+    /// runtime items. Relative paths are resolved from the crate root.
+    pub crate_path: String,
+
+    /// If `true`, every unit has to explicitly handle every locale. Wildcard
+    /// patterns (`_` or bindings) are forbidden.
+    pub strict: bool,
+
+    /// If `true`, keys can be looked up by string at runtime via
+    /// `Dict::get()`, using a perfect hash table generated at compile time.
+    pub dynamic: bool,
+
+    /// If `true`, identifiers in arm patterns which are not a language or
+    /// region have to start with a lowercase letter or `_` to be used as
+    /// variable bindings. This catches typos like `Eng`.
+    pub strict_patterns: bool,
+
+    /// If `true`, each use of a missing translation is reported via
+    /// `mauzi::report_missing()`. Set via `on_missing = "log"`.
+    pub log_missing: bool,
+
+    /// If set, C functions to convert between language tags and stable
+    /// integer ids of the locales are generated, with this prefix in their
+    /// names. The prefix keeps the symbols of several dictionaries (or
+    /// crates) apart. Set via `ffi = "myapp"`, which generates
+    /// `myapp_locale_from_code()` and `myapp_locale_to_code()`.
+    pub ffi: Option<String>,
+
+    /// If `true`, a global dictionary and free functions for all top level
+    /// units are generated, which forward to the global dictionary.
+    pub global: bool,
+
+    /// The visibility of the generated types and functions, e.g. `pub` or
+    /// `pub(crate)`. Set via `pub_crate` or `vis = "pub(in path)"`.
+    pub vis: String,
+
+    /// A version number for tools consuming the exports, which is written
+    /// into the exports. Set via `schema_version = 1`.
+    pub schema_version: Option<u64>,
+
+    /// The name of the export files without extension, e.g. `mauzi` for
+    /// `mauzi.json` and `mauzi.md`. Each dictionary of a crate needs its own
+    /// name, otherwise they overwrite each other's exports. Set via
+    /// `export_name = "errors"`.
+    pub export_name: String,
+
+    /// If `true`, `PartialEq`, `Eq` and `Hash` are derived for the locale
+    /// types and all `Dict` types, e.g. to use a `Dict` as key of a cache. With
+    /// locale types from `mauzi_locale!`, those need the flag as well.
+    pub hash: bool,
+
+    /// If `true`, the language `Pseudo` is added to the locale. It shows the
+    /// strings of the base language with accented letters and markers, to
+    /// find untranslated strings and layout problems. With locale types from
+    /// `mauzi_locale!` (`use locale::Locale;`), the flag of that invocation
+    /// is used.
+    pub pseudo: bool,
+
+    /// Markers like `TODO` which translators leave in unfinished strings.
+    /// A warning is emitted for each string arm containing one of them. Set
+    /// via `warn_todo` (`TODO` and `FIXME`) or `warn_todo = "TODO, XXX"`.
+    /// Empty if the lint is disabled.
+    pub todo_markers: Vec<String>,
+
+    /// The name of a macro used instead of `format!()` for string bodies,
+    /// e.g. one escaping the interpolated values for HTML. It's called like
+    /// `format!()` and has to return a `String`. Since it's called by name,
+    /// it has to be in scope where `mauzi!` is invoked. Set via
+    /// `format_macro = "html_format"`.
+    pub format_macro: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            crate_path: "mauzi".into(),
+            strict: false,
+            dynamic: false,
+            strict_patterns: false,
+            log_missing: false,
+            ffi: None,
+            global: false,
+            vis: "pub".into(),
+            schema_version: None,
+            export_name: "mauzi".into(),
+            hash: false,
+            pseudo: false,
+            todo_markers: Vec::new(),
+            format_macro: None,
+        }
+    }
+}
+
+impl Config {
+    /// Returns the path to the `mauzi` crate as token stream.
+    pub fn crate_path(&self) -> TokenStream {
+        let path = if self.crate_path.starts_with("::") {
+            self.crate_path.clone()
+        } else {
+            format!("::{}", self.crate_path)
+        };
+
+        // The path was already checked to be valid while parsing.
+        path.parse().unwrap()
+    }
+
+    /// Returns the visibility of generated items as token stream.
+    pub fn vis(&self) -> TokenStream {
+        // The visibility was already checked to be valid while parsing.
+        self.vis.parse().unwrap()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,12 +223,28 @@ pub struct Mod {
     pub name: Ident,
     pub modules: Vec<Mod>,
     pub trans_units: Vec<TransUnit>,
+    /// `true` if the module is marked with `#[flatten]`: its units and
+    /// submodules belong to the parent module. See `Dict::flatten_modules()`.
+    pub flatten: bool,
 }
 
 /// Defines all languages and regions used by the dictionary.
 #[derive(Debug, Clone)]
 pub struct LocaleDef {
     pub langs: Vec<LocaleLang>,
+    /// Attributes in front of the locale definition (except `#[mauzi]`),
+    /// without the surrounding `#[...]`. They are passed through to the
+    /// generated `Locale` and `*Region` enums, e.g. `derive(Hash)`.
+    pub attrs: Vec<TokenStream>,
+    /// `true` if the locale types are already defined via `mauzi_locale!`
+    /// (`use locale::Locale;`) and must not be generated again.
+    pub is_external: bool,
+    /// The language given via `base En;` after the locale definition, if
+    /// any. See `base_lang()`.
+    pub base: Option<Ident>,
+    /// The file the locale definition was loaded from, if it was written as
+    /// `locales from "locales.toml";`.
+    pub source_file: Option<PathBuf>,
 }
 
 impl LocaleDef {
@@ -63,13 +258,35 @@ impl LocaleDef {
         self.langs.iter()
             .find(|lang| lang.name.as_str() == lang_name)
     }
+
+    /// Returns the base language: the language the translations originate
+    /// from. It is used as fallback for missing translations and as source
+    /// in exports. If not declared explicitly, it's the first language.
+    pub fn base_lang(&self) -> Option<&LocaleLang> {
+        match self.base {
+            Some(base) => self.get_lang(&base),
+            None => self.langs.first(),
+        }
+    }
 }
 
-/// A language with an optional list of regions.
+/// A language with an optional list of regions and region groups.
+///
+/// # Example
+///
+/// ```
+/// En as "English" { Gb as "British English", Us } groups { Americas: [Us] }
+/// ```
 #[derive(Debug, Clone)]
 pub struct LocaleLang {
     pub name: Ident,
-    pub regions: Vec<Ident>,
+    pub display_name: Option<String>,
+    pub regions: Vec<LocaleRegion>,
+    pub groups: Vec<RegionGroup>,
+    /// The name of the region set (`regions Eu { ... }`) if the regions were
+    /// given via `En(Eu)`. All languages using the same set share one region
+    /// type.
+    pub region_set: Option<Ident>,
 }
 
 impl LocaleLang {
@@ -79,9 +296,55 @@ impl LocaleLang {
 
     pub fn contains_region(&self, region_name: &str) -> bool {
         self.regions.iter()
-            .find(|region| region.as_str() == region_name)
+            .find(|region| region.name.as_str() == region_name)
             .is_some()
     }
+
+    /// Returns the region used when only the language is known: the region
+    /// marked with `default` or, if there is none, the first region.
+    pub fn default_region(&self) -> Option<&LocaleRegion> {
+        self.regions.iter()
+            .find(|region| region.is_default)
+            .or(self.regions.first())
+    }
+
+    /// Returns the region group with the given name if it exists.
+    pub fn get_group(&self, group_name: &str) -> Option<&RegionGroup> {
+        self.groups.iter()
+            .find(|group| group.name.as_str() == group_name)
+    }
+}
+
+/// A region of a language. One region per language can be marked as default
+/// region with the `default` keyword.
+///
+/// # Example
+///
+/// ```
+/// default Gb = 826 as "British English"
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocaleRegion {
+    pub name: Ident,
+    pub display_name: Option<String>,
+    pub is_default: bool,
+    /// The numeric UN M49 code of the region (e.g. `826` for `Gb`), used by
+    /// `Locale::from_numeric()`.
+    pub numeric_code: Option<Spanned<u16>>,
+}
+
+/// A named group of regions of one language. In arm patterns, the group can
+/// be used like a region (`En(Europe)`) and matches all of its regions.
+///
+/// # Example
+///
+/// ```
+/// Europe: [Gb, Ie]
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegionGroup {
+    pub name: Ident,
+    pub regions: Vec<Ident>,
 }
 
 /// A named translation unit, consisting of a definition and optional
@@ -91,14 +354,73 @@ impl LocaleLang {
 /// definition of a translation unit contains definitions for different
 /// languages. In those definitions, the translation unit's parameters may be
 /// used.
+///
+/// A unit can have a note for translators (`#[note = "..."]`). It's not used
+/// to generate code, but it is part of the exports.
+///
+/// Units marked with `#[mauzi(const)]` are generated as `const fn` returning
+/// `&'static str`.
+///
+/// Units marked with `#[no_translate]` (e.g. brand names) are excluded from
+/// all exports, but code is generated as usual.
 #[derive(Debug, Clone)]
 pub struct TransUnit {
     pub name: Ident,
+    pub note: Option<String>,
+    /// Optional category given via `#[category = "checkout"]`. It only groups
+    /// units in the exports and doesn't affect the generated code.
+    pub category: Option<String>,
+    /// Optional key given via `#[key = "menu.file.open"]`. It replaces the
+    /// full key of the unit (module path and name) in the exports, while the
+    /// generated method is still named after the unit.
+    pub key: Option<Spanned<String>>,
+    /// Optional context to distinguish units with the same name, like
+    /// gettext's `msgctxt`.
+    pub context: Option<Spanned<String>>,
+    /// Custom placeholder delimiters (e.g. `%{` and `}%`) specified via
+    /// `#[placeholders = "%{ }%"]`. `None` means `{` and `}`.
+    pub placeholders: Option<(String, String)>,
+    /// `true` if the unit is marked with `#[mauzi(const)]`. Its method is then
+    /// a `const fn` returning a `&'static str`, and so is `new()` (if
+    /// possible). This requires `#![feature(const_fn)]` in the crate using
+    /// `mauzi!` and a compiler which allows `match` in a `const fn`.
+    pub is_const: bool,
+    /// `Some(_)` if the unit is marked with `#[deprecated]` or
+    /// `#[deprecated = "note"]`. The attribute is added to the generated
+    /// method.
+    pub deprecated: Option<Option<String>>,
+    pub no_translate: bool,
+    /// The maximum length (in characters, without placeholders) of each
+    /// translation, given via `#[max_len = 40]`.
+    pub max_len: Option<Spanned<u64>>,
+    /// `true` if the unit is marked with `#[cache]`: its results are
+    /// memoized per `Dict` and arguments.
+    pub cache: bool,
+    /// `true` if the unit is marked with `#[async]`: its method returns a
+    /// future, which raw bodies can use to call asynchronous fallbacks.
+    pub is_async: bool,
     pub params: Option<Vec<UnitParam>>,
     pub return_type: Option<Ty>,
     pub body: UnitBody,
 }
 
+impl TransUnit {
+    /// Returns the name of the method generated for this unit. That's the
+    /// unit's name, suffixed with the context if there is one (e.g.
+    /// `post_verb`).
+    pub fn method_name(&self) -> Ident {
+        match self.context {
+            None => self.name,
+            Some(ref context) => {
+                Ident::new(
+                    Term::intern(&format!("{}_{}", self.name, context.obj)),
+                    self.name.diag_span(),
+                )
+            }
+        }
+    }
+}
+
 /// A paramter of a translation unit.
 ///
 /// # Example
@@ -125,18 +447,35 @@ pub struct UnitBody {
     pub arms: Vec<UnitArm>,
 }
 
-/// One arm of a translation unit's body, consisting of a pattern/matcher and
-/// a body.
+/// One arm of a translation unit's body, consisting of a pattern/matcher, an
+/// optional guard and a body.
 ///
 /// # Example
 ///
 /// ```
 /// En(Gb) => "Hello sir"
+/// De if role == Role::Admin => "Hallo Chef"
+/// De #[format_as = "En"] => "Betrag: {amount:n}"
 /// ```
 #[derive(Debug, Clone)]
 pub struct UnitArm {
+    /// `#[cfg(...)]` attributes in front of the arm (without the surrounding
+    /// `#[...]`). They are put on the generated match arms.
+    pub cfgs: Vec<Spanned<TokenStream>>,
     pub pattern: ArmPattern,
+    /// The guard expression (without `if`), spanned by the `if` keyword.
+    pub guard: Option<Spanned<TokenStream>>,
+    /// `true` if the guard is a count condition written after a comma, like
+    /// `De, count == 1 => "eine Mail"`. Those arms are tried before all
+    /// others and need an arm without condition for the same locales.
+    pub is_count_condition: bool,
+    /// The language whose conventions are used to format numbers in this
+    /// arm, specified via `#[format_as = "En"]` after the pattern.
+    pub format_as: Option<Spanned<String>>,
     pub body: Spanned<ArmBody>,
+    /// The file a string body was read from, if it was written as
+    /// `file("welcome.txt")` or loaded from a CSV file.
+    pub source_file: Option<PathBuf>,
 }
 
 /// One arm's pattern.
@@ -158,8 +497,12 @@ pub struct UnitArm {
 /// Language and region given
 /// ```
 /// En(Gb)
+/// // ... or (a region group) ...
+/// En(Europe)
 /// // ... or ...
 /// En(region)
+/// // ... or (binding the language and region tag) ...
+/// lang(region)
 /// ```
 #[derive(Debug, Clone)]
 pub enum ArmPattern {
@@ -172,15 +515,13 @@ pub enum ArmPattern {
 }
 
 impl ArmPattern {
-    /// Assumes all idents used in this pattern have spans.
-    #[allow(dead_code)]
     pub fn span(&self) -> Span {
         match *self {
             ArmPattern::Underscore(span) => span,
-            ArmPattern::Lang(lang) => lang.span().unwrap(),
+            ArmPattern::Lang(lang) => lang.diag_span(),
 
             // TODO: join these two spans!
-            ArmPattern::WithRegion { lang, .. } => lang.span().unwrap(),
+            ArmPattern::WithRegion { lang, .. } => lang.diag_span(),
         }
     }
 }
@@ -238,6 +579,47 @@ impl ArmBody {
 #[derive(Debug, Clone)]
 pub struct Ty(pub String);
 
+impl Ty {
+    /// Returns `true` if this type is `Result<String, E>` (for any `E`).
+    pub fn is_string_result(&self) -> bool {
+        let ty = self.0.split_whitespace().collect::<String>();
+        ty.starts_with("Result<String,")
+    }
+
+    /// Returns the bound if this type has the form `impl Bound`. Parameters of
+    /// such a type become type parameters of the generated methods, since
+    /// `impl Trait` can't be used for arguments on stable Rust yet.
+    pub fn impl_bound(&self) -> Option<&str> {
+        let ty = self.0.trim();
+        if ty.starts_with("impl ") {
+            Some(ty["impl ".len()..].trim())
+        } else {
+            None
+        }
+    }
+
+    /// Returns all named lifetimes (except `'static`) used in this type.
+    pub fn lifetimes(&self) -> Vec<String> {
+        // We know that it can be parsed correctly, since we created the
+        // string from a token stream.
+        self.0.parse::<TokenStream>().unwrap()
+            .into_iter()
+            .flat_map(|tt| match tt.kind {
+                TokenNode::Group(_, ts) => Ty(ts.to_string()).lifetimes(),
+                TokenNode::Term(term) => {
+                    let s = term.as_str();
+                    if s.starts_with('\'') && s != "'static" && s != "'_" {
+                        vec![s.to_string()]
+                    } else {
+                        vec![]
+                    }
+                }
+                _ => vec![],
+            })
+            .collect()
+    }
+}
+
 /// An identificator (some word like thing).
 #[derive(Debug, Clone, Copy)]
 pub struct Ident {
@@ -255,7 +637,8 @@ impl Ident {
     }
 
     /// Creates a `Ident` which won't be visible to the calling code. It won't
-    /// have a span, so you can't emit a spanned diagnostic for this ident.
+    /// have a span, so diagnostics for this ident point to the macro
+    /// invocation (see `diag_span`).
     ///
     /// This function should only be used for new, generated idents.
     pub fn internal(s: &str) -> Self {
@@ -280,8 +663,10 @@ impl Ident {
         self.term.as_str()
     }
 
-    pub fn span(&self) -> Option<Span> {
-        self.span
+    /// Returns the span to use for diagnostics about this ident. Internal
+    /// idents don't have a span, so we fall back to the call site.
+    pub fn diag_span(&self) -> Span {
+        self.span.unwrap_or_else(Span::call_site)
     }
 }
 
@@ -1,19 +1,58 @@
 use Result;
-use ast;
+use ast::{self, Ident};
+use gen::{camel_case, split_format_str};
+use util::{
+    env_placeholder, fallback_placeholder, is_binding_name, is_keyword, is_valid_ident,
+    number_placeholder, split_call_args, try_placeholder, unit_call_placeholder,
+};
 
 pub fn check(ast: &ast::Dict) -> Result<()> {
+    valid_idents(ast)?;
+    no_duplicate_locales(ast)?;
+    valid_base_lang(ast)?;
+    valid_region_groups(ast)?;
+    no_duplicate_units(&ast.trans_units, &ast.modules)?;
+    no_flatten_collisions(&ast.trans_units, &ast.modules)?;
+    no_reserved_unit_names(ast)?;
+    no_generated_name_clashes(&ast.trans_units, &ast.modules)?;
+    no_duplicate_export_keys(ast)?;
+    no_translation_key_collisions(ast)?;
+    valid_context(ast)?;
     custom_return_implies_raw_body(ast)?;
+    const_units_are_simple(ast)?;
+    async_units_are_uncached(ast)?;
+    positional_placeholders_match_params(ast)?;
+    placeholder_indices_in_range(ast)?;
+    known_placeholder_sigils(ast)?;
+    try_placeholders_in_result_units(ast)?;
+    valid_unit_calls(&ast.trans_units, &ast.modules)?;
+    translations_within_max_len(ast)?;
+    warn_unused_params(ast);
+    warn_inconsistent_placeholders(ast);
+    warn_todo_markers(ast);
+    valid_format_as(ast)?;
+    if ast.config.strict {
+        no_wildcards(ast)?;
+    }
+    if ast.config.strict_patterns {
+        no_ambiguous_bindings(ast)?;
+    }
 
     Ok(())
 }
 
 /// Translation unit arms can have string or raw bodies. The latter is raw
 /// Rust code. Since string bodies always produce a `String` it doesn't make
-/// sense to use those in combination with custom return types.
+/// sense to use those in combination with custom return types. The exception
+/// are units returning `Result<String, _>`, whose string bodies are wrapped in
+/// `Ok`.
 ///
 /// We make sure those are never used in combination by checking it here.
 fn custom_return_implies_raw_body(ast: &ast::Dict) -> Result<()> {
-    for unit in ast.units().filter(|unit| unit.return_type.is_some()) {
+    let is_custom = |unit: &&ast::TransUnit| {
+        unit.return_type.as_ref().map(|ty| !ty.is_string_result()).unwrap_or(false)
+    };
+    for unit in ast.units().filter(is_custom) {
         let not_raw = unit.body.arms.iter()
             .find(|arm| !arm.body.obj.is_raw_block());
 
@@ -30,3 +69,1045 @@ fn custom_return_implies_raw_body(ast: &ast::Dict) -> Result<()> {
 
     Ok(())
 }
+
+/// All names in the locale definition and all unit names end up as Rust
+/// identifiers in the generated code. Rust allows non-ASCII identifiers (with
+/// `#![feature(non_ascii_idents)]`), so names written in their own script are
+/// fine. But we check that the names are valid identifiers at all, to emit a
+/// helpful error instead of confusing errors in the generated code.
+fn valid_idents(ast: &ast::Dict) -> Result<()> {
+    let check_ident = |ident: Ident, kind: &str| {
+        if is_valid_ident(&ident) {
+            Ok(())
+        } else if is_keyword(&ident) {
+            err!(ident.diag_span(), "'{}' is not a valid {} name: it's a Rust keyword", ident, kind)
+        } else {
+            err!(
+                ident.diag_span(),
+                "'{}' is not a valid {} name: it has to be a valid Rust identifier",
+                ident,
+                kind
+            )
+        }
+    };
+
+    for lang in &ast.locale_def.langs {
+        check_ident(lang.name, "language")?;
+        for region in &lang.regions {
+            check_ident(region.name, "region")?;
+        }
+        for group in &lang.groups {
+            check_ident(group.name, "region group")?;
+        }
+    }
+
+    for unit in ast.units() {
+        check_ident(unit.name, "unit")?;
+        if unit.context.is_some() {
+            check_ident(unit.method_name(), "context-qualified unit")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Units marked with `#[async]` return a boxed future, which can't be cloned
+/// and is consumed when awaited. So they can't be `const` or `#[cache]`d.
+fn async_units_are_uncached(ast: &ast::Dict) -> Result<()> {
+    for unit in ast.units().filter(|unit| unit.is_async) {
+        if unit.is_const {
+            return err!(
+                unit.name.diag_span(),
+                "translation unit '{}' is marked as `const` and `#[async]`",
+                unit.name
+            );
+        }
+
+        if unit.cache {
+            return Err(unit.name.diag_span()
+                .error(format!(
+                    "translation unit '{}' is marked as `#[async]` and `#[cache]`",
+                    unit.name,
+                ))
+                .note("futures can't be cloned, so their results can't be cached")
+                .help("cache the result of the future in your own code"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Units marked with `#[mauzi(const)]` are generated as `const fn` returning
+/// a `&'static str`. This only works if the unit has no parameters, no custom
+/// return type, no guards and only string bodies (the absence of placeholders
+/// is checked while generating the code).
+fn const_units_are_simple(ast: &ast::Dict) -> Result<()> {
+    for unit in ast.units().filter(|unit| unit.is_const) {
+        if unit.params.as_ref().map(|p| !p.is_empty()).unwrap_or(false) {
+            return err!(
+                unit.name.diag_span(),
+                "translation unit '{}' is marked as `const`, but has parameters",
+                unit.name
+            );
+        }
+
+        if unit.return_type.is_some() {
+            return err!(
+                unit.name.diag_span(),
+                "translation unit '{}' is marked as `const`, but has a custom return type",
+                unit.name
+            );
+        }
+
+        if unit.cache {
+            return Err(unit.name.diag_span()
+                .error(format!(
+                    "translation unit '{}' is marked as `const` and `#[cache]`",
+                    unit.name,
+                ))
+                .help("remove `#[cache]`: `const` units are evaluated at compile time"));
+        }
+
+        let raw = unit.body.arms.iter().find(|arm| arm.body.obj.is_raw_block());
+        if let Some(raw) = raw {
+            return err!(
+                raw.body.span,
+                "translation unit '{}' is marked as `const`, but its arm '{}' has a raw \
+                    body (only string bodies are allowed)",
+                unit.name,
+                raw.pattern
+            );
+        }
+
+        let guarded = unit.body.arms.iter().find(|arm| arm.guard.is_some());
+        if let Some(guarded) = guarded {
+            return err!(
+                guarded.guard.as_ref().unwrap().span,
+                "translation unit '{}' is marked as `const`, but its arm '{}' has a guard",
+                unit.name,
+                guarded.pattern
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// `#[format_as = "..."]` on an arm has to name a language of the locale
+/// definition. Since it only affects placeholders, it doesn't make sense on
+/// raw bodies.
+fn valid_format_as(ast: &ast::Dict) -> Result<()> {
+    for unit in ast.units() {
+        for arm in &unit.body.arms {
+            let lang = match arm.format_as {
+                Some(ref lang) => lang,
+                None => continue,
+            };
+
+            if ast.locale_def.get_lang(&lang.obj).is_none() {
+                return err!(lang.span, "'{}' is not a language of this dictionary", lang.obj);
+            }
+
+            if arm.body.obj.is_raw_block() {
+                return err!(
+                    lang.span,
+                    "`format_as` has no effect on raw bodies; use `Locale::format_number` \
+                        instead"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Translation units become methods of the generated `Dict` types, which
+/// already have a few methods. Units with those names would clash. In dynamic
+/// mode, `get`, `get_str` and `render_key` are reserved as well, and in
+/// global mode the names of the functions for the global dictionary.
+fn no_reserved_unit_names(ast: &ast::Dict) -> Result<()> {
+    const RESERVED: &[&str] = &[
+        "as_map", "get_by_key", "get_str_by_key", "new", "new_lang", "render", "render_by_key",
+    ];
+
+    let reserved = ast.units().find(|unit| {
+        let name = unit.method_name();
+        RESERVED.contains(&name.as_str())
+            || (ast.config.dynamic && ["get", "get_str", "render_key"].contains(&name.as_str()))
+            || (ast.config.global && ["global", "init_global"].contains(&name.as_str()))
+    });
+    if let Some(unit) = reserved {
+        return err!(
+            unit.name.diag_span(),
+            "'{}' is a reserved name and can't be used as translation unit name",
+            unit.method_name()
+        );
+    }
+
+    Ok(())
+}
+
+/// Besides its own method, a unit gets a few more methods, e.g. `try_foo()`
+/// and `foo_fmt()` for a unit `foo`. A unit with one of those names in the
+/// same module would clash with them.
+fn no_generated_name_clashes(units: &[ast::TransUnit], modules: &[ast::Mod]) -> Result<()> {
+    for unit in units {
+        let name = unit.method_name();
+        let has_params = unit.params.as_ref().map(|p| !p.is_empty()).unwrap_or(false);
+        let returns_string = unit.return_type.is_none() && !unit.is_async;
+
+        let mut generated = vec![format!("{}_available_locales", name)];
+        if !unit.is_const {
+            generated.push(format!("try_{}", name));
+        }
+        if returns_string {
+            generated.push(format!("{}_fmt", name));
+        }
+        if returns_string && !has_params {
+            generated.push(format!("{}_all", name));
+        }
+        if has_params && !unit.is_async {
+            generated.push(format!("{}_fn", name));
+        }
+
+        let clash = units.iter().find(|other| generated.contains(&other.method_name().to_string()));
+        if let Some(other) = clash {
+            return Err(other.name.diag_span()
+                .error(format!(
+                    "translation unit '{}' clashes with a method generated for unit '{}'",
+                    other.method_name(),
+                    name,
+                ))
+                .note(format!(
+                    "the methods generated for '{}' are `{}()` and `{}()`",
+                    name,
+                    name,
+                    generated.join("()`, `"),
+                ))
+                .help(format!("rename '{}'", other.method_name())));
+        }
+    }
+
+    for module in modules {
+        no_generated_name_clashes(&module.trans_units, &module.modules)?;
+    }
+
+    Ok(())
+}
+
+/// Each language and each region of a language has to be defined only once.
+/// Otherwise we would generate enums with duplicate variants. The error is
+/// reported at the second definition.
+fn no_duplicate_locales(ast: &ast::Dict) -> Result<()> {
+    let langs = &ast.locale_def.langs;
+    for (i, lang) in langs.iter().enumerate() {
+        if langs[..i].iter().any(|other| other.name.as_str() == lang.name.as_str()) {
+            return err!(
+                lang.name.diag_span(),
+                "language '{}' is defined multiple times",
+                lang.name
+            );
+        }
+
+        let regions = &lang.regions;
+        for (j, region) in regions.iter().enumerate() {
+            if regions[..j].iter().any(|other| other.name.as_str() == region.name.as_str()) {
+                return err!(
+                    region.name.diag_span(),
+                    "region '{}' of language '{}' is defined multiple times",
+                    region.name,
+                    lang.name
+                );
+            }
+
+            // Numeric codes have to identify the region unambiguously.
+            if let Some(ref code) = region.numeric_code {
+                let other = regions[..j].iter().find(|other| {
+                    other.numeric_code.as_ref().map(|c| c.obj) == Some(code.obj)
+                });
+                if let Some(other) = other {
+                    return Err(code.span
+                        .error(format!(
+                            "numeric code {} of region '{}' is already used by region '{}'",
+                            code.obj,
+                            region.name,
+                            other.name,
+                        ))
+                        .note(format!("in language '{}'", lang.name)));
+                }
+            }
+        }
+
+        // The region type of a language with its own regions (`EnRegion`)
+        // would clash with the one of a region set with the same name.
+        let has_own_regions = lang.has_regions() && lang.region_set.is_none();
+        let clashing_set = langs.iter()
+            .filter_map(|other| other.region_set)
+            .find(|set| has_own_regions && set.as_str() == lang.name.as_str());
+        if let Some(set) = clashing_set {
+            return Err(set.diag_span()
+                .error(format!("region set '{}' has the name of a language", set))
+                .note(format!("both would have the region type `{}Region`", set))
+                .help("rename the region set"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Region groups are used like regions in arm patterns, so their names must
+/// not clash with regions or other groups of the same language. Each group
+/// has to consist of at least one region of its language.
+fn valid_region_groups(ast: &ast::Dict) -> Result<()> {
+    for lang in &ast.locale_def.langs {
+        for (i, group) in lang.groups.iter().enumerate() {
+            let name = group.name.as_str();
+            let is_duplicate = lang.contains_region(name)
+                || lang.groups[..i].iter().any(|other| other.name.as_str() == name);
+            if is_duplicate {
+                return err!(
+                    group.name.diag_span(),
+                    "region group '{}' of language '{}' clashes with another region or group",
+                    group.name,
+                    lang.name
+                );
+            }
+
+            if group.regions.is_empty() {
+                return err!(group.name.diag_span(), "region group '{}' is empty", group.name);
+            }
+
+            let unknown = group.regions.iter().find(|r| !lang.contains_region(r));
+            if let Some(region) = unknown {
+                return err!(
+                    region.diag_span(),
+                    "'{}' in region group '{}' is not a region of language '{}'",
+                    region,
+                    group.name,
+                    lang.name
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// In strict mode, all locales have to be handled explicitly by each unit.
+/// Wildcard patterns would silently handle locales added in the future, so
+/// we forbid them. Whether all locales are handled is checked in `gen`.
+fn no_wildcards(ast: &ast::Dict) -> Result<()> {
+    let locale = &ast.locale_def;
+    for unit in ast.units() {
+        for arm in &unit.body.arms {
+            let is_wildcard = match arm.pattern {
+                ast::ArmPattern::Underscore(_) => true,
+                ast::ArmPattern::Lang(lang) => locale.get_lang(&lang).is_none(),
+                ast::ArmPattern::WithRegion { lang, .. } => locale.get_lang(&lang).is_none(),
+            };
+
+            if is_wildcard {
+                return err!(
+                    arm.pattern.span(),
+                    "wildcard pattern '{}' in unit '{}' is not allowed in strict mode",
+                    arm.pattern,
+                    unit.name
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The units and submodules of modules marked with `#[flatten]` are moved
+/// into the parent module. Their names must not collide with the names
+/// already in the parent (including other flattened modules).
+fn no_flatten_collisions(units: &[ast::TransUnit], modules: &[ast::Mod]) -> Result<()> {
+    // Returns all units and submodules which end up in the parent of the
+    // given flattened module.
+    fn lifted(module: &ast::Mod) -> (Vec<&ast::TransUnit>, Vec<&ast::Mod>) {
+        let mut units = module.trans_units.iter().collect::<Vec<_>>();
+        let mut modules = Vec::new();
+        for sub in &module.modules {
+            if sub.flatten {
+                let (sub_units, sub_modules) = lifted(sub);
+                units.extend(sub_units);
+                modules.extend(sub_modules);
+            } else {
+                modules.push(sub);
+            }
+        }
+
+        (units, modules)
+    }
+
+    let mut unit_names = units.iter().map(|u| u.method_name()).collect::<Vec<_>>();
+    let mut module_names = modules.iter()
+        .filter(|m| !m.flatten)
+        .map(|m| m.name.to_string())
+        .collect::<Vec<_>>();
+
+    for module in modules.iter().filter(|m| m.flatten) {
+        let (lifted_units, lifted_modules) = lifted(module);
+        for unit in lifted_units {
+            let name = unit.method_name();
+            if unit_names.iter().any(|n| n.as_str() == name.as_str()) {
+                return err!(
+                    module.name.diag_span(),
+                    "unit '{}' of flattened module '{}' collides with another unit in the \
+                        parent module",
+                    name,
+                    module.name
+                );
+            }
+            unit_names.push(name);
+        }
+        for sub in lifted_modules {
+            if module_names.contains(&sub.name.to_string()) {
+                return err!(
+                    module.name.diag_span(),
+                    "module '{}' of flattened module '{}' collides with another module in the \
+                        parent module",
+                    sub.name,
+                    module.name
+                );
+            }
+            module_names.push(sub.name.to_string());
+        }
+    }
+
+    for module in modules {
+        no_flatten_collisions(&module.trans_units, &module.modules)?;
+    }
+
+    Ok(())
+}
+
+/// Positional placeholders (`{}`) refer to the parameters of the unit in
+/// order. If an arm uses them, it has to use exactly one per parameter.
+/// Otherwise, the generated `format!()` would get too few or too many
+/// arguments, resulting in a confusing error inside of the macro output.
+fn positional_placeholders_match_params(ast: &ast::Dict) -> Result<()> {
+    for (unit, arm, s) in string_arms(ast.units()) {
+        let param_count = unit.params.as_ref().map(|p| p.len()).unwrap_or(0);
+        let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+        let positional = args.iter().filter(|arg| arg.trim().is_empty()).count();
+        if positional > 0 && positional != param_count {
+            return err!(
+                arm.body.span,
+                "arm '{}' of unit '{}' has {} positional placeholder(s), but the unit has \
+                    {} parameter(s)",
+                arm.pattern,
+                unit.name,
+                positional,
+                param_count
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// A `@` at the start of a placeholder introduces a sigil: `@env:NAME` or
+/// `@unit(args)`.
+fn known_placeholder_sigils(ast: &ast::Dict) -> Result<()> {
+    for (unit, arm, s) in string_arms(ast.units()) {
+        let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+        let unknown = args.iter().find(|arg| {
+            arg.trim().starts_with('@')
+                && env_placeholder(arg).is_none()
+                && unit_call_placeholder(arg).is_none()
+        });
+        if let Some(arg) = unknown {
+            return Err(arm.body.span
+                .error(format!(
+                    "unknown sigil in placeholder '{{{}}}' of unit '{}'",
+                    arg,
+                    unit.name,
+                ))
+                .help("the sigils in placeholders are `@env:NAME` and `@unit(args)`"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Placeholders ending with the try operator (`{value?}`) can only be used in
+/// units returning `Result<String, _>`.
+fn try_placeholders_in_result_units(ast: &ast::Dict) -> Result<()> {
+    let units = ast.units().filter(|unit| {
+        !unit.return_type.as_ref().map(|ty| ty.is_string_result()).unwrap_or(false)
+    });
+    for (unit, arm, s) in string_arms(units) {
+        let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+        if let Some(arg) = args.iter().find(|arg| try_placeholder(arg)) {
+            return Err(arm.body.span
+                .error(format!(
+                    "placeholder '{{{}}}' of unit '{}' uses the `?` operator, but the unit \
+                        doesn't return `Result<String, _>`",
+                    arg,
+                    unit.name,
+                ))
+                .help("for a fallback text of an `Option`, write the text after `?`, \
+                    e.g. `{name?someone}`"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Placeholders like `{@greet(name)}` call another unit of the same module
+/// (including the units of flattened submodules and, for units of a flattened
+/// module, those of the parent), which has to exist and take as many
+/// parameters as arguments are given. A unit can't call itself.
+fn valid_unit_calls(units: &[ast::TransUnit], modules: &[ast::Mod]) -> Result<()> {
+    // Collects the units and the (not flattened) submodules ending up in the
+    // same module as `units`.
+    fn scope<'a>(
+        units: &'a [ast::TransUnit],
+        modules: &'a [ast::Mod],
+        out_units: &mut Vec<&'a ast::TransUnit>,
+        out_modules: &mut Vec<&'a ast::Mod>,
+    ) {
+        out_units.extend(units);
+        for module in modules {
+            if module.flatten {
+                scope(&module.trans_units, &module.modules, out_units, out_modules);
+            } else {
+                out_modules.push(module);
+            }
+        }
+    }
+
+    let mut siblings = Vec::new();
+    let mut submodules = Vec::new();
+    scope(units, modules, &mut siblings, &mut submodules);
+
+    for (unit, arm, s) in string_arms(siblings.iter().cloned()) {
+        let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+        for arg in &args {
+            let expr = fallback_placeholder(arg).map(|(expr, _)| expr).unwrap_or(arg);
+            let (name, call_args) = match unit_call_placeholder(expr) {
+                Some(call) => call,
+                None => continue,
+            };
+
+            if name == unit.method_name().as_str() {
+                return Err(arm.body.span
+                    .error(format!("translation unit '{}' calls itself", unit.name))
+                    .note(format!("in the placeholder '{{{}}}'", arg)));
+            }
+
+            let callee = match siblings.iter().find(|u| u.method_name().as_str() == name) {
+                Some(callee) => callee,
+                None => {
+                    return Err(arm.body.span
+                        .error(format!(
+                            "placeholder '{{{}}}' of unit '{}' calls the unknown unit '{}'",
+                            arg,
+                            unit.name,
+                            name,
+                        ))
+                        .help("only units of the same module can be called"));
+                }
+            };
+
+            let expected = callee.params.as_ref().map(|p| p.len()).unwrap_or(0);
+            let given = split_call_args(call_args).len();
+            if given != expected {
+                return err!(
+                    arm.body.span,
+                    "placeholder '{{{}}}' of unit '{}' passes {} argument(s) to unit '{}', \
+                        which takes {} parameter(s)",
+                    arg,
+                    unit.name,
+                    given,
+                    name,
+                    expected
+                );
+            }
+        }
+    }
+
+    // The units of flattened modules were checked with the parent's scope
+    // above, so only the real submodules are left.
+    for module in submodules {
+        valid_unit_calls(&module.trans_units, &module.modules)?;
+    }
+
+    Ok(())
+}
+
+/// Placeholders like `{1}` refer to the parameter with that index, which has
+/// to exist. The index can be combined with `:n` and `?fallback`.
+fn placeholder_indices_in_range(ast: &ast::Dict) -> Result<()> {
+    for (unit, arm, s) in string_arms(ast.units()) {
+        let param_count = unit.params.as_ref().map(|p| p.len()).unwrap_or(0);
+        let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+        for arg in &args {
+            let expr = fallback_placeholder(arg).map(|(expr, _)| expr).unwrap_or(arg).trim();
+            let expr = number_placeholder(expr).unwrap_or(expr);
+            let index = match expr.trim().parse::<usize>() {
+                Ok(index) => index,
+                Err(_) => continue,
+            };
+
+            if index >= param_count {
+                return err!(
+                    arm.body.span,
+                    "placeholder '{{{}}}' of unit '{}' refers to parameter {}, but the unit \
+                        only has {} parameter(s) (indices start at 0)",
+                    arg,
+                    unit.name,
+                    index,
+                    param_count
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Warns about parameters which are not used by any arm of their unit. What
+/// raw bodies do with the parameters is unknown, so only units with string
+/// bodies only are checked. Guards count as usage, too.
+fn warn_unused_params(ast: &ast::Dict) {
+    // Marks all parameters whose name appears as a word in `s` as used.
+    fn mark_words(s: &str, params: &[ast::UnitParam], used: &mut [bool]) {
+        for word in s.split(|c: char| !(c == '_' || c.is_alphanumeric())) {
+            for (i, param) in params.iter().enumerate() {
+                if param.name.as_str() == word {
+                    used[i] = true;
+                }
+            }
+        }
+    }
+
+    for unit in ast.units() {
+        let params = match unit.params {
+            Some(ref params) if !params.is_empty() => params,
+            _ => continue,
+        };
+        if unit.body.arms.iter().any(|arm| arm.body.obj.is_raw_block()) {
+            continue;
+        }
+
+        let mut used = vec![false; params.len()];
+        for arm in &unit.body.arms {
+            if let Some(ref guard) = arm.guard {
+                mark_words(&guard.obj.to_string(), params, &mut used);
+            }
+
+            let s = match arm.body.obj {
+                ast::ArmBody::Str(ref s) => s,
+                ast::ArmBody::Raw(_) => unreachable!(),
+            };
+            let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+            for arg in args.iter().filter(|arg| env_placeholder(arg).is_none()) {
+                let expr = fallback_placeholder(arg).map(|(expr, _)| expr).unwrap_or(arg).trim();
+                let expr = number_placeholder(expr).unwrap_or(expr);
+
+                // Positional placeholders use all parameters, which was
+                // already checked above.
+                if expr.trim().is_empty() {
+                    used = vec![true; params.len()];
+                } else if let Ok(index) = expr.trim().parse::<usize>() {
+                    used[index] = true;
+                } else {
+                    mark_words(expr, params, &mut used);
+                }
+            }
+        }
+
+        for (param, _) in params.iter().zip(used).filter(|&(_, used)| !used) {
+            param.name.diag_span()
+                .warning(format!(
+                    "parameter '{}' of translation unit '{}' is never used",
+                    param.name,
+                    unit.name,
+                ))
+                .help(format!("remove it or use it as placeholder, e.g. `{{{}}}`", param.name))
+                .emit();
+        }
+    }
+}
+
+/// Warns if an arm doesn't use all placeholders which other arms of the same
+/// unit use, e.g. if `En` uses `{name}` and `{count}`, but `De` only
+/// `{name}`. That's most likely a mistake in the translation. Arms with a
+/// guard or count condition are special cases, so they are not compared.
+fn warn_inconsistent_placeholders(ast: &ast::Dict) {
+    for unit in ast.units() {
+        let params = unit.params.iter()
+            .flat_map(|v| v)
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+
+        // The placeholders used by each arm, normalized to the expression
+        // (without `:n` and fallback). Placeholders referring to parameters by
+        // position or index are replaced by the parameter's name.
+        let arms = unit.body.arms.iter()
+            .filter(|arm| arm.guard.is_none())
+            .filter_map(|arm| match arm.body.obj {
+                ast::ArmBody::Str(ref s) => Some((arm, s)),
+                ast::ArmBody::Raw(_) => None,
+            })
+            .map(|(arm, s)| {
+                let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+                let mut positional = params.iter();
+                let mut used = args.iter()
+                    .filter(|arg| env_placeholder(arg).is_none())
+                    .map(|arg| {
+                        let expr = fallback_placeholder(arg).map(|(e, _)| e).unwrap_or(arg);
+                        let expr = expr.trim().trim_right_matches(":n").trim();
+
+                        // The separator of `{list | ", "}` may differ.
+                        let expr = match expr.rfind('|') {
+                            Some(pos) if expr[pos + 1..].trim().starts_with('"') => {
+                                expr[..pos].trim()
+                            }
+                            _ => expr,
+                        };
+                        if expr.is_empty() {
+                            positional.next().map(|p| p.to_string()).unwrap_or_default()
+                        } else if let Ok(index) = expr.parse::<usize>() {
+                            params.get(index).map(|p| p.to_string()).unwrap_or_default()
+                        } else {
+                            expr.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                used.sort();
+                used.dedup();
+                (arm, used)
+            })
+            .collect::<Vec<_>>();
+
+        for &(arm, ref used) in &arms {
+            let mut missing = arms.iter()
+                .flat_map(|&(_, ref other)| other)
+                .filter(|p| !used.contains(p))
+                .map(|p| format!("{{{}}}", p))
+                .collect::<Vec<_>>();
+            missing.sort();
+            missing.dedup();
+
+            if !missing.is_empty() {
+                arm.body.span
+                    .warning(format!(
+                        "arm '{}' of translation unit '{}' doesn't use all placeholders of the \
+                            other arms",
+                        arm.pattern,
+                        unit.name,
+                    ))
+                    .note(format!("missing: {}", missing.join(", ")))
+                    .emit();
+            }
+        }
+    }
+}
+
+/// With `#[mauzi(warn_todo)]`, string arms containing a marker like `TODO`
+/// are reported, so that unfinished translations don't get shipped
+/// unnoticed.
+fn warn_todo_markers(ast: &ast::Dict) {
+    if ast.config.todo_markers.is_empty() {
+        return;
+    }
+
+    for (unit, arm, s) in string_arms(ast.units()) {
+        let found = ast.config.todo_markers.iter().find(|marker| s.contains(marker.as_str()));
+        if let Some(marker) = found {
+            arm.body.span
+                .warning(format!(
+                    "arm '{}' of translation unit '{}' contains the marker '{}'",
+                    arm.pattern,
+                    unit.name,
+                    marker,
+                ))
+                .help("finish the translation or remove the arm to use the fallback")
+                .emit();
+        }
+    }
+}
+
+/// Units with `#[max_len = N]` have to fit into a limited space in the UI.
+/// Thus, the text of every string arm may have at most `N` characters.
+/// Placeholders are not counted, since their length is only known at
+/// runtime.
+fn translations_within_max_len(ast: &ast::Dict) -> Result<()> {
+    for (unit, arm, s) in string_arms(ast.units()) {
+        let max_len = match unit.max_len {
+            Some(ref max_len) => max_len.obj,
+            None => continue,
+        };
+
+        let (format_str, _) = split_format_str(s, unit.placeholders.as_ref());
+        let text = format_str.replace("{}", "").replace("{{", "{").replace("}}", "}");
+        let len = text.chars().count() as u64;
+        if len > max_len {
+            let msg = format!(
+                "translation of unit '{}' for '{}' is {} characters long, but at most {} \
+                    are allowed",
+                unit.name,
+                arm.pattern,
+                len,
+                max_len,
+            );
+            return Err(arm.body.span.error(msg)
+                .span_note(unit.max_len.as_ref().unwrap().span, "the limit is set here")
+                .note("placeholders are not counted"));
+        }
+    }
+
+    Ok(())
+}
+
+/// An identifier in an arm pattern which isn't a language (or a region of the
+/// language) is a variable binding. Thus a typo like `Eng` silently becomes a
+/// catch-all pattern. With `#[mauzi(strict_patterns)]`, bindings have to
+/// start with a lowercase letter or `_`, so that capitalized names always
+/// have to refer to a language or region.
+fn no_ambiguous_bindings(ast: &ast::Dict) -> Result<()> {
+    let locale = &ast.locale_def;
+    for unit in ast.units() {
+        for arm in &unit.body.arms {
+            let (name, what) = match arm.pattern {
+                ast::ArmPattern::Underscore(_) => continue,
+                ast::ArmPattern::Lang(lang) => {
+                    if locale.get_lang(&lang).is_some() {
+                        continue;
+                    }
+                    (lang, "language")
+                }
+                ast::ArmPattern::WithRegion { lang, region } => {
+                    match locale.get_lang(&lang) {
+                        Some(l) if l.contains_region(&region) => continue,
+                        Some(l) if l.get_group(&region).is_some() => continue,
+                        Some(_) => (region, "region"),
+                        None if !is_binding_name(&lang) => (lang, "language"),
+                        None => (region, "region"),
+                    }
+                }
+            };
+
+            if !is_binding_name(&name) {
+                let msg = format!("unknown {} `{}` in unit '{}'", what, name, unit.name);
+                return Err(name.diag_span().error(msg)
+                    .help(format!("to bind the {} to a variable, use a lowercase name", what))
+                    .note("capitalized bindings are forbidden by `strict_patterns`"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The base language declared via `base X;` has to be one of the languages.
+fn valid_base_lang(ast: &ast::Dict) -> Result<()> {
+    let locale = &ast.locale_def;
+    match locale.base {
+        Some(base) if locale.get_lang(&base).is_none() => {
+            err!(base.diag_span(), "base language `{}` is not defined in `Locale`", base)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The context (`context(user: Rc<User>);`) is stored in fields of the
+/// `Dict` types and bound to variables in all units. So its names must not
+/// clash with the `locale` field, submodules, parameters or other context
+/// values. The global dictionary is created without arguments, so it can't
+/// be used with a context. In hash mode, the `Dict` types derive `Hash` and
+/// `Eq`, which the context types usually don't implement, so that's not
+/// allowed either.
+fn valid_context(ast: &ast::Dict) -> Result<()> {
+    let first = match ast.context.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+
+    if ast.config.global {
+        return Err(first.name.diag_span()
+            .error("a dictionary with context can't be used with `#[mauzi(global)]`")
+            .note("the global dictionary is created from the locale only"));
+    }
+    if ast.config.hash {
+        return Err(first.name.diag_span()
+            .error("a dictionary with context can't be used with `#[mauzi(hash)]`")
+            .note("in hash mode, the `Dict` types derive `Hash` and `Eq`, which would require \
+                the context types to implement those, too"));
+    }
+
+    let mut module_names = Vec::new();
+    let mut stack = ast.modules.iter().collect::<Vec<_>>();
+    while let Some(module) = stack.pop() {
+        module_names.push(module.name.as_str().to_string());
+        stack.extend(&module.modules);
+    }
+
+    for (i, param) in ast.context.iter().enumerate() {
+        let name = param.name.as_str();
+        if ast.context[..i].iter().any(|other| other.name.as_str() == name) {
+            return err!(
+                param.name.diag_span(),
+                "context value '{}' is defined multiple times",
+                name
+            );
+        }
+        if name == "locale" || module_names.iter().any(|m| m == name) {
+            return err!(
+                param.name.diag_span(),
+                "context value '{}' has the name of a field of the `Dict` types",
+                name
+            );
+        }
+    }
+
+    for unit in ast.units() {
+        let clash = unit.params.iter().flat_map(|v| v).find(|p| {
+            ast.context.iter().any(|c| c.name.as_str() == p.name.as_str())
+        });
+        if let Some(param) = clash {
+            return Err(param.name.diag_span()
+                .error(format!(
+                    "parameter '{}' of translation unit '{}' has the name of a context value",
+                    param.name,
+                    unit.name,
+                ))
+                .help("the context value can be used without declaring it as parameter"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Keys given via `#[key = "..."]` replace the full key of a unit in the
+/// exports, so they must not clash with the key of any other unit.
+fn no_duplicate_export_keys(ast: &ast::Dict) -> Result<()> {
+    let keys = ast.units_with_keys().into_iter()
+        .map(|(key, unit)| match unit.key {
+            Some(ref custom) => (custom.obj.clone(), unit),
+            None => (key, unit),
+        })
+        .collect::<Vec<_>>();
+
+    for (i, &(ref key, unit)) in keys.iter().enumerate() {
+        let custom = match unit.key {
+            Some(ref custom) => custom,
+            None => continue,
+        };
+
+        let other = keys.iter().enumerate()
+            .find(|&(j, &(ref other_key, _))| i != j && other_key == key);
+        if let Some((_, &(_, other))) = other {
+            return Err(custom.span
+                .error(format!(
+                    "key '{}' of translation unit '{}' is already used by translation unit '{}'",
+                    key,
+                    unit.name,
+                    other.name,
+                ))
+                .note("the key identifies the unit in the exports"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Each unit gets a variant of `TranslationKey` named after its key in camel
+/// case, e.g. `FooBar` for `foo.bar`. But `foo_bar` becomes `FooBar` as well,
+/// which would result in duplicate variants. The keys are the ones after
+/// flattening the modules marked with `#[flatten]`.
+fn no_translation_key_collisions(ast: &ast::Dict) -> Result<()> {
+    let mut flattened = ast.clone();
+    flattened.flatten_modules();
+
+    let variants = flattened.units_with_keys().into_iter()
+        .map(|(key, unit)| (camel_case(&key), key, unit))
+        .collect::<Vec<_>>();
+    for (i, &(ref variant, ref key, unit)) in variants.iter().enumerate() {
+        let other = variants[..i].iter().find(|&&(ref other, _, _)| other == variant);
+        if let Some(&(_, ref other_key, _)) = other {
+            return Err(unit.name.diag_span()
+                .error(format!(
+                    "the key '{}' of translation unit '{}' collides with the key '{}'",
+                    key,
+                    unit.name,
+                    other_key,
+                ))
+                .note(format!("both keys would be `TranslationKey::{}`", variant))
+                .help("rename one of the units or modules"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Units in the same module need to have different methods. Units with the
+/// same name are distinguished by their context, but the context is part of
+/// the method name (`method_name()`), so e.g. a unit `post` with context
+/// `verb` and a unit `post_verb` still clash.
+fn no_duplicate_units(units: &[ast::TransUnit], modules: &[ast::Mod]) -> Result<()> {
+    let describe = |unit: &ast::TransUnit| match unit.context {
+        Some(ref c) => format!("'{}' with context '{}'", unit.name, c.obj),
+        None => format!("'{}'", unit.name),
+    };
+
+    for (i, unit) in units.iter().enumerate() {
+        let method_name = unit.method_name();
+        let other = units[..i].iter()
+            .find(|other| other.method_name().as_str() == method_name.as_str());
+        let other = match other {
+            Some(other) => other,
+            None => continue,
+        };
+
+        let e = if describe(other) != describe(unit) {
+            unit.name.diag_span()
+                .error(format!(
+                    "translation units {} and {} both generate the method '{}'",
+                    describe(other),
+                    describe(unit),
+                    method_name,
+                ))
+                .help("rename one of the units")
+        } else {
+            let e = unit.name.diag_span()
+                .error(format!("translation unit {} is defined multiple times", describe(unit)));
+            match unit.context {
+                Some(_) => e,
+                None => e.help("use `#[context = \"...\"]` to distinguish units with the same \
+                    name"),
+            }
+        };
+
+        return Err(e.span_note(other.name.diag_span(), "the other unit is defined here"));
+    }
+
+    for module in modules {
+        no_duplicate_units(&module.trans_units, &module.modules)?;
+    }
+
+    Ok(())
+}
+
+/// A string arm together with its unit and the string of its body.
+type StringArm<'a> = (&'a ast::TransUnit, &'a ast::UnitArm, &'a str);
+
+/// Iterates over all arms with a string body of the given units. Arms with a
+/// raw body are skipped.
+fn string_arms<'a, I>(units: I) -> Box<Iterator<Item = StringArm<'a>> + 'a>
+where
+    I: IntoIterator<Item = &'a ast::TransUnit>,
+    I::IntoIter: 'a,
+{
+    Box::new(units.into_iter().flat_map(|unit| {
+        unit.body.arms.iter().filter_map(move |arm| match arm.body.obj {
+            ast::ArmBody::Str(ref s) => Some((unit, arm, s.as_str())),
+            ast::ArmBody::Raw(_) => None,
+        })
+    }))
+}
@@ -0,0 +1,132 @@
+//! A minimal CSV parser, used to load translation units from spreadsheets.
+//!
+//! Fields are separated by `,` and records by newlines. Fields can be quoted
+//! with `"`; quoted fields may contain commas and newlines, and `""` inside a
+//! quoted field is a literal quote. Empty lines are skipped.
+
+/// Parses the given CSV content into a list of records.
+///
+/// On error, a message describing the problem (including the line) is
+/// returned.
+pub fn parse(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+
+    // Whether the current field is quoted and we are still inside the quotes.
+    let mut in_quotes = false;
+
+    // Whether the current field was quoted. After the closing quote, only a
+    // separator is allowed.
+    let mut was_quoted = false;
+
+    let mut line = 1;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            ',' => {
+                record.push(field);
+                field = String::new();
+                was_quoted = false;
+            }
+            '\n' => {
+                // Skip empty lines
+                if !record.is_empty() || !field.is_empty() || was_quoted {
+                    record.push(field);
+                    records.push(record);
+                }
+                record = Vec::new();
+                field = String::new();
+                was_quoted = false;
+            }
+            _ if was_quoted => {
+                return Err(format!("unexpected character '{}' after quoted field in line {}",
+                    c, line));
+            }
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                was_quoted = true;
+            }
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("unclosed quoted field at the end of the file".into());
+    }
+
+    // The last record might not be terminated by a newline.
+    if !record.is_empty() || !field.is_empty() || was_quoted {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(records: &[&[&str]]) -> Vec<Vec<String>> {
+        records.iter().map(|r| r.iter().map(|f| f.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn simple() {
+        let expected = strings(&[&["key", "De", "En"], &["cat", "Katze", "cat"]]);
+        assert_eq!(parse("key,De,En\ncat,Katze,cat\n"), Ok(expected.clone()));
+        assert_eq!(parse("key,De,En\n\ncat,Katze,cat"), Ok(expected));
+        assert_eq!(parse("a,,b"), Ok(strings(&[&["a", "", "b"]])));
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(parse("\"a, b\",c"), Ok(strings(&[&["a, b", "c"]])));
+        assert_eq!(parse("\"\",x"), Ok(strings(&[&["", "x"]])));
+        assert_eq!(parse("\"\""), Ok(strings(&[&[""]])));
+
+        // `""` inside a quoted field is a literal quote.
+        let quoted = strings(&[&["Sie sagte: \"Hallo!\""]]);
+        assert_eq!(parse("\"Sie sagte: \"\"Hallo!\"\"\""), Ok(quoted));
+        assert_eq!(parse("\"\"\"\"\"\""), Ok(strings(&[&["\"\""]])));
+
+        // Outside of quoted fields, quotes are normal characters.
+        assert_eq!(parse("a\"b"), Ok(strings(&[&["a\"b"]])));
+    }
+
+    #[test]
+    fn newlines_in_quotes() {
+        let records = parse("key,De\nemail,\"Hallo,\n\nbis bald\"\nnext,x\n");
+        let expected = strings(&[&["key", "De"], &["email", "Hallo,\n\nbis bald"], &["next", "x"]]);
+        assert_eq!(records, Ok(expected));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("a,\"b").is_err());
+        assert!(parse("a,\"b\nc,d\n").is_err());
+
+        // The line of the unexpected character is reported.
+        let e = parse("a\n\"b\nc\"d").unwrap_err();
+        assert!(e.contains("'d'") && e.contains("line 3"), "{}", e);
+    }
+}
@@ -0,0 +1,240 @@
+//! Exports the dictionary into formats that can be handed to translators.
+//!
+//! Exporting is disabled by default. If the environment variable
+//! `MAUZI_EXPORT_DIR` is set while compiling, the dictionary is written as
+//! `mauzi.json` into that directory. Only string bodies are exported: raw
+//! bodies are Rust code and thus not really useful for translators. Units
+//! marked with `#[no_translate]` are not exported at all.
+//!
+//! Additionally, a human readable overview of all units is written as
+//! `mauzi.md`. It's meant to be committed, so that changes to units can be
+//! reviewed by translators.
+//!
+//! The files of different dictionaries are distinguished by the export name
+//! (`#[mauzi(export_name = "errors")]` writes `errors.json` and `errors.md`).
+//! In a crate with several dictionaries, all but one of them need an export
+//! name, otherwise the last dictionary expanded overwrites the others.
+//!
+//! XLIFF is not supported. An XLIFF file has exactly one target language,
+//! but an arm can handle several locales (`En`, region groups, bindings) and
+//! the same locale can be handled by several arms with guards. Tools which
+//! need XLIFF can convert the JSON, in which translations are keyed by the
+//! arm pattern.
+
+use std::env;
+use std::fmt::Write;
+use std::fs::File;
+use std::io::Write as IoWrite;
+use std::path::Path;
+
+use proc_macro::Span;
+
+use Result;
+use ast;
+use util::pretty_type;
+
+
+/// Name of the environment variable which enables the export.
+const EXPORT_DIR_VAR: &str = "MAUZI_EXPORT_DIR";
+
+/// Writes all exports of the given dictionary, if exporting is enabled.
+pub fn export(dict: &ast::Dict) -> Result<()> {
+    let dir = match env::var(EXPORT_DIR_VAR) {
+        Ok(dir) => dir,
+        Err(_) => return Ok(()),
+    };
+
+    let dir = Path::new(&dir);
+    let name = &dict.config.export_name;
+    write_file(&dir.join(format!("{}.json", name)), &to_json(dict))?;
+    write_file(&dir.join(format!("{}.md", name)), &to_markdown(dict))
+}
+
+/// Writes `content` into the file at `path`, replacing the old content.
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    File::create(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| {
+            Span::call_site()
+                .error(format!("error writing export file '{}'", path.display()))
+                .note(e.to_string())
+        })
+}
+
+/// Returns all units which should be exported, together with their full key.
+/// A key given via `#[key = "..."]` replaces the full key.
+fn translatable_units(dict: &ast::Dict) -> Vec<(String, &ast::TransUnit)> {
+    dict.units_with_keys()
+        .into_iter()
+        .filter(|&(_, unit)| !unit.no_translate)
+        .map(|(key, unit)| match unit.key {
+            Some(ref custom) => (custom.obj.clone(), unit),
+            None => (key, unit),
+        })
+        .collect()
+}
+
+/// Serializes the dictionary as JSON.
+///
+/// We don't want to pull in a serialization library just for this, so the
+/// JSON is written by hand. Each unit is identified by its full key, e.g.
+/// `bar.baz.bye_world`.
+fn to_json(dict: &ast::Dict) -> String {
+    let units = translatable_units(dict);
+
+    let mut out = String::new();
+    out.push('{');
+    if let Some(version) = dict.config.schema_version {
+        write!(out, "\n  \"schema_version\": {},", version).unwrap();
+    }
+    if let Some(base) = dict.locale_def.base_lang() {
+        write!(out, "\n  \"base_language\": {},", json_str(&base.name)).unwrap();
+    }
+    out.push_str("\n  \"units\": [");
+    for (i, &(ref key, unit)) in units.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+
+        write!(out, "\n    {{\n      \"key\": {},", json_str(key)).unwrap();
+        if let Some(ref category) = unit.category {
+            write!(out, "\n      \"category\": {},", json_str(category)).unwrap();
+        }
+        if let Some(ref context) = unit.context {
+            write!(out, "\n      \"context\": {},", json_str(&context.obj)).unwrap();
+        }
+        if let Some(ref note) = unit.note {
+            write!(out, "\n      \"note\": {},", json_str(note)).unwrap();
+        }
+        if let Some(ref max_len) = unit.max_len {
+            write!(out, "\n      \"max_len\": {},", max_len.obj).unwrap();
+        }
+
+        out.push_str("\n      \"translations\": {");
+        let arms = unit.body.arms.iter().filter_map(|arm| {
+            let pattern = match arm.guard {
+                Some(ref guard) if arm.is_count_condition => {
+                    format!("{}, {}", arm.pattern, guard.obj)
+                }
+                Some(ref guard) => format!("{} if {}", arm.pattern, guard.obj),
+                None => arm.pattern.to_string(),
+            };
+
+            match arm.body.obj {
+                ast::ArmBody::Str(ref s) => Some((pattern, s)),
+                ast::ArmBody::Raw(_) => None,
+            }
+        });
+        for (i, (pattern, s)) in arms.enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            write!(out, "\n        {}: {}", json_str(&pattern), json_str(s)).unwrap();
+        }
+        out.push_str("\n      }\n    }");
+    }
+    out.push_str("\n  ]\n}\n");
+
+    out
+}
+
+/// Renders an overview of all units as Markdown, grouped by module.
+///
+/// For each unit, the parameters, the category, the context, the note and the
+/// string of the base language (see `LocaleDef::base_lang()`) are listed.
+fn to_markdown(dict: &ast::Dict) -> String {
+    let base = dict.locale_def.base_lang().map(|lang| lang.name.as_str());
+
+    let mut out = String::new();
+    out.push_str("# Translation units\n");
+    if let Some(version) = dict.config.schema_version {
+        write!(out, "\nSchema version: {}\n", version).unwrap();
+    }
+    if let Some(base) = base {
+        write!(out, "\nBase language: `{}`\n", base).unwrap();
+    }
+
+    let mut current_module = None;
+    for (key, unit) in translatable_units(dict) {
+        // All units of one module are next to each other, so we only need a
+        // new heading when the module changes.
+        let module = key.rfind('.').map(|pos| key[..pos].to_string());
+        if current_module.as_ref() != Some(&module) {
+            match module {
+                Some(ref module) => write!(out, "\n\n## Module `{}`\n", module).unwrap(),
+                None => out.push_str("\n\n## Top level\n"),
+            }
+            current_module = Some(module);
+        }
+
+        write!(out, "\n### `{}`\n", key).unwrap();
+
+        // The list of details is left out if there are none, so that there is
+        // only one blank line between the heading and the string.
+        let mut details = String::new();
+        if let Some(ref params) = unit.params {
+            let params = params.iter()
+                .map(|p| format!("`{}: {}`", p.name, pretty_type(&p.ty.0)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(details, "- **Parameters:** {}\n", params).unwrap();
+        }
+        if let Some(ref category) = unit.category {
+            write!(details, "- **Category:** {}\n", category).unwrap();
+        }
+        if let Some(ref context) = unit.context {
+            write!(details, "- **Context:** {}\n", context.obj).unwrap();
+        }
+        if let Some(ref note) = unit.note {
+            write!(details, "- **Note:** {}\n", note).unwrap();
+        }
+        if let Some(ref max_len) = unit.max_len {
+            write!(details, "- **Max. length:** {} characters\n", max_len.obj).unwrap();
+        }
+        if !details.is_empty() {
+            out.push('\n');
+            out.push_str(&details);
+        }
+
+        // The arm used for the base language: either an arm for that
+        // language or a wildcard arm (`_` or a binding like `lang`). Arms with
+        // guards are only used in special cases, so we skip them.
+        let base_arm = unit.body.arms.iter()
+            .filter(|arm| arm.guard.is_none())
+            .find(|arm| match arm.pattern {
+                ast::ArmPattern::Underscore(_) => true,
+                ast::ArmPattern::Lang(lang) | ast::ArmPattern::WithRegion { lang, .. } => {
+                    Some(lang.as_str()) == base || dict.locale_def.get_lang(&lang).is_none()
+                }
+            });
+        match base_arm.map(|arm| &arm.body.obj) {
+            Some(&ast::ArmBody::Str(ref s)) => {
+                write!(out, "\n```text\n{}\n```\n", s).unwrap();
+            }
+            Some(&ast::ArmBody::Raw(_)) => out.push_str("\n*(generated by Rust code)*\n"),
+            None => out.push_str("\n*(no translation for the base language)*\n"),
+        }
+    }
+
+    out
+}
+
+/// Returns the given string as quoted and escaped JSON string.
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+
+    out
+}
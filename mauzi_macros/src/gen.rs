@@ -1,10 +1,20 @@
-use proc_macro::{quote, Literal, TokenNode, TokenStream};
+use proc_macro::{quote, Delimiter, Literal, Spacing, Span, TokenNode, TokenStream, TokenTree};
+use literalext::LiteralExt;
 
 use Result;
 use ast::{self, Ident};
-use util::{PatternUsage, Spanned};
+use phf;
+use util::{
+    env_placeholder, fallback_placeholder, is_binding_name, number_placeholder,
+    unit_call_placeholder, PatternUsage, Spanned,
+};
 
 
+/// If this environment variable is set (e.g. to `1`) while compiling, a
+/// warning is emitted for each unit with missing translations. Useful for CI
+/// to keep track of the translations still to do.
+const REPORT_MISSING_VAR: &str = "MAUZI_REPORT_MISSING";
+
 /// Generates the resulting Rust code from the AST.
 ///
 /// This function is the "compiler backend" of this proc macro: it takes all
@@ -17,42 +27,550 @@ use util::{PatternUsage, Spanned};
 ///
 /// For each translation key, the type has one method with the name and the
 /// parameters of said key. This method internally matches over the actual
-/// locale to decide which "body" to use. Those methods always return a
-/// `String`.
+/// locale to decide which "body" to use. Those methods return a `String`,
+/// unless the unit specifies another return type (`-> Type`) or is marked
+/// `const` (`&'static str`). Methods of `async` units wrap that type in a
+/// `UnitFuture`.
 pub fn gen(dict: ast::Dict) -> Result<TokenStream> {
-    let ast::Dict { trans_units, modules, locale_def } = dict;
+    let translation_keys = gen_translation_keys(&dict)?;
+
+    // `new()` is only a `const fn` if that was requested by marking a unit
+    // with `#[mauzi(const)]`. Otherwise, every crate using `mauzi!` would
+    // need `#![feature(const_fn)]`.
+    let const_new = dict.units().any(|unit| unit.is_const);
+    let ast::Dict { config, trans_units, modules, locale_def, helpers, context } = dict;
+
+    // Helpers are emitted as they are, next to the `Dict` types, so that all
+    // raw bodies can call them.
+    let helpers = helpers.into_iter().map(|h| h.obj).collect::<TokenStream>();
 
     // We want to create a few new names which the user can refer to. Due to
     // macro hygiene, we have to create special ident-tokens that live in the
     // same "context" as the invocation of `mauzi!{}` is in. Otherwise, the
     // names would be hidden/trapped inside of our macro context.
     let new_ident = Ident::exported("new");
+    let new_lang_ident = Ident::exported("new_lang");
     let locale_ident = locale_def.name();
+    let language_ident = Ident::exported("Language");
+    let from_language_ident = Ident::exported("from_language");
+
+    let global = if config.global {
+        gen_global(&trans_units, &locale_def, &config)
+    } else {
+        quote! {}
+    };
+
+    // `new()` can only be a `const fn` if it doesn't have to create caches
+    // or clone context values for the submodules.
+    let const_kw = if const_new && is_const_constructible(&modules, &trans_units, &context) {
+        quote! { const }
+    } else {
+        quote! {}
+    };
+
+    let module_tree_def =
+        gen_module(modules, trans_units, &locale_def, &config, &context, const_new, "", "")?;
 
-    let module_tree_def = gen_module(modules, trans_units, &locale_def, "")?;
+    // The context values are passed to `new()` after the locale.
+    let (ctx_params, ctx_args) = gen_context_params(&context);
+    let (ctx_params2, ctx_args2) = gen_context_params(&context);
 
-    // Generate the definition of `Locale` and possibly `*Region`.
-    let locale = gen_locale(locale_def)?;
+    // All generated types and functions get the configured visibility.
+    let vis = config.vis();
+    let vis2 = vis.clone();
+
+    // Generate the definition of `Locale` and possibly `*Region`, unless
+    // those were already defined via `mauzi_locale!`.
+    let locale = if locale_def.is_external {
+        quote! {}
+    } else {
+        gen_locale(locale_def, &config)?
+    };
 
     // Now we just return this quoted Rust code.
     //
-    // We need to refer to the `Locale` type from the `mauzi_runtime` crate,
-    // but there isn't a good way to do that currently.
+    // Items from the runtime crate are referred to via the crate path from
+    // the config, since the user might have renamed or reexported `mauzi`.
     Ok(quote! {
         $locale
 
-        pub fn $new_ident(locale: $locale_ident) -> Dict {
-            Dict::new(locale)
+        $vis $const_kw fn $new_ident(locale: $locale_ident $ctx_params) -> Dict {
+            Dict::new(locale $ctx_args)
         }
 
+        $vis2 fn $new_lang_ident(language: $language_ident $ctx_params2) -> Dict {
+            Dict::new($locale_ident::$from_language_ident(language) $ctx_args2)
+        }
+
+        $global
+
+        $helpers
+
         $module_tree_def
+
+        $translation_keys
+    })
+}
+
+/// Returns `true` if `new()` of the dictionary with the given modules and
+/// units can be a `const fn`, i.e. if there is no context and no unit in the
+/// module tree is `#[cache]`d.
+fn is_const_constructible(
+    modules: &[ast::Mod],
+    trans_units: &[ast::TransUnit],
+    context: &[ast::UnitParam],
+) -> bool {
+    context.is_empty()
+        && !trans_units.iter().any(|unit| unit.cache)
+        && modules.iter().all(|m| is_const_constructible(&m.modules, &m.trans_units, context))
+}
+
+/// Generates the global dictionary (`#[mauzi(global)]`): the functions
+/// `init_global()` and `global()`, and one free function per top level unit
+/// which forwards to the global dictionary. Units of submodules are reached
+/// via `global()`, e.g. `global().errors.not_found()`.
+///
+/// The dictionary is created once (guarded by a `Once`) and leaked. Its
+/// address is stored in an `AtomicUsize`, so it can be used from all threads,
+/// but its locale can't be changed after initialization.
+fn gen_global(
+    trans_units: &[ast::TransUnit],
+    locale_def: &ast::LocaleDef,
+    config: &ast::Config,
+) -> TokenStream {
+    let vis = config.vis();
+    let locale_ident = locale_def.name();
+    let init_global = Ident::exported("init_global");
+    let global = Ident::exported("global");
+    let storage = Ident::internal("__MAUZI_GLOBAL_DICT");
+    let once = Ident::internal("__MAUZI_GLOBAL_ONCE");
+
+    let forwarders = trans_units.iter().map(|unit| {
+        let fn_name = unit.method_name();
+        let generics = gen_unit_generics(unit);
+        let params = unit.params.iter().flat_map(|v| v).map(|param| {
+            let name = param.name;
+            let ty = gen_param_type(param);
+            quote! { $name: $ty, }
+        }).collect::<TokenStream>();
+        let args = unit.params.iter().flat_map(|v| v).map(|param| {
+            let name = param.name;
+            quote! { $name, }
+        }).collect::<TokenStream>();
+        let return_type = match unit.return_type {
+            Some(ref ty) => ty.0.parse::<TokenStream>().unwrap(),
+            None if unit.is_const => quote! { &'static str },
+            None => quote! { String },
+        };
+        let return_type = if unit.is_async {
+            let krate = config.crate_path();
+            quote! { $krate::UnitFuture<$return_type> }
+        } else {
+            return_type
+        };
+        let deprecated = gen_deprecated(unit);
+
+        let vis = vis.clone();
+        quote! {
+            $deprecated
+            #[allow(deprecated)]
+            $vis fn $fn_name $generics ($params) -> $return_type {
+                $global().$fn_name($args)
+            }
+        }
+    }).collect::<TokenStream>();
+    let vis2 = vis.clone();
+
+    quote! {
+        static $once: ::std::sync::Once = ::std::sync::ONCE_INIT;
+        static $storage: ::std::sync::atomic::AtomicUsize =
+            ::std::sync::atomic::ATOMIC_USIZE_INIT;
+
+        // Initializes the global dictionary with the given locale. If it
+        // was initialized already, the given locale is returned as error.
+        $vis fn $init_global(locale: $locale_ident) -> Result<(), $locale_ident> {
+            let mut initialized = false;
+            $once.call_once(|| {
+                let dict = Box::into_raw(Box::new(Dict::new(locale)));
+                $storage.store(dict as usize, ::std::sync::atomic::Ordering::SeqCst);
+                initialized = true;
+            });
+
+            if initialized {
+                Ok(())
+            } else {
+                Err(locale)
+            }
+        }
+
+        // Returns the global dictionary.
+        //
+        // Panics if `init_global()` wasn't called before.
+        $vis2 fn $global() -> &'static Dict {
+            match $storage.load(::std::sync::atomic::Ordering::SeqCst) {
+                0 => panic!("global dictionary used before calling `init_global()`"),
+                // The dictionary is never freed, so the reference is valid
+                // for the rest of the program.
+                ptr => unsafe { &*(ptr as *const Dict) },
+            }
+        }
+
+        $forwarders
+    }
+}
+
+/// Generates only the locale types (`Locale`, `Language` and the `*Region`
+/// enums) of the given dictionary. Used by `mauzi_locale!`.
+pub fn gen_locale_only(dict: ast::Dict) -> Result<TokenStream> {
+    gen_locale(dict.locale_def, &dict.config)
+}
+
+/// Generates the `TranslationKey` enum with one variant per unit (e.g.
+/// `BarBazByeWorld` for the unit `bye_world` in module `bar.baz`) and the
+/// method `Dict::get_by_key()`.
+fn gen_translation_keys(dict: &ast::Dict) -> Result<TokenStream> {
+    let key_ident = Ident::exported("TranslationKey");
+    let all_ident = Ident::exported("all");
+    let get_by_key_ident = Ident::exported("get_by_key");
+    let render_by_key_ident = Ident::exported("render_by_key");
+    let get_str_by_key_ident = Ident::exported("get_str_by_key");
+    let krate = dict.config.crate_path();
+    let locale_ident = dict.locale_def.name();
+
+    let mut variants = Vec::new();
+    let mut all = Vec::new();
+    let mut display_arms = Vec::new();
+    let mut get_arms = Vec::new();
+    let mut get_str_arms = Vec::new();
+    let mut render_arms = Vec::new();
+    for (key, unit) in dict.units_with_keys() {
+        let variant = Ident::exported(&camel_case(&key));
+        variants.push(quote! { $variant, });
+        all.push(quote! { $key_ident::$variant, });
+
+        let key_lit = TokenNode::Literal(Literal::string(&key));
+        display_arms.push(quote! { $key_ident::$variant => $key_lit, });
+
+        // Only units without parameters returning a string can be called
+        // here.
+        let param_count = unit.params.as_ref().map(|p| p.len()).unwrap_or(0);
+        let callable = param_count == 0 && unit.return_type.is_none() && !unit.is_async;
+        let path = key.split('.')
+            .map(|part| {
+                let part = Ident::exported(part);
+                quote! { . $part }
+            })
+            .collect::<TokenStream>();
+        let value = if callable {
+            let path = path.clone();
+            quote! { Some(self $path ().to_string()) }
+        } else {
+            quote! { None }
+        };
+        get_arms.push(quote! { $key_ident::$variant => $value, });
+
+        // Only `const` units return a `&'static str`, which can be returned
+        // without allocating.
+        let str_value = if unit.is_const {
+            let path = path.clone();
+            quote! { Some(self $path ()) }
+        } else {
+            quote! { None }
+        };
+        get_str_arms.push(quote! { $key_ident::$variant => $str_value, });
+
+        // For rendering with arguments, the number of arguments is checked
+        // first. Units with parameters are rendered by their hidden render
+        // method (see `gen_render_fn`).
+        let name = TokenNode::Literal(Literal::string(&key));
+        let render = if !is_renderable(unit) {
+            let krate = krate.clone();
+            quote! { Err($krate::MauziError::NotRenderable($name)) }
+        } else {
+            let expected = param_count.to_string().parse::<TokenStream>().unwrap();
+            let expected2 = expected.clone();
+            let call = if callable {
+                quote! { self $path ().to_string() }
+            } else {
+                let mut parts = key.rsplitn(2, '.');
+                let name = parts.next().unwrap();
+                let method = Ident::exported(&format!("__mauzi_render_{}", name));
+                let path = parts.next().into_iter()
+                    .flat_map(|prefix| prefix.split('.'))
+                    .map(|part| {
+                        let part = Ident::exported(part);
+                        quote! { . $part }
+                    })
+                    .collect::<TokenStream>();
+                quote! { self $path . $method(args) }
+            };
+            let krate = krate.clone();
+            quote! {
+                if args.len() != $expected {
+                    Err($krate::MauziError::ArgumentCount {
+                        unit: $name,
+                        expected: $expected2,
+                        given: args.len(),
+                    })
+                } else {
+                    Ok($call)
+                }
+            }
+        };
+        render_arms.push(quote! { $key_ident::$variant => { $render } });
+    }
+    let variants = variants.into_iter().collect::<TokenStream>();
+    let all = all.into_iter().collect::<TokenStream>();
+    let display_arms = display_arms.into_iter().collect::<TokenStream>();
+    let get_arms = get_arms.into_iter().collect::<TokenStream>();
+    let render_arms = render_arms.into_iter().collect::<TokenStream>();
+    let get_str_arms = get_str_arms.into_iter().collect::<TokenStream>();
+    let dynamic = if dict.config.dynamic {
+        gen_dynamic_lookup(dict)?
+    } else {
+        quote! {}
+    };
+    let vis = dict.config.vis();
+
+    Ok(quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $key_ident {
+            $variants
+        }
+
+        impl $key_ident {
+            pub fn $all_ident() -> &'static [$key_ident] {
+                &[$all]
+            }
+        }
+
+        impl ::std::fmt::Display for $key_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                let key = match *self {
+                    $display_arms
+                };
+                f.write_str(key)
+            }
+        }
+
+        impl Dict {
+            #[allow(deprecated)]
+            pub fn $get_by_key_ident(&self, key: $key_ident) -> Option<String> {
+                match key {
+                    $get_arms
+                }
+            }
+
+            // Returns the translation of the unit with the given key
+            // without allocating. Returns `None` if the unit isn't `const`.
+            #[allow(deprecated)]
+            pub fn $get_str_by_key_ident(&self, key: $key_ident) -> Option<&'static str> {
+                match key {
+                    $get_str_arms
+                }
+            }
+
+            // Renders the unit with the given key, passing `args` as its
+            // parameters in order. Returns an error if the number of
+            // arguments doesn't match or if the unit can't be rendered with
+            // arguments only known to implement `Display`. If no unit can be
+            // rendered like that, `args` is unused.
+            #[allow(deprecated, unused_variables)]
+            pub fn $render_by_key_ident(
+                &self,
+                key: $key_ident,
+                args: &[&::std::fmt::Display],
+            ) -> Result<String, $krate::MauziError<$locale_ident>> {
+                match key {
+                    $render_arms
+                }
+            }
+        }
+
+        $dynamic
+    })
+}
+
+/// Generates `TranslationKey::from_key()`, `Dict::get()`, `Dict::get_str()`
+/// and `Dict::render_key()` to look up units by their string key, e.g.
+/// `"bar.baz.bye_world"`. The key is found with a perfect hash table built at
+/// compile time, so no string comparisons other than the final one are
+/// needed.
+fn gen_dynamic_lookup(dict: &ast::Dict) -> Result<TokenStream> {
+    let key_ident = Ident::exported("TranslationKey");
+    let from_key_ident = Ident::exported("from_key");
+    let get_ident = Ident::exported("get");
+    let get_by_key_ident = Ident::exported("get_by_key");
+    let render_key_ident = Ident::exported("render_key");
+    let get_str_ident = Ident::exported("get_str");
+    let get_str_by_key_ident = Ident::exported("get_str_by_key");
+    let render_by_key_ident = Ident::exported("render_by_key");
+    let krate = dict.config.crate_path();
+    let locale_ident = dict.locale_def.name();
+
+    let keys = dict.units_with_keys().into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+    let table = phf::build(&keys).map_err(|e| {
+        Span::call_site().error(format!("can't build the lookup table of the unit keys: {}", e))
+    })?;
+
+    let disps = table.disps.iter()
+        .map(|&(d1, d2)| format!("({}, {}),", d1, d2))
+        .collect::<String>();
+    let disps_len = table.disps.len().to_string();
+    let entries = table.slots.iter()
+        .map(|&i| {
+            let key_lit = TokenNode::Literal(Literal::string(&keys[i]));
+            let variant = Ident::exported(&camel_case(&keys[i]));
+            quote! { ($key_lit, $key_ident::$variant), }
+        })
+        .collect::<TokenStream>();
+    let entries_len = table.slots.len().to_string();
+
+    // These only contain integers and tuples, so parsing can't fail.
+    let disps = disps.parse::<TokenStream>().unwrap();
+    let disps_len = disps_len.parse::<TokenStream>().unwrap();
+    let entries_len = entries_len.parse::<TokenStream>().unwrap();
+    let seed = format!("{}u64", table.seed).parse::<TokenStream>().unwrap();
+    let (krate2, krate3) = (krate.clone(), krate.clone());
+
+    Ok(quote! {
+        impl $key_ident {
+            // Returns the key with the given name, e.g. `"bar.baz.bye_world"`.
+            pub fn $from_key_ident(key: &str) -> Option<$key_ident> {
+                static DISPS: [(u32, u32); $disps_len] = [$disps];
+                static ENTRIES: [(&'static str, $key_ident); $entries_len] = [$entries];
+
+                if ENTRIES.is_empty() {
+                    return None;
+                }
+
+                let idx = $krate::phf_index(key, $seed, &DISPS, ENTRIES.len());
+                let (name, k) = ENTRIES[idx];
+                if name == key {
+                    Some(k)
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl Dict {
+            // Returns the translation of the unit with the given key, e.g.
+            // `"bar.baz.bye_world"`. Returns `None` if there is no such unit
+            // or if the unit can't be called without arguments.
+            pub fn $get_ident(&self, key: &str) -> Option<String> {
+                $key_ident::$from_key_ident(key).and_then(|k| self.$get_by_key_ident(k))
+            }
+
+            // Like `get()`, but returns the translation without allocating.
+            // Returns `None` if there is no such unit or if the unit isn't
+            // `const`.
+            pub fn $get_str_ident(&self, key: &str) -> Option<&'static str> {
+                $key_ident::$from_key_ident(key).and_then(|k| self.$get_str_by_key_ident(k))
+            }
+
+            // Renders the unit with the given key, e.g. `"greet"`, passing
+            // `args` as its parameters in order:
+            // `dict.render_key("greet", &[&"Ferris"])`. See
+            // `render_by_key()` for the possible errors.
+            pub fn $render_key_ident(
+                &self,
+                key: &str,
+                args: &[&::std::fmt::Display],
+            ) -> Result<String, $krate2::MauziError<$locale_ident>> {
+                match $key_ident::$from_key_ident(key) {
+                    Some(k) => self.$render_by_key_ident(k, args),
+                    None => Err($krate3::MauziError::UnknownUnit(key.to_string())),
+                }
+            }
+        }
     })
 }
 
+/// Converts a unit key like `bar.baz.bye_world` into camel case
+/// (`BarBazByeWorld`). Collisions are checked in `check`.
+pub fn camel_case(key: &str) -> String {
+    key.split(|c| c == '.' || c == '_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap();
+            first.to_uppercase().chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
 /// Generates the definition of the `Locale` enum as well as all potential
 /// `*Region` enums.
-fn gen_locale(locale_def: ast::LocaleDef) -> Result<TokenStream> {
+fn gen_locale(locale_def: ast::LocaleDef, config: &ast::Config) -> Result<TokenStream> {
     let locale_ident = locale_def.name();
+    let krate = config.crate_path();
+
+    // Generate the methods and impls before we consume the locale definition
+    // below.
+    let display_name_fn = gen_display_name_fn(&locale_def);
+    let tag_fns = gen_tag_fns(&locale_def);
+    let language = gen_language(&locale_def, config.vis());
+    let plural_category = Ident::exported("plural_category");
+    let language_tag = Ident::exported("language_tag");
+    let region_tag = Ident::exported("region_tag");
+    let language_fn = Ident::exported("language");
+    let from_language = Ident::exported("from_language");
+    let closest = Ident::exported("closest");
+    let format_number = Ident::exported("format_number");
+    let tag_eq_impls = gen_tag_eq_impls(&locale_def);
+    let as_str_ident = Ident::exported("as_str");
+    let from_str_impl = gen_from_str_impl(&locale_def, krate.clone());
+    let error_type = gen_error_type(config);
+    let all = Ident::exported("all");
+    let all_locales = locale_variants(&locale_def).into_iter()
+        .map(|(value, _, _)| quote! { $value, })
+        .collect::<TokenStream>();
+    let ffi = match config.ffi {
+        Some(ref prefix) => gen_ffi(&locale_def, prefix),
+        None => quote! {},
+    };
+    let ids = gen_locale_ids(&locale_def, krate.clone());
+    let from_numeric = gen_from_numeric(&locale_def);
+    let from_env = Ident::exported("from_env");
+    let from_config_file = if cfg!(feature = "config_file") {
+        gen_from_config_file(&krate)
+    } else {
+        quote! {}
+    };
+
+    // The traits we derive for `Locale` and the region enums. In hash mode,
+    // the `Dict` types derive `Hash`, which requires the locale types to do
+    // so as well.
+    let own_derives: &[&str] = if config.hash {
+        &["Debug", "Clone", "Copy", "PartialEq", "Eq", "Hash"]
+    } else {
+        &["Debug", "Clone", "Copy"]
+    };
+    let derives = own_derives.join(", ").parse::<TokenStream>().unwrap();
+    let derives = quote! { #[derive($derives)] };
+
+    // Like bodies read from files, a locale file is included via
+    // `include_str!` to rebuild the crate when it changes.
+    let file_dep = match locale_def.source_file {
+        Some(ref path) => {
+            let path = TokenNode::Literal(Literal::string(&path.display().to_string()));
+            quote! {
+                #[allow(dead_code)]
+                const _MAUZI_LOCALE_FILE: &'static str = include_str!($path);
+            }
+        }
+        None => quote! {},
+    };
+
+    // Attributes given in front of the locale definition. They are added to
+    // the region enums, too, since e.g. derives on `Locale` require the
+    // region types to implement the trait as well.
+    let attrs = locale_def.attrs.into_iter()
+        .map(|attr| without_own_derives(attr, own_derives))
+        .filter(|attr| !is_empty_derive(attr))
+        .map(|attr| quote! { #[$attr] })
+        .collect::<TokenStream>();
 
     // In this vector we collect all region types we have to generate.
     let mut region_types = Vec::new();
@@ -67,8 +585,13 @@ fn gen_locale(locale_def: ast::LocaleDef) -> Result<TokenStream> {
             quote! { $name , }
         } else {
             // ... otherwise it is a tuple-variant.
-            let region_ty = region_ty_name(lang.name.as_str());
-            region_types.push((region_ty.clone(), lang.regions));
+            // Languages using the same region set share the region type.
+            let region_ty = region_ty_name(&lang);
+            let exists = region_types.iter()
+                .any(|&(ref ty, _): &(Ident, _)| ty.as_str() == region_ty.as_str());
+            if !exists {
+                region_types.push((region_ty.clone(), lang.regions));
+            }
 
             quote! { $name ( $region_ty ) , }
         }
@@ -77,188 +600,1798 @@ fn gen_locale(locale_def: ast::LocaleDef) -> Result<TokenStream> {
     // Collect all definitions of region types.
     let region_types = region_types.into_iter().map(|(ident, regions)| {
         let regions = regions.into_iter()
-            .map(|region_name| {
+            .map(|region| {
+                let region_name = region.name;
                 quote! { $region_name , }
             })
             .collect::<TokenStream>();
 
+        let (derives, attrs) = (derives.clone(), attrs.clone());
+        let vis = config.vis();
         quote! {
-            #[derive(Debug, Clone, Copy)]
-            pub enum $ident {
+            $derives
+            $attrs
+            $vis enum $ident {
                 $regions
             }
         }
     }).collect::<TokenStream>();
 
+    let vis = config.vis();
+
     Ok(quote! {
-        #[derive(Debug, Clone, Copy)]
-        pub enum $locale_ident {
+        $file_dep
+
+        $derives
+        $attrs
+        $vis enum $locale_ident {
             $langs
         }
 
-        $region_types
+        impl $locale_ident {
+            $display_name_fn
+            $tag_fns
+
+            // Returns all locales in the order of their definition.
+            pub fn $all() -> &'static [Self] {
+                &[$all_locales]
+            }
+
+            pub fn $plural_category(self, n: u64) -> $krate::PluralCategory {
+                $krate::plural_category(self.$language_tag(), n)
+            }
+
+            pub fn $format_number<N: ::std::fmt::Display>(self, n: N) -> String {
+                $krate::format_number(self.$as_str_ident(), n)
+            }
+
+            // Returns the locale of `supported` which is closest to this one:
+            // the same locale, else the default region of the same language,
+            // else any other region of the same language.
+            pub fn $closest(self, supported: &[Self]) -> Option<Self> {
+                let lang = self.$language_tag();
+                let same_lang = || {
+                    supported.iter().cloned().filter(move |l| l.$language_tag() == lang)
+                };
+
+                same_lang().find(|l| l.$region_tag() == self.$region_tag())
+                    .or_else(|| {
+                        let default_region = Self::$from_language(self.$language_fn())
+                            .$region_tag();
+                        same_lang().find(|l| l.$region_tag() == default_region)
+                    })
+                    .or_else(|| same_lang().next())
+            }
+
+            // Returns the locale set by the environment variables `LC_ALL`,
+            // `LC_MESSAGES` or `LANG` (e.g. `en_US.UTF-8`), if it's supported.
+            // For unsupported regions, the default region of the language is
+            // used.
+            pub fn $from_env() -> Option<Self> {
+                $krate::env_language_tags().into_iter()
+                    .filter_map(|tag| {
+                        tag.parse().ok().or_else(|| {
+                            let lang = tag.split(|c| c == '-' || c == '_').next().unwrap();
+                            lang.parse().ok()
+                        })
+                    })
+                    .next()
+            }
+
+            $from_config_file
+
+            $from_numeric
+        }
+
+        $language
+
+        impl $krate::Locale for $locale_ident {
+            fn display_name(self) -> &'static str {
+                $locale_ident::display_name(self)
+            }
+        }
+
+        // `Display` shows the language tag (e.g. "en-US"), while the derived
+        // `Debug` shows the variant (e.g. "En(Us)").
+        impl ::std::fmt::Display for $locale_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(self.$as_str_ident())
+            }
+        }
+
+        $tag_eq_impls
+
+        $error_type
+
+        $from_str_impl
+
+        $ids
+
+        $ffi
+
+        $region_types
+    })
+}
+
+/// Returns `true` if the given attribute is `derive()`, which happens if
+/// `without_own_derives` removed all of its traits. The compiler warns about
+/// empty derives, so those are dropped.
+fn is_empty_derive(attr: &TokenStream) -> bool {
+    let tts = attr.clone().into_iter().collect::<Vec<_>>();
+    if tts.len() != 2 {
+        return false;
+    }
+
+    match (&tts[0].kind, &tts[1].kind) {
+        (&TokenNode::Term(term), &TokenNode::Group(Delimiter::Parenthesis, ref inner)) => {
+            term.as_str() == "derive" && inner.is_empty()
+        }
+        _ => false,
+    }
+}
+
+/// Removes the traits which are derived for `Locale` anyway (`own_derives`,
+/// e.g. `Debug`, `Clone` and `Copy`) from all `derive(...)` in the given
+/// attribute, including those nested in `cfg_attr(...)`. Deriving a trait
+/// twice would be an error.
+fn without_own_derives(attr: TokenStream, own_derives: &[&str]) -> TokenStream {
+    let mut out = Vec::new();
+    let mut after_derive = false;
+    for TokenTree { kind, span } in attr {
+        let kind = match kind {
+            TokenNode::Group(Delimiter::Parenthesis, inner) if after_derive => {
+                // Split the list of traits at the commas. The last term of
+                // each path is the trait's name.
+                let mut traits = vec![Vec::new()];
+                for tt in inner {
+                    match tt.kind {
+                        TokenNode::Op(',', _) => traits.push(Vec::new()),
+                        _ => traits.last_mut().unwrap().push(tt),
+                    }
+                }
+
+                let traits = traits.into_iter()
+                    .filter(|path| {
+                        let name = path.iter().filter_map(|tt| match tt.kind {
+                            TokenNode::Term(term) => Some(term),
+                            _ => None,
+                        }).last();
+
+                        match name {
+                            Some(name) => !own_derives.contains(&name.as_str()),
+                            None => false,
+                        }
+                    })
+                    .enumerate()
+                    .map(|(i, path)| {
+                        let path = path.into_iter().collect::<TokenStream>();
+                        if i == 0 { path } else { quote! { , $path } }
+                    })
+                    .collect::<TokenStream>();
+
+                TokenNode::Group(Delimiter::Parenthesis, traits)
+            }
+            TokenNode::Group(delim, inner) => {
+                TokenNode::Group(delim, without_own_derives(inner, own_derives))
+            }
+            other => other,
+        };
+
+        after_derive = match kind {
+            TokenNode::Term(term) => term.as_str() == "derive",
+            _ => false,
+        };
+        out.push(TokenTree { kind, span });
+    }
+
+    out.into_iter().collect()
+}
+
+/// Generates the `display_name()` method of `Locale` which returns a human
+/// readable name of the locale.
+///
+/// The names can be specified via `as "..."` in the locale definition. If a
+/// region doesn't have a name, the name of its language is used. If neither
+/// has a name, we fall back to the language tag (e.g. `en-GB`).
+fn gen_display_name_fn(locale_def: &ast::LocaleDef) -> TokenStream {
+    let fn_name = Ident::exported("display_name");
+
+    let arms = locale_variants(locale_def).into_iter().map(|(pattern, lang, region)| {
+        let name = region.and_then(|r| r.display_name.clone())
+            .or_else(|| lang.display_name.clone())
+            .unwrap_or_else(|| lang_tag(lang, region));
+        let name = TokenNode::Literal(Literal::string(&name));
+
+        quote! { $pattern => $name, }
+    }).collect::<TokenStream>();
+
+    quote! {
+        pub fn $fn_name(self) -> &'static str {
+            match self {
+                $arms
+            }
+        }
+    }
+}
+
+/// Generates the `Language` enum, which contains all languages without
+/// regions, and the methods of `Locale` to query the language: `language()`,
+/// `is_language()` and one predicate per language (e.g. `is_en()`).
+fn gen_language(locale_def: &ast::LocaleDef, vis: TokenStream) -> TokenStream {
+    let locale_ident = locale_def.name();
+    let language_ident = Ident::exported("Language");
+    let language_fn = Ident::exported("language");
+    let is_language_fn = Ident::exported("is_language");
+    let from_language_fn = Ident::exported("from_language");
+
+    let mut variants = Vec::new();
+    let mut arms = Vec::new();
+    let mut from_arms = Vec::new();
+    let mut predicates = Vec::new();
+    for lang in &locale_def.langs {
+        let lang_name = lang.name;
+        variants.push(quote! { $lang_name, });
+
+        let pattern = if lang.has_regions() {
+            quote! { $locale_ident::$lang_name(_) }
+        } else {
+            quote! { $locale_ident::$lang_name }
+        };
+        arms.push(quote! { $pattern => $language_ident::$lang_name, });
+
+        // Without a region, we use the default region of the language.
+        let locale = match lang.default_region() {
+            Some(region) => {
+                let region_ty = region_ty_name(lang);
+                let region_name = region.name;
+                quote! { $locale_ident::$lang_name($region_ty::$region_name) }
+            }
+            None => quote! { $locale_ident::$lang_name },
+        };
+        from_arms.push(quote! { $language_ident::$lang_name => $locale, });
+
+        let predicate = Ident::exported(&format!("is_{}", lang_name.to_lowercase()));
+        predicates.push(quote! {
+            pub fn $predicate(self) -> bool {
+                self.$language_fn() == $language_ident::$lang_name
+            }
+        });
+    }
+    let variants = variants.into_iter().collect::<TokenStream>();
+    let arms = arms.into_iter().collect::<TokenStream>();
+    let from_arms = from_arms.into_iter().collect::<TokenStream>();
+    let predicates = predicates.into_iter().collect::<TokenStream>();
+
+    quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis enum $language_ident {
+            $variants
+        }
+
+        impl $locale_ident {
+            pub fn $language_fn(self) -> $language_ident {
+                match self {
+                    $arms
+                }
+            }
+
+            pub fn $is_language_fn(self, lang: $language_ident) -> bool {
+                self.$language_fn() == lang
+            }
+
+            // Returns the locale for the given language, with the default
+            // region if the language has regions.
+            pub fn $from_language_fn(lang: $language_ident) -> Self {
+                match lang {
+                    $from_arms
+                }
+            }
+
+            $predicates
+        }
+    }
+}
+
+/// Generates the methods `language_tag()` and `region_tag()` of `Locale`,
+/// returning e.g. `"en"` and `Some("GB")`.
+fn gen_tag_fns(locale_def: &ast::LocaleDef) -> TokenStream {
+    let language_tag = Ident::exported("language_tag");
+    let region_tag = Ident::exported("region_tag");
+    let as_str = Ident::exported("as_str");
+    let debug_tag = Ident::exported("debug_tag");
+
+    let mut full_arms = Vec::new();
+    let mut lang_arms = Vec::new();
+    let mut region_arms = Vec::new();
+    let mut debug_arms = Vec::new();
+    for (pattern, lang, region) in locale_variants(locale_def) {
+        let tag = TokenNode::Literal(Literal::string(&lang_tag(lang, region)));
+        let full_pattern = pattern.clone();
+        full_arms.push(quote! { $full_pattern => $tag, });
+
+        let name = match region {
+            Some(region) => format!("{}({})", lang.name, region.name),
+            None => lang.name.to_string(),
+        };
+        let name = TokenNode::Literal(Literal::string(&name));
+        let debug_pattern = pattern.clone();
+        debug_arms.push(quote! { $debug_pattern => $name, });
+
+        let tag = TokenNode::Literal(Literal::string(&lang_tag(lang, None)));
+        lang_arms.push(quote! { $pattern => $tag, });
+
+        let region_tag = match region {
+            Some(region) => {
+                let tag = TokenNode::Literal(Literal::string(&region.name.to_uppercase()));
+                quote! { Some($tag) }
+            }
+            None => quote! { None },
+        };
+        region_arms.push(quote! { $pattern => $region_tag, });
+    }
+    let full_arms = full_arms.into_iter().collect::<TokenStream>();
+    let lang_arms = lang_arms.into_iter().collect::<TokenStream>();
+    let region_arms = region_arms.into_iter().collect::<TokenStream>();
+    let debug_arms = debug_arms.into_iter().collect::<TokenStream>();
+
+    quote! {
+        // Returns the full language tag of this locale, e.g. "en-US".
+        pub fn $as_str(self) -> &'static str {
+            match self {
+                $full_arms
+            }
+        }
+
+        // Returns the name of the variant like `Debug` prints it, e.g.
+        // "En(Us)", without allocating.
+        pub fn $debug_tag(self) -> &'static str {
+            match self {
+                $debug_arms
+            }
+        }
+
+        pub fn $language_tag(self) -> &'static str {
+            match self {
+                $lang_arms
+            }
+        }
+
+        pub fn $region_tag(self) -> Option<&'static str> {
+            match self {
+                $region_arms
+            }
+        }
+    }
+}
+
+/// Generates `PartialEq` impls to compare a `Locale` with a language tag
+/// like `"en-US"` or `"de"`.
+///
+/// Language tags are case insensitive. A locale with region is only equal to
+/// the full tag: `En(Us)` is not equal to `"en"`.
+fn gen_tag_eq_impls(locale_def: &ast::LocaleDef) -> TokenStream {
+    let locale_ident = locale_def.name();
+
+    let arms = locale_variants(locale_def).into_iter().map(|(pattern, lang, region)| {
+        let tag = TokenNode::Literal(Literal::string(&lang_tag(lang, region)));
+        quote! { $pattern => other.eq_ignore_ascii_case($tag), }
+    }).collect::<TokenStream>();
+
+    quote! {
+        impl PartialEq<str> for $locale_ident {
+            fn eq(&self, other: &str) -> bool {
+                match *self {
+                    $arms
+                }
+            }
+        }
+
+        impl<'a> PartialEq<&'a str> for $locale_ident {
+            fn eq(&self, other: &&'a str) -> bool {
+                *self == **other
+            }
+        }
+    }
+}
+
+/// Reexports `MauziError`, the error type of all fallible functions
+/// generated for the locale and the dictionaries, next to the locale types.
+/// The generated code itself refers to the type in the runtime crate, so it
+/// doesn't need to be in scope of dictionaries using shared locale types.
+fn gen_error_type(config: &ast::Config) -> TokenStream {
+    let krate = config.crate_path();
+    let vis = config.vis();
+    let error = Ident::exported("MauziError");
+
+    quote! {
+        $vis use $krate::$error;
+    }
+}
+
+/// Generates `id()` for the `Locale` type and, if the `try_from` feature is
+/// enabled, `TryFrom<u32>`. The id of a locale is its index in declaration
+/// order.
+fn gen_locale_ids(locale_def: &ast::LocaleDef, krate: TokenStream) -> TokenStream {
+    let locale_ident = locale_def.name();
+    let id = Ident::exported("id");
+
+    let mut id_arms = Vec::new();
+    let mut from_id_arms = Vec::new();
+    for (i, (value, _, _)) in locale_variants(locale_def).into_iter().enumerate() {
+        let i = format!("{}u32", i).parse::<TokenStream>().unwrap();
+        let (value_from, i_from) = (value.clone(), i.clone());
+        id_arms.push(quote! { $value => $i, });
+        from_id_arms.push(quote! { $i_from => Ok($value_from), });
+    }
+    let id_arms = id_arms.into_iter().collect::<TokenStream>();
+    let from_id_arms = from_id_arms.into_iter().collect::<TokenStream>();
+
+    let try_from_impl = if cfg!(feature = "try_from") {
+        let krate2 = krate.clone();
+        quote! {
+            impl ::std::convert::TryFrom<u32> for $locale_ident {
+                type Error = $krate::MauziError<$locale_ident>;
+
+                fn try_from(id: u32) -> Result<Self, Self::Error> {
+                    match id {
+                        $from_id_arms
+                        _ => Err($krate2::MauziError::UnknownLocale(id.to_string())),
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        impl $locale_ident {
+            // Returns the stable id of this locale: its index in declaration
+            // order. Only adding locales at the end keeps existing ids.
+            pub fn $id(self) -> u32 {
+                match self {
+                    $id_arms
+                }
+            }
+        }
+
+        $try_from_impl
+    }
+}
+
+/// Generates `from_config_file()` for the `Locale` type, which is only done
+/// if the `config_file` feature is enabled.
+fn gen_from_config_file(krate: &TokenStream) -> TokenStream {
+    let from_config_file = Ident::exported("from_config_file");
+    let krate = krate.clone();
+
+    quote! {
+        // Returns the locale set by the `locale = ...` line of the given
+        // config file, if the file exists and the locale is supported.
+        pub fn $from_config_file<P: AsRef<::std::path::Path>>(path: P) -> Option<Self> {
+            $krate::config_file_language_tag(path).and_then(|tag| tag.parse().ok())
+        }
+    }
+}
+
+/// Generates the `FromStr` impl of `Locale`, parsing language tags like
+/// `"en-US"`, `"en_US"` or `"de"` (case insensitive). A tag without region
+/// results in the default region of the language.
+fn gen_from_str_impl(locale_def: &ast::LocaleDef, krate: TokenStream) -> TokenStream {
+    let locale_ident = locale_def.name();
+    let krate2 = krate.clone();
+    let language = Ident::exported("Language");
+    let from_language = Ident::exported("from_language");
+
+    let mut arms = locale_variants(locale_def).into_iter().map(|(value, lang, region)| {
+        let tag = TokenNode::Literal(Literal::string(&lang_tag(lang, region).to_lowercase()));
+        quote! { $tag => Ok($value), }
+    }).collect::<Vec<_>>();
+    for lang in locale_def.langs.iter().filter(|lang| lang.has_regions()) {
+        let tag = TokenNode::Literal(Literal::string(&lang_tag(lang, None)));
+        let lang_name = lang.name;
+        arms.push(quote! { $tag => Ok($locale_ident::$from_language($language::$lang_name)), });
+    }
+    let arms = arms.into_iter().collect::<TokenStream>();
+
+    quote! {
+        impl ::std::str::FromStr for $locale_ident {
+            type Err = $krate::MauziError<$locale_ident>;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match &*s.replace('_', "-").to_lowercase() {
+                    $arms
+                    _ => Err($krate2::MauziError::UnknownLocale(s.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/// Generates `Locale::from_numeric()`, which returns the locale of the given
+/// language and numeric UN M49 region code (e.g. `826` for `Gb`), as given
+/// in the locale definition (`En { Gb = 826 }`). For languages without
+/// regions, the code is ignored.
+fn gen_from_numeric(locale_def: &ast::LocaleDef) -> TokenStream {
+    let language = Ident::exported("Language");
+    let from_numeric = Ident::exported("from_numeric");
+
+    let arms = locale_variants(locale_def).into_iter()
+        .filter_map(|(value, lang, region)| {
+            let lang_name = lang.name;
+            match region {
+                None => Some(quote! { ($language::$lang_name, _) => Some($value), }),
+                Some(region) => region.numeric_code.as_ref().map(|code| {
+                    let code = code.obj.to_string().parse::<TokenStream>().unwrap();
+                    quote! { ($language::$lang_name, $code) => Some($value), }
+                }),
+            }
+        })
+        .collect::<TokenStream>();
+
+    quote! {
+        // Returns the locale with the given language and numeric UN M49
+        // region code, e.g. `826` for Great Britain.
+        #[allow(unreachable_patterns)]
+        pub fn $from_numeric(language: $language, code: u16) -> Option<Self> {
+            match (language, code) {
+                $arms
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Generates `Locale::from_id()` as well as the C functions
+/// `<prefix>_locale_from_code()` and `<prefix>_locale_to_code()`, which
+/// convert between language tags and ids. The ids are the ones of
+/// `Locale::id()` (see `gen_locale_ids`). The C functions are exported with
+/// their unmangled names, so the prefix has to be unique among all linked
+/// crates.
+///
+/// The ids are the indices of the locales in declaration order, e.g. with
+/// `enum Locale { De, En { Gb, Us } }`, `De` is 0, `En(Gb)` is 1 and `En(Us)`
+/// is 2. Thus, ids only stay the same if new locales are added at the end.
+/// The C functions use `i32` instead of `u32`, so that they can return -1
+/// for invalid tags.
+fn gen_ffi(locale_def: &ast::LocaleDef, prefix: &str) -> TokenStream {
+    let locale_ident = locale_def.name();
+    let id = Ident::exported("id");
+    let from_id = Ident::exported("from_id");
+    let locale_from_code = Ident::exported(&format!("{}_locale_from_code", prefix));
+    let locale_to_code = Ident::exported(&format!("{}_locale_to_code", prefix));
+
+    let mut from_id_arms = Vec::new();
+    let mut code_arms = Vec::new();
+    for (i, (value, lang, region)) in locale_variants(locale_def).into_iter().enumerate() {
+        let i = format!("{}u32", i).parse::<TokenStream>().unwrap();
+        let code = format!("{}\0", lang_tag(lang, region));
+        let code = TokenNode::Literal(Literal::byte_string(code.as_bytes()));
+
+        let i_code = i.clone();
+        from_id_arms.push(quote! { $i => Some($value), });
+        code_arms.push(quote! { $i_code => $code.as_ptr() as *const ::std::os::raw::c_char, });
+    }
+    let from_id_arms = from_id_arms.into_iter().collect::<TokenStream>();
+    let code_arms = code_arms.into_iter().collect::<TokenStream>();
+
+    quote! {
+        impl $locale_ident {
+            // Returns the locale with the given id (see `id()`), or `None` if
+            // the id is invalid.
+            pub fn $from_id(id: u32) -> Option<Self> {
+                match id {
+                    $from_id_arms
+                    _ => None,
+                }
+            }
+        }
+
+        // Returns the id of the locale with the given language tag (e.g.
+        // "en-US"), or -1 if the tag is null, invalid or not supported.
+        #[no_mangle]
+        pub extern "C" fn $locale_from_code(code: *const ::std::os::raw::c_char) -> i32 {
+            if code.is_null() {
+                return -1;
+            }
+
+            // There are far fewer than `i32::MAX` locales, so the id always
+            // fits into an `i32`.
+            let code = unsafe { ::std::ffi::CStr::from_ptr(code) };
+            code.to_str().ok()
+                .and_then(|code| code.parse::<$locale_ident>().ok())
+                .map(|locale| locale.$id() as i32)
+                .unwrap_or(-1)
+        }
+
+        // Returns the language tag of the locale with the given id as static
+        // null-terminated string, or null if the id is invalid.
+        #[no_mangle]
+        pub extern "C" fn $locale_to_code(id: i32) -> *const ::std::os::raw::c_char {
+            // Negative ids are invalid, all others are valid `u32` ids.
+            if id < 0 {
+                return ::std::ptr::null();
+            }
+
+            match id as u32 {
+                $code_arms
+                _ => ::std::ptr::null(),
+            }
+        }
+    }
+}
+
+/// Returns all values of the `Locale` enum as pattern, together with the
+/// language and region they represent.
+fn locale_variants(
+    locale_def: &ast::LocaleDef,
+) -> Vec<(TokenStream, &ast::LocaleLang, Option<&ast::LocaleRegion>)> {
+    let locale_ident = locale_def.name();
+
+    let mut out = Vec::new();
+    for lang in &locale_def.langs {
+        let lang_name = lang.name;
+
+        if lang.regions.is_empty() {
+            out.push((quote! { $locale_ident::$lang_name }, lang, None));
+        } else {
+            let region_ty = region_ty_name(lang);
+            for region in &lang.regions {
+                let region_name = region.name;
+                let pattern = quote! { $locale_ident::$lang_name($region_ty::$region_name) };
+                out.push((pattern, lang, Some(region)));
+            }
+        }
+    }
+
+    out
+}
+
+/// Returns the language tag of the given language and region, e.g. `en-GB`.
+///
+/// The language part is lowercased and the region part is uppercased, as
+/// recommended by BCP 47.
+fn lang_tag(lang: &ast::LocaleLang, region: Option<&ast::LocaleRegion>) -> String {
+    match region {
+        Some(region) => format!(
+            "{}-{}",
+            lang.name.to_lowercase(),
+            region.name.to_uppercase()
+        ),
+        None => lang.name.to_lowercase(),
+    }
+}
+
+/// Simple helper to generate the name of the region type, e.g. `EnRegion`.
+/// Languages using a region set share the type of the set, e.g. `EuRegion`.
+fn region_ty_name(lang: &ast::LocaleLang) -> Ident {
+    let name = lang.region_set.unwrap_or(lang.name);
+    Ident::exported(&format!("{}Region", name))
+}
+
+/// Generates the code for the given module and all of its submodules.
+///
+/// Each module has its own `Dict` type. However, sadly, mauzi-modules don't
+/// map to Rust modules. When I worked on this, emitting Rust-modules was a
+/// pain due to strange visibility effects.
+///
+/// `const_new` tells whether `new()` should be a `const fn` (see `gen`).
+///
+/// Instead, each `Dict` type gets an ugly prefix. This means that everything
+/// lives in one module, and most of the types have super strange names.
+fn gen_module(
+    sub_modules: Vec<ast::Mod>,
+    trans_units: Vec<ast::TransUnit>,
+    locale: &ast::LocaleDef,
+    config: &ast::Config,
+    context: &[ast::UnitParam],
+    const_new: bool,
+    stem: &str,
+    key_prefix: &str,
+) -> Result<TokenStream> {
+    let locale_ident = locale.name();
+    let const_kw = if const_new && is_const_constructible(&sub_modules, &trans_units, context) {
+        quote! { const }
+    } else {
+        quote! {}
+    };
+
+    // We generate the token streams for all sub modules and combine them into
+    // a big stream.
+    let mut sub_module_names = Vec::new();
+    let sub_modules = sub_modules.into_iter().map(|sub| {
+        // Generate new prefix and type name ...
+        let new_stem = format!("{}{}___this_is_a_bad_solution___", stem, sub.name.as_str());
+        let ty_name = Ident::internal(&format!("{}Dict", new_stem));
+
+        let new_key_prefix = format!("{}{}.", key_prefix, sub.name.as_str());
+
+        sub_module_names.push((sub.name, ty_name));
+        gen_module(
+            sub.modules,
+            sub.trans_units,
+            locale,
+            config,
+            context,
+            const_new,
+            &new_stem,
+            &new_key_prefix,
+        )
+    }).collect::<Result<TokenStream>>()?;
+
+    // The fields for submodules in our `Dict` definition
+    let sub_module_fields = sub_module_names.iter().map(|&(name, ty_name)| {
+        quote! { pub $name: $ty_name , }
+    }).collect::<TokenStream>();
+
+    // The initializer list of the submodules in our `Dict::new()` method.
+    // Each submodule gets a clone of the context.
+    let sub_module_field_inits = sub_module_names.iter().map(|&(name, ty_name)| {
+        let ctx_clones = context.iter().map(|param| {
+            let name = param.name;
+            quote! { , $name.clone() }
+        }).collect::<TokenStream>();
+        quote! { $name: $ty_name::new(locale $ctx_clones), }
+    }).collect::<TokenStream>();
+
+    // The results of units with `#[cache]` are stored in the `Dict`.
+    let (cache_fields, cache_inits): (Vec<_>, Vec<_>) = trans_units.iter()
+        .filter(|unit| unit.cache)
+        .map(|unit| gen_cache_field(unit, config))
+        .unzip();
+    let cache_fields = cache_fields.into_iter().collect::<TokenStream>();
+    let cache_inits = cache_inits.into_iter().collect::<TokenStream>();
+
+    // The context values are stored in fields of the same name.
+    let ctx_fields = context.iter().map(|param| {
+        let name = param.name;
+        let ty = param.ty.0.parse::<TokenStream>().unwrap();
+        quote! { $name: $ty, }
+    }).collect::<TokenStream>();
+    let ctx_field_inits = context.iter().map(|param| {
+        let name = param.name;
+        quote! { $name, }
+    }).collect::<TokenStream>();
+    let (ctx_params, _) = gen_context_params(context);
+    let (ctx_params2, ctx_args2) = gen_context_params(context);
+
+    // `as_map()` contains all synchronous units without parameters which
+    // return a string, including the ones of all submodules. The keys are the
+    // full paths of the units, e.g. `bar.baz.bye_world`.
+    let as_map_ident = Ident::exported("as_map");
+    let map_inserts = trans_units.iter()
+        .filter(|unit| {
+            unit.params.as_ref().map(|p| p.is_empty()).unwrap_or(true)
+                && unit.return_type.is_none()
+                && !unit.is_async
+        })
+        .map(|unit| {
+            let name = unit.method_name();
+            let key = format!("{}{}", key_prefix, name.as_str());
+            let key = TokenNode::Literal(Literal::string(&key));
+            quote! { map.insert($key, self.$name().to_string()); }
+        })
+        .collect::<TokenStream>();
+    let sub_module_maps = sub_module_names.iter().map(|&(name, _)| {
+        quote! { map.extend(self.$name.$as_map_ident()); }
+    }).collect::<TokenStream>();
+
+    // We generate the token streams for all methods and combine them into a
+    // big token stream.
+    let methods = trans_units.into_iter()
+        .map(|unit| gen_trans_unit(unit, locale, config, context))
+        .collect::<Result<TokenStream>>()?;
+
+    // Our type name.
+    let ty_name = Ident::internal(&format!("{}Dict", stem));
+    let vis = config.vis();
+    let derives = if config.hash {
+        quote! { #[derive(PartialEq, Eq, Hash)] }
+    } else {
+        quote! {}
+    };
+
+    // Raw bodies can use this helper to write `fmt::Arguments` into a string.
+    // The user has to be able to refer to it, so it's exported.
+    let render_ident = Ident::exported("render");
+
+    // Creates a dictionary with the default region of the given language.
+    let new_lang_ident = Ident::exported("new_lang");
+    let language_ident = Ident::exported("Language");
+    let from_language_ident = Ident::exported("from_language");
+
+    Ok(quote! {
+        $sub_modules
+
+        #[allow(non_camel_case_types)]
+        #[allow(dead_code)]
+        $derives
+        $vis struct $ty_name {
+            locale: $locale_ident,
+            $ctx_fields
+            $sub_module_fields
+            $cache_fields
+        }
+
+        impl $ty_name {
+            pub $const_kw fn new(locale: $locale_ident $ctx_params) -> Self {
+                Self {
+                    locale,
+                    $sub_module_field_inits
+                    $ctx_field_inits
+                    $cache_inits
+                }
+            }
+
+            pub fn $new_lang_ident(language: $language_ident $ctx_params2) -> Self {
+                Self::new($locale_ident::$from_language_ident(language) $ctx_args2)
+            }
+
+            // Renders the given format arguments into a `String`. This is
+            // useful in raw bodies: `{ self.render(format_args!(...)) }`.
+            #[allow(dead_code)]
+            fn $render_ident(&self, args: ::std::fmt::Arguments) -> String {
+                ::std::fmt::format(args)
+            }
+
+            #[allow(deprecated)]
+            pub fn $as_map_ident(&self)
+                -> ::std::collections::BTreeMap<&'static str, String>
+            {
+                #[allow(unused_mut)]
+                let mut map = ::std::collections::BTreeMap::new();
+                $map_inserts
+                $sub_module_maps
+                map
+            }
+
+            $methods
+        }
+    })
+}
+
+/// Takes one translation unit and generates the corresponding Rust code.
+fn gen_trans_unit(
+    unit: ast::TransUnit,
+    locale: &ast::LocaleDef,
+    config: &ast::Config,
+    context: &[ast::UnitParam],
+) -> Result<TokenStream> {
+    // ===== Function signature ==============================================
+    // We want to make the name of the translation unit available to the user.
+    let fn_name = unit.method_name();
+
+    let generics = gen_unit_generics(&unit);
+
+    // Units without parameters returning a string get a method returning
+    // the translations of all locales (e.g. `fav_color_all()`).
+    let has_all_fn = unit.params.as_ref().map(|p| p.is_empty()).unwrap_or(true)
+        && unit.return_type.is_none()
+        && !unit.is_async;
+
+    // Positional placeholders (`{}`) refer to the parameters in order.
+    let param_names = unit.params.iter().flat_map(|v| v).map(|p| p.name).collect::<Vec<_>>();
+
+    // Units with parameters get a method returning a closure which takes
+    // those parameters (e.g. `greet_fn()`), useful for callbacks.
+    let fn_fn = if param_names.is_empty() || unit.is_async {
+        quote! {}
+    } else {
+        gen_partial_fn(&unit)
+    };
+    let deprecated = gen_deprecated(&unit);
+    let renderable = is_renderable(&unit);
+    let cache_params = cache_key_params(&unit);
+
+    // Generate code for all parameters, merging all together into one
+    // token stream.
+    let params: TokenStream = unit.params.into_iter().flat_map(|v| v).map(|param| {
+        let name = param.name;
+
+        // We store the type as a simple `String` in the AST so we need to
+        // parse it to a token stream. We know that it can be parsed
+        // correctly, since we create the string from a token stream.
+        let ty = gen_param_type(&param);
+
+        quote! {
+            , $name: $ty
+        }
+    }).collect();
+
+
+    // Units marked as `const` return a `&'static str` and can be evaluated at
+    // compile time.
+    let is_const = unit.is_const;
+    let const_kw = if is_const { quote! { const } } else { quote! {} };
+
+    // The context values can be used in all units by name, so they are bound
+    // to references to the fields of the `Dict`. `const fn`s can't contain
+    // `let` statements, so `const` units can't use the context.
+    let ctx_bindings = if is_const {
+        quote! {}
+    } else {
+        context.iter().map(|param| {
+            let name = param.name;
+            quote! {
+                #[allow(unused_variables)]
+                let $name = &self.$name;
+            }
+        }).collect::<TokenStream>()
+    };
+
+    let return_type = match unit.return_type {
+        Some(ref ty) => ty.0.parse::<TokenStream>().unwrap(),
+        None if is_const => quote! { &'static str },
+        None => quote! { String },
+    };
+
+    // Units marked with `#[async]` return a boxed future of the value. The
+    // values of string arms are computed right away and wrapped in a future
+    // which is ready immediately.
+    let is_async = unit.is_async;
+    let return_type = if is_async {
+        let krate = config.crate_path();
+        quote! { $krate::UnitFuture<$return_type> }
+    } else {
+        return_type
+    };
+    let ready = |value: TokenStream| {
+        if is_async {
+            let krate = config.crate_path();
+            quote! { Box::new($krate::futures::future::ok::<_, $krate::AsyncError>($value)) }
+        } else {
+            value
+        }
+    };
+
+    // Units returning a string additionally get a method which writes into a
+    // `fmt::Formatter` (e.g. `greet_fmt()`). This is useful to implement
+    // `Display` without allocating.
+    let has_fmt_fn = unit.return_type.is_none() && !is_async;
+    let fmt_fn_name = Ident::exported(&format!("{}_fmt", fn_name));
+    let formatter = Ident::internal("__mauzi_f");
+    let format_macro = config.format_macro.as_ref().map(|m| Ident::exported(m));
+
+    // ===== Function body ===================================================
+    // Here we store which variants of the enum were already tested to check
+    // if the match is exhaustive.
+    let mut usage = PatternUsage::new(locale);
+
+    let returns_result = unit.return_type.as_ref()
+        .map(|ty| ty.is_string_result())
+        .unwrap_or(false);
+
+    // Generate a match arm for each translation arm, for both methods.
+    let mut match_arms = Vec::new();
+    let mut fmt_match_arms = Vec::new();
+
+    // Bodies read from files (`file("...")`) are already inlined. To rebuild
+    // the crate when such a file changes, we additionally include it via
+    // `include_str!`, which registers it as dependency.
+    let file_deps = unit.body.arms.iter()
+        .filter_map(|arm| arm.source_file.as_ref())
+        .map(|path| {
+            let path = TokenNode::Literal(Literal::string(&path.display().to_string()));
+            quote! {{
+                #[allow(dead_code)]
+                const _MAUZI_FILE: &'static str = include_str!($path);
+            }}
+        })
+        .collect::<TokenStream>();
+
+    // The first arm with a `#[cfg]` attribute, if any.
+    let first_cfg = unit.body.arms.iter()
+        .filter_map(|arm| arm.cfgs.first())
+        .map(|cfg| cfg.span)
+        .next();
+
+    // Arms with a count condition (`De, count == 1 => ...`) are tried before
+    // all other arms. Each of them needs an arm without condition handling
+    // the same locales, which is checked after all arms are processed.
+    let mut arms = unit.body.arms;
+    arms.sort_by_key(|arm| !arm.is_count_condition);
+    let count_arms = arms.iter()
+        .filter(|arm| arm.is_count_condition)
+        .map(|arm| (arm.pattern.clone(), arm.guard.as_ref().unwrap().span))
+        .collect::<Vec<_>>();
+
+    // In pseudo mode, `Locale::Pseudo` gets an arm first, showing the string
+    // of the base language with accented letters and markers. Units whose
+    // base language arm isn't a simple string are handled like any other
+    // locale.
+    let pseudo_str = arms.iter()
+        .filter(|arm| config.pseudo && arm.guard.is_none() && arm.cfgs.is_empty())
+        .find(|arm| is_base_pattern(&arm.pattern, locale))
+        .and_then(|arm| match arm.body.obj {
+            ast::ArmBody::Str(ref s) => Some(Spanned::new(s.clone(), arm.body.span)),
+            ast::ArmBody::Raw(_) => None,
+        });
+    if let Some(s) = pseudo_str {
+        let pseudo = Ident::exported("Pseudo");
+        usage.use_lang(pseudo)?;
+
+        let delims = unit.placeholders.as_ref();
+        let body = Spanned::new(ast::ArmBody::Str(pseudo_localize(&s.obj, delims)), s.span);
+        let (body, fmt_body) = if is_const {
+            let s = gen_const_arm_body(body, None)?;
+            (s.clone(), quote! { $formatter.write_str($s) })
+        } else {
+            let number_locale = quote! { self.locale };
+            (
+                gen_arm_body(body.clone(), None, &param_names, None, &number_locale, format_macro)?,
+                gen_arm_body(
+                    body, None, &param_names, Some(formatter), &number_locale, format_macro,
+                )?,
+            )
+        };
+        let body = if returns_result { quote! { Ok($body) } } else { body };
+        let body = ready(body);
+
+        let locale_ident = locale.name();
+        match_arms.push(quote! { $locale_ident::$pseudo => { $body } });
+        fmt_match_arms.push(quote! { $locale_ident::$pseudo => { $fmt_body } });
+    }
+
+    // The bodies of the arm handling the base language, used as fallback.
+    let mut base_bodies = None;
+    for arm in arms {
+        let is_conditional = arm.guard.is_some() || !arm.cfgs.is_empty();
+        let is_base = !is_conditional && is_base_pattern(&arm.pattern, locale);
+        // Generate the *matcher* (the left part of a match arm). An arm with
+        // a guard or a `#[cfg]` doesn't necessarily match, so it must not
+        // count towards exhausting the match. We still check that it's
+        // reachable.
+        let (pattern, bindings) = if is_conditional {
+            gen_arm_pattern(arm.pattern, &mut usage.clone(), locale)?
+        } else {
+            gen_arm_pattern(arm.pattern, &mut usage, locale)?
+        };
+        let cfgs = arm.cfgs.into_iter().map(|cfg| {
+            let cfg = cfg.obj;
+            quote! { #[$cfg] }
+        }).collect::<TokenStream>();
+        let guard = match arm.guard {
+            Some(guard) => {
+                let guard = guard.obj;
+                quote! { if $guard }
+            }
+            None => quote! {},
+        };
+
+        // Numbers are formatted according to the locale, unless the arm
+        // specifies another language via `#[format_as = "..."]`.
+        let number_locale = match arm.format_as {
+            Some(lang) => {
+                let locale_ident = locale.name();
+                let language = Ident::exported("Language");
+                let from_language = Ident::exported("from_language");
+                let lang = Ident::exported(&lang.obj);
+                quote! { $locale_ident::$from_language($language::$lang) }
+            }
+            None => quote! { self.locale },
+        };
+
+        // Generate the body of the match arm. String bodies of units returning
+        // `Result<String, _>` are wrapped in `Ok`.
+        let delims = unit.placeholders.as_ref();
+        let is_raw = arm.body.obj.is_raw_block();
+        let wrap_ok = returns_result && !is_raw;
+        let (body, fmt_body) = if is_const {
+            let s = gen_const_arm_body(arm.body, delims)?;
+            (s.clone(), quote! { $formatter.write_str($s) })
+        } else {
+            (
+                gen_arm_body(
+                    arm.body.clone(), delims, &param_names, None, &number_locale, format_macro,
+                )?,
+                gen_arm_body(
+                    arm.body, delims, &param_names, Some(formatter), &number_locale, format_macro,
+                )?,
+            )
+        };
+        let body = if wrap_ok { quote! { Ok($body) } } else { body };
+
+        // The value of a raw body is bound to a variable of the return type,
+        // so that a wrong type is reported at the arm instead of at the
+        // whole `match`. In `#[async]` units, raw bodies can return anything
+        // convertible into a future, whose error is converted into the
+        // `AsyncError` of the runtime crate.
+        let body = if is_async && is_raw {
+            let krate = config.crate_path();
+            let krate2 = krate.clone();
+            let krate3 = krate.clone();
+            let value = Ident::internal("__mauzi_value");
+            let return_type = return_type.clone();
+            quote! {
+                let $value: $return_type = Box::new($krate::futures::Future::map_err(
+                    $krate2::futures::IntoFuture::into_future({ $body }),
+                    <$krate3::AsyncError as ::std::convert::From<_>>::from,
+                ));
+                $value
+            }
+        } else if is_async {
+            ready(body)
+        } else if is_raw {
+            let value = Ident::internal("__mauzi_value");
+            let return_type = return_type.clone();
+            quote! {
+                let $value: $return_type = { $body };
+                $value
+            }
+        } else {
+            body
+        };
+
+        if is_base && base_bodies.is_none() {
+            base_bodies = Some((body.clone(), fmt_body.clone()));
+        }
+
+        // Combine both into the full match arm
+        let (fmt_pattern, fmt_bindings) = (pattern.clone(), bindings.clone());
+        let (fmt_guard, fmt_cfgs) = (guard.clone(), cfgs.clone());
+        match_arms.push(quote! {
+            $cfgs $pattern $guard => { $bindings $body }
+        });
+        fmt_match_arms.push(quote! {
+            $fmt_cfgs $fmt_pattern $fmt_guard => { $fmt_bindings $fmt_body }
+        });
+    }
+
+    // If the pattern of an arm with count condition can still be used, not
+    // all of its locales have an arm without condition.
+    for (pattern, span) in count_arms {
+        let desc = pattern.to_string();
+        if gen_arm_pattern(pattern, &mut usage.clone(), locale).is_ok() {
+            return Err(span
+                .error(format!(
+                    "the arm '{}' of translation unit '{}' has a count condition, but there is \
+                        no arm without condition for all of its locales",
+                    desc,
+                    unit.name,
+                ))
+                .help(format!("add an arm `{} => ...` for all other counts", desc)));
+        }
+    }
+
+    // Whether the arms with `#[cfg]` exist depends on the features, so the
+    // other arms have to handle all locales on their own. Wildcard arms are
+    // forbidden in strict mode, so we can't suggest one there.
+    if let Some(cfg_span) = first_cfg {
+        if !usage.is_exhausted() {
+            let help = if config.strict {
+                format!("add arms without `#[cfg]` for {}", usage.missing().join(", "))
+            } else {
+                "add a wildcard arm `_ => ...` at the end".to_string()
+            };
+            return Err(unit.name.diag_span()
+                .error(format!(
+                    "translation unit '{}' has arms with `#[cfg]`, so the other arms have to \
+                        handle all locales",
+                    unit.name,
+                ))
+                .span_note(cfg_span, "conditional arm here")
+                .help(help));
+        }
+    }
+    let match_arms = match_arms.into_iter().collect::<TokenStream>();
+    let fmt_match_arms = fmt_match_arms.into_iter().collect::<TokenStream>();
+
+    // Handling only some regions of a language (e.g. only `En(Gb)`) is
+    // probably a mistake, so we warn about it. In strict mode, this is an
+    // error below anyway.
+    if !config.strict {
+        for (lang, regions) in usage.partially_covered_langs() {
+            let missing = regions.iter()
+                .map(|region| format!("{}({})", lang, region))
+                .collect::<Vec<_>>()
+                .join(", ");
+            unit.name.diag_span()
+                .warning(format!(
+                    "translation unit '{}' only handles some regions of `{}`",
+                    unit.name,
+                    lang,
+                ))
+                .note(format!("missing: {}", missing))
+                .help(format!("add an arm for `{}` to handle all remaining regions", lang))
+                .emit();
+        }
+    }
+
+    // If the user didn't provide a wildcard arm, we need to add one. It uses
+    // the translation of the base language, if there is one. In strict mode,
+    // this is an error instead.
+    // With `#[mauzi(on_missing = "log")]`, using a missing translation is
+    // reported to the hook of the runtime crate. `const fn`s can't call it.
+    let report = if config.log_missing && !is_const {
+        let krate = config.crate_path();
+        let name = TokenNode::Literal(Literal::string(unit.name.as_str()));
+        quote! { $krate::report_missing($name, &format!("{:?}", self.locale)); }
+    } else {
+        quote! {}
+    };
+    let fmt_report = report.clone();
+
+    if !usage.is_exhausted() && !config.strict {
+        let missing = reported_missing(usage.missing());
+        if !missing.is_empty() {
+            unit.name.diag_span()
+                .warning(format!("translation unit '{}' has missing translations", unit.name))
+                .note(format!("missing: {}", missing.join(", ")))
+                .note(format!("reported because `{}` is set", REPORT_MISSING_VAR))
+                .emit();
+        }
+    }
+
+    let mut fmt_wildcard_arm = quote! {};
+    let wildcard_arm = if usage.is_exhausted() {
+        quote! {}
+    } else if config.strict {
+        let msg = format!(
+            "translation unit '{}' doesn't handle all locales (required in strict mode)",
+            unit.name
+        );
+        let missing = format!("missing: {}", usage.missing().join(", "));
+        return Err(unit.name.diag_span().error(msg).note(missing));
+    } else if let Some((body, fmt_body)) = base_bodies {
+        // Missing translations fall back to the base language.
+        fmt_wildcard_arm = quote! {
+            _ => { $fmt_report $fmt_body }
+        };
+        quote! {
+            _ => { $report $body }
+        }
+    } else {
+        let msg = format!("[[MISSING TRANSLATION FOR '{}']]", unit.name.as_str());
+        let msg = TokenNode::Literal(Literal::string(&msg));
+
+        let fmt_msg = msg.clone();
+        fmt_wildcard_arm = quote! {
+            _ => { $fmt_report $formatter.write_str($fmt_msg) }
+        };
+
+        match unit.return_type {
+            // Fallible units return the message as successful result, just
+            // like units returning `String`.
+            Some(ref ty) if ty.is_string_result() => {
+                let value = ready(quote! { Ok($msg.into()) });
+                quote! {
+                    _ => { $report $value }
+                }
+            }
+            // For any other custom return type, we can't create a value.
+            Some(_) => quote! {
+                _ => panic!($msg),
+            },
+            None if is_const => quote! {
+                _ => $msg,
+            },
+            None => {
+                let value = ready(quote! { $msg.into() });
+                quote! {
+                    _ => { $report $value }
+                }
+            }
+        }
+    };
+
+    let fmt_fn = if has_fmt_fn {
+        let (deprecated, generics, params) = (deprecated.clone(), generics.clone(), params.clone());
+        let ctx_bindings = ctx_bindings.clone();
+        quote! {
+            $deprecated
+            #[allow(unreachable_patterns)]
+            pub fn $fmt_fn_name $generics (
+                &self,
+                $formatter: &mut ::std::fmt::Formatter
+                $params
+            ) -> ::std::fmt::Result {
+                $ctx_bindings
+                match self.locale {
+                    $fmt_match_arms
+                    $fmt_wildcard_arm
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Each unit gets a method returning all locales it has translations for
+    // (e.g. `greet_available_locales()`). Neither the fallback arm nor
+    // wildcard arms written by the user count.
+    let available_fn_name = Ident::exported(&format!("{}_available_locales", fn_name));
+    let locale_ident = locale.name();
+    let available_locales = usage.covered().into_iter().map(|(lang, region)| {
+        match region {
+            Some(region) => {
+                let region_ty = region_ty_name(locale.get_lang(&lang).unwrap());
+                quote! { $locale_ident::$lang($region_ty::$region), }
+            }
+            None => quote! { $locale_ident::$lang, },
+        }
+    }).collect::<TokenStream>();
+    let available_deprecated = deprecated.clone();
+
+    // Each (non-const) unit gets a method returning an error instead of the
+    // fallback for missing translations (e.g. `try_greet()`).
+    let try_fn = if is_const {
+        quote! {}
+    } else {
+        let try_fn_name = Ident::exported(&format!("try_{}", fn_name));
+        let krate = config.crate_path();
+        let krate2 = krate.clone();
+        let name = TokenNode::Literal(Literal::string(unit.name.as_str()));
+        let args = param_names.iter().map(|&name| quote! { $name, }).collect::<TokenStream>();
+        let covered = usage.covered().into_iter().map(|(lang, region)| {
+            match region {
+                Some(region) => {
+                    let region_ty = region_ty_name(locale.get_lang(&lang).unwrap());
+                    quote! { | $locale_ident::$lang($region_ty::$region) }
+                }
+                None => quote! { | $locale_ident::$lang },
+            }
+        }).collect::<Vec<_>>();
+
+        let body = if usage.is_exhausted() {
+            quote! { Ok(self.$fn_name($args)) }
+        } else {
+            let missing = quote! {
+                Err($krate::MauziError::MissingTranslation { unit: $name, locale: self.locale })
+            };
+            if covered.is_empty() {
+                missing
+            } else {
+                // The leading `|` of the first pattern is removed.
+                let covered = covered.into_iter().collect::<TokenStream>()
+                    .into_iter().skip(1).collect::<TokenStream>();
+                quote! {
+                    match self.locale {
+                        $covered => Ok(self.$fn_name($args)),
+                        _ => $missing,
+                    }
+                }
+            }
+        };
+
+        let (deprecated, generics, params) = (deprecated.clone(), generics.clone(), params.clone());
+        let return_type = return_type.clone();
+        quote! {
+            $deprecated
+            #[allow(deprecated, unreachable_patterns)]
+            pub fn $try_fn_name $generics (&self $params)
+                -> Result<$return_type, $krate2::MauziError<$locale_ident>>
+            {
+                $body
+            }
+        }
+    };
+
+    let all_fn = if has_all_fn {
+        let all_fn_name = Ident::exported(&format!("{}_all", fn_name));
+        let all = Ident::exported("all");
+        let deprecated = deprecated.clone();
+        let ctx_clones = context.iter().map(|param| {
+            let name = param.name;
+            quote! { , self.$name.clone() }
+        }).collect::<TokenStream>();
+        quote! {
+            $deprecated
+            #[allow(deprecated)]
+            pub fn $all_fn_name(&self) -> Vec<($locale_ident, String)> {
+                $locale_ident::$all().iter()
+                    .map(|&locale| (locale, Self::new(locale $ctx_clones).$fn_name().into()))
+                    .collect()
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Units with parameters which can be rendered dynamically get a hidden
+    // method taking the arguments as a slice. Units without parameters are
+    // called directly.
+    let render_fn = if renderable && !param_names.is_empty() {
+        let (match_arms, wildcard_arm) = (match_arms.clone(), wildcard_arm.clone());
+        gen_render_fn(fn_name, &param_names, quote! { $match_arms $wildcard_arm })
+    } else {
+        quote! {}
+    };
+
+    let body = quote! {
+        match self.locale {
+            $match_arms
+            $wildcard_arm
+        }
+    };
+
+    // Units with `#[cache]` store their results in a field of the `Dict`,
+    // which only has one locale. Units with parameters use a map from the
+    // hash of the arguments to all keys with that hash, so that a cache hit
+    // doesn't need to allocate. The body is wrapped in a closure, so that
+    // `return`s in raw bodies still work.
+    let body = if unit.cache {
+        let field = cache_field_name(&fn_name);
+        let return_type = return_type.clone();
+
+        if cache_params.is_empty() {
+            quote! {
+                if let Some(ref value) = *self.$field.lock() {
+                    return value.clone();
+                }
+
+                let value = (|| -> $return_type { $body })();
+                *self.$field.lock() = Some(value.clone());
+                value
+            }
+        } else {
+            let key = Ident::internal("__mauzi_key");
+            let hash = Ident::internal("__mauzi_hash");
+            let hasher = Ident::internal("__mauzi_hasher");
+            let hash_args = cache_params.iter().map(|&(name, _, _)| {
+                quote! { ::std::hash::Hash::hash(&$name, &mut $hasher); }
+            }).collect::<TokenStream>();
+            let eqs = cache_params.iter().enumerate().map(|(i, &(name, ref ty, is_ref))| {
+                let i = i.to_string().parse::<TokenStream>().unwrap();
+                if is_ref {
+                    let ty = ty.parse::<TokenStream>().unwrap();
+                    quote! { && ::std::borrow::Borrow::<$ty>::borrow(&$key.$i) == $name }
+                } else {
+                    quote! { && $key.$i == $name }
+                }
+            }).collect::<TokenStream>();
+            let new_key = cache_params.iter().map(|&(name, _, is_ref)| {
+                if is_ref {
+                    quote! { ::std::borrow::ToOwned::to_owned($name), }
+                } else {
+                    quote! { $name.clone(), }
+                }
+            }).collect::<TokenStream>();
+            quote! {
+                let $hash = {
+                    let mut $hasher = ::std::collections::hash_map::DefaultHasher::new();
+                    $hash_args
+                    ::std::hash::Hasher::finish(&$hasher)
+                };
+                if let Some(bucket) = self.$field.lock().get(&$hash) {
+                    for &(ref $key, ref value) in bucket {
+                        if true $eqs {
+                            return value.clone();
+                        }
+                    }
+                }
+
+                let $key = ($new_key);
+                let value = (|| -> $return_type { $body })();
+                self.$field.lock()
+                    .entry($hash)
+                    .or_insert_with(Vec::new)
+                    .push(($key, value.clone()));
+                value
+            }
+        }
+    } else {
+        body
+    };
+
+    // Combine everything into the methods. An arm with `#[cfg]` can be
+    // followed by an arm with the same pattern for the other case, which is
+    // unreachable if the first one is compiled in. Attributes on the `match`
+    // itself are unstable, so the lint is allowed for the methods.
+    Ok(quote! {
+        $deprecated
+        #[allow(unreachable_patterns)]
+        pub $const_kw fn $fn_name $generics (&self $params) -> $return_type {
+            $ctx_bindings
+            $file_deps
+            $body
+        }
+
+        $fmt_fn
+
+        $available_deprecated
+        pub fn $available_fn_name(&self) -> Vec<$locale_ident> {
+            vec![$available_locales]
+        }
+
+        $all_fn
+
+        $fn_fn
+
+        $try_fn
+
+        $render_fn
     })
 }
 
-/// Simple helper to generate the name of the region type, e.g. `EnRegion`.
-fn region_ty_name(lang_name: &str) -> Ident {
-    Ident::exported(&format!("{}Region", lang_name))
+/// Returns `true` if missing translations should be reported, i.e. if
+/// `MAUZI_REPORT_MISSING` is set to anything but an empty string or `0`.
+fn report_missing_enabled() -> bool {
+    match ::std::env::var(REPORT_MISSING_VAR) {
+        Ok(value) => value != "" && value != "0",
+        Err(_) => false,
+    }
 }
 
-/// Generates the code for the given module and all of its submodules.
-///
-/// Each module has its own `Dict` type. However, sadly, mauzi-modules don't
-/// map to Rust modules. When I worked on this, emitting Rust-modules was a
-/// pain due to strange visibility effects.
-///
-/// Instead, each `Dict` type gets an ugly prefix. This means that everything
-/// lives in one module, and most of the types have super strange names.
-fn gen_module(
-    sub_modules: Vec<ast::Mod>,
-    trans_units: Vec<ast::TransUnit>,
-    locale: &ast::LocaleDef,
-    stem: &str,
-) -> Result<TokenStream> {
-    let locale_ident = locale.name();
+/// Returns the locales of `missing` which should be reported as a warning.
+/// That's none if reporting is disabled. The pseudo locale is generated, so
+/// it never needs translations.
+fn reported_missing(missing: Vec<String>) -> Vec<String> {
+    if !report_missing_enabled() {
+        return Vec::new();
+    }
 
-    // We generate the token streams for all sub modules and combine them into
-    // a big stream.
-    let mut sub_module_names = Vec::new();
-    let sub_modules = sub_modules.into_iter().map(|sub| {
-        // Generate new prefix and type name ...
-        let new_stem = format!("{}{}___this_is_a_bad_solution___", stem, sub.name.as_str());
-        let ty_name = Ident::internal(&format!("{}Dict", new_stem));
+    missing.into_iter().filter(|locale| locale != "Pseudo").collect()
+}
 
-        sub_module_names.push((sub.name, ty_name));
-        gen_module(sub.modules, sub.trans_units, locale, &new_stem)
-    }).collect::<Result<TokenStream>>()?;
+/// Generates the method of a unit returning a closure which captures `self`
+/// and takes the unit's parameters, e.g. `greet_fn()`.
+///
+/// We return a boxed closure, as `impl Trait` requires a feature gate in the
+/// user's crate.
+fn gen_partial_fn(unit: &ast::TransUnit) -> TokenStream {
+    let fn_name = unit.method_name();
+    let fn_fn_name = Ident::exported(&format!("{}_fn", fn_name));
 
-    // The fields for submodules in our `Dict` definition
-    let sub_module_fields = sub_module_names.iter().map(|&(name, ty_name)| {
-        quote! { pub $name: $ty_name , }
+    let params = unit.params.iter().flat_map(|v| v).collect::<Vec<_>>();
+    let arg_types = params.iter().map(|param| {
+        let ty = gen_param_type(param);
+        quote! { $ty, }
     }).collect::<TokenStream>();
-
-    // The initializer list of the submodules in our `Dict::new()` method
-    let sub_module_field_inits = sub_module_names.iter().map(|&(name, ty_name)| {
-        quote! { $name: $ty_name::new(locale), }
+    let closure_params = params.iter().map(|param| {
+        let name = param.name;
+        let ty = gen_param_type(param);
+        quote! { $name: $ty, }
+    }).collect::<TokenStream>();
+    let args = params.iter().map(|param| {
+        let name = param.name;
+        quote! { $name, }
     }).collect::<TokenStream>();
 
-    // We generate the token streams for all methods and combine them into a
-    // big token stream.
-    let methods = trans_units.into_iter()
-        .map(|unit| gen_trans_unit(unit, locale))
-        .collect::<Result<TokenStream>>()?;
+    let return_type = match unit.return_type {
+        Some(ref ty) => ty.0.parse::<TokenStream>().unwrap(),
+        None if unit.is_const => quote! { &'static str },
+        None => quote! { String },
+    };
 
-    // Our type name.
-    let ty_name = Ident::internal(&format!("{}Dict", stem));
+    // The closure borrows `self`, so we need a named lifetime for it. It's
+    // added to the lifetimes of the parameters. The closure's type contains
+    // the type parameters, so those have to outlive it, too.
+    let mut generics = unit_lifetimes(unit);
+    generics.insert(0, "'__mauzi_self".into());
+    generics.extend(unit_type_params(unit, " + '__mauzi_self"));
+    let generics = generics.join(", ").parse::<TokenStream>().unwrap();
+    let self_lt = "'__mauzi_self".parse::<TokenStream>().unwrap();
+    let self_lt2 = self_lt.clone();
 
-    Ok(quote! {
-        $sub_modules
+    let deprecated = gen_deprecated(unit);
 
-        #[allow(non_camel_case_types)]
-        #[allow(dead_code)]
-        pub struct $ty_name {
-            locale: $locale_ident,
-            $sub_module_fields
+    quote! {
+        $deprecated
+        #[allow(deprecated)]
+        pub fn $fn_fn_name < $generics > (&$self_lt self)
+            -> Box<Fn($arg_types) -> $return_type + $self_lt2>
+        {
+            Box::new(move |$closure_params| self.$fn_name($args))
         }
+    }
+}
 
-        impl $ty_name {
-            pub fn new(locale: $locale_ident) -> Self {
-                Self {
-                    locale,
-                    $sub_module_field_inits
-                }
-            }
+/// Returns `true` if the unit can be rendered with arguments given as
+/// `&[&Display]` (see `Dict::render_by_key()`). This is the case for units
+/// returning a `String` whose bodies are strings only using the parameters
+/// themselves in placeholders: anything else (a method call, a guard
+/// comparing a parameter, ...) would need the actual parameter type.
+fn is_renderable(unit: &ast::TransUnit) -> bool {
+    if unit.is_const || unit.cache || unit.is_async || unit.return_type.is_some() {
+        return false;
+    }
 
-            $methods
+    let params = unit.params.iter()
+        .flat_map(|v| v)
+        .map(|p| p.name.as_str().to_string())
+        .collect::<Vec<_>>();
+    let simple_placeholder = |arg_s: &str| {
+        let expr = number_placeholder(arg_s).unwrap_or(arg_s.trim());
+
+        expr.is_empty()
+            || expr.parse::<usize>().is_ok()
+            || env_placeholder(arg_s).is_some()
+            || params.iter().any(|p| p == expr)
+    };
+
+    unit.body.arms.iter().all(|arm| {
+        arm.guard.is_none() && match arm.body.obj {
+            ast::ArmBody::Str(ref s) => {
+                let (_, args) = split_format_str(s, unit.placeholders.as_ref());
+                args.iter().all(|arg| simple_placeholder(arg))
+            }
+            ast::ArmBody::Raw(_) => false,
         }
     })
 }
 
-/// Takes one translation unit and generates the corresponding Rust code.
-fn gen_trans_unit(unit: ast::TransUnit, locale: &ast::LocaleDef) -> Result<TokenStream> {
-    // ===== Function signature ==============================================
-    // We want to make the name of the translation unit available to the user.
-    let fn_name = unit.name;
+/// Generates the hidden method rendering a unit with the arguments given as
+/// `&[&Display]` (e.g. `__mauzi_render_greet()`), which is called by
+/// `Dict::render_by_key()`. The parameters are bound to the arguments in
+/// order, so the arms can be used unchanged. The number of arguments is
+/// checked by the caller.
+fn gen_render_fn(fn_name: Ident, params: &[Ident], arms: TokenStream) -> TokenStream {
+    let render_fn_name = Ident::exported(&format!("__mauzi_render_{}", fn_name));
+    let bindings = params.iter().enumerate().map(|(i, &name)| {
+        let i = i.to_string().parse::<TokenStream>().unwrap();
+        quote! { let $name: &::std::fmt::Display = args[$i]; }
+    }).collect::<TokenStream>();
 
-    // Generate code for all parameters, merging all together into one
-    // token stream.
-    let params: TokenStream = unit.params.into_iter().flat_map(|v| v).map(|param| {
-        let name = param.name;
+    quote! {
+        #[doc(hidden)]
+        #[allow(deprecated, unreachable_patterns, unused_variables)]
+        pub fn $render_fn_name(&self, args: &[&::std::fmt::Display]) -> String {
+            $bindings
+            match self.locale {
+                $arms
+            }
+        }
+    }
+}
 
-        // We store the type as a simple `String` in the AST so we need to
-        // parse it to a token stream. We know that it can be parsed
-        // correctly, since we create the string from a token stream.
-        let ty = param.ty.0.parse::<TokenStream>().unwrap();
+/// Returns the name of the `Dict` field storing the results of the
+/// `#[cache]` unit with the given method name.
+fn cache_field_name(method_name: &Ident) -> Ident {
+    Ident::internal(&format!("__mauzi_cache_{}", method_name))
+}
 
-        quote! {
-            , $name: $ty
+/// Returns the parameters of a unit as parts of its cache key: the name, the
+/// type and whether it's a reference. For references (e.g. `&'a str`), the
+/// type is the referenced type (`str`), whose owned version (`String`) is
+/// stored in the key.
+fn cache_key_params(unit: &ast::TransUnit) -> Vec<(Ident, String, bool)> {
+    unit.params.iter().flat_map(|v| v).map(|param| {
+        let ty = param.ty.0.trim();
+        if !ty.starts_with('&') {
+            return (param.name, ty.to_string(), false);
         }
-    }).collect();
 
+        let mut referent = ty[1..].trim_left();
+        if referent.starts_with('\'') {
+            let end = referent.find(char::is_whitespace).unwrap_or(referent.len());
+            referent = referent[end..].trim_left();
+        }
+        if referent.starts_with("mut ") {
+            referent = referent[4..].trim_left();
+        }
+        (param.name, referent.to_string(), true)
+    }).collect()
+}
+
+/// Generates the `Dict` field storing the results of a `#[cache]` unit. The
+/// results of a unit without parameters are stored as `Option`, all others
+/// in a map from the hash of the arguments to the keys with that hash and
+/// their results.
+fn gen_cache_field(unit: &ast::TransUnit, config: &ast::Config) -> (TokenStream, TokenStream) {
+    let krate = config.crate_path();
+    let krate2 = krate.clone();
+    let field = cache_field_name(&unit.method_name());
     let return_type = match unit.return_type {
         Some(ref ty) => ty.0.parse::<TokenStream>().unwrap(),
         None => quote! { String },
     };
 
-    // ===== Function body ===================================================
-    // Here we store which variants of the enum were already tested to check
-    // if the match is exhaustive.
-    let mut usage = PatternUsage::new(locale);
+    let params = cache_key_params(unit);
+    let ty = if params.is_empty() {
+        quote! { Option<$return_type> }
+    } else {
+        let key_types = params.iter().map(|&(_, ref ty, is_ref)| {
+            let ty = ty.parse::<TokenStream>().unwrap();
+            if is_ref {
+                quote! { <$ty as ::std::borrow::ToOwned>::Owned, }
+            } else {
+                quote! { $ty, }
+            }
+        }).collect::<TokenStream>();
+        quote! { ::std::collections::HashMap<u64, Vec<(($key_types), $return_type)>> }
+    };
 
-    // Generate a match arm for each translation arm.
-    let match_arms: TokenStream = unit.body.arms.into_iter().map(|arm| {
-        // Generate the *matcher* (the left part of a match arm).
-        let pattern = gen_arm_pattern(arm.pattern, &mut usage, locale)?;
+    (
+        quote! { $field: $krate::UnitCache<$ty>, },
+        quote! { $field: $krate2::UnitCache::default(), },
+    )
+}
 
-        // Generate the body of the match arm.
-        let body = gen_arm_body(arm.body)?;
+/// Generates the context values as parameters (`, user: Rc<User>`) and as
+/// arguments (`, user`), to be appended after the locale in `new()`.
+fn gen_context_params(context: &[ast::UnitParam]) -> (TokenStream, TokenStream) {
+    let params = context.iter().map(|param| {
+        let name = param.name;
+        let ty = param.ty.0.parse::<TokenStream>().unwrap();
+        quote! { , $name: $ty }
+    }).collect();
+    let args = context.iter().map(|param| {
+        let name = param.name;
+        quote! { , $name }
+    }).collect();
 
-        // Combine both into the full match arm
-        Ok(quote! {
-            $pattern => { $body }
-        })
-    }).collect::<Result<_>>()?;
+    (params, args)
+}
 
-    // If the user didn't provide a wildcard arm, we need to add one.
-    let wildcard_arm = if usage.is_exhausted() {
+/// Generates the generics of the methods of a unit. Parameters can be
+/// references with named lifetimes (e.g. `&'a str`). Those lifetimes have to
+/// be declared on the method. Parameters of type `impl Bound` get a type
+/// parameter, see `unit_type_params`.
+fn gen_unit_generics(unit: &ast::TransUnit) -> TokenStream {
+    let mut generics = unit_lifetimes(unit);
+    generics.extend(unit_type_params(unit, ""));
+    if generics.is_empty() {
         quote! {}
     } else {
-        // TODO: let the user decide what we want to do here. Possibilites:
-        // - panic (should probably be avoided?)
-        // - print debug string (probably very useful during development)
-        // - compile time reror (probably very useful before releasing)
-        let msg = format!("[[MISSING TRANSLATION FOR '{}']]", unit.name.as_str());
-        let msg = TokenNode::Literal(Literal::string(&msg));
+        let generics = generics.join(", ").parse::<TokenStream>().unwrap();
+        quote! { < $generics > }
+    }
+}
 
-        if unit.return_type.is_some() {
-            quote! {
-                _ => panic!($msg),
-            }
-        } else {
-            quote! {
-                _ => $msg.into(),
-            }
-        }
-    };
+/// Returns the type parameters of a unit (e.g. `__MauziTCount: Display`),
+/// one for each parameter of type `impl Bound`. `extra_bound` is appended to
+/// each bound.
+fn unit_type_params(unit: &ast::TransUnit, extra_bound: &str) -> Vec<String> {
+    unit.params.iter()
+        .flat_map(|v| v)
+        .filter_map(|param| param.ty.impl_bound().map(|bound| {
+            format!("{}: {}{}", type_param_name(param), bound, extra_bound)
+        }))
+        .collect()
+}
 
-    // Combine everything into the method.
-    Ok(quote! {
-        pub fn $fn_name(&self $params) -> $return_type {
-            match self.locale {
-                $match_arms
-                $wildcard_arm
+/// Returns the name of the type parameter of a parameter of type
+/// `impl Bound`. It's camel case, so that it doesn't trigger the
+/// `non_camel_case_types` lint in the crate using `mauzi!`.
+fn type_param_name(param: &ast::UnitParam) -> String {
+    format!("__MauziT{}", camel_case(param.name.as_str()))
+}
+
+/// Generates the type of a unit parameter. For parameters of type
+/// `impl Bound`, that's the type parameter declared by `gen_unit_generics`.
+fn gen_param_type(param: &ast::UnitParam) -> TokenStream {
+    match param.ty.impl_bound() {
+        Some(_) => type_param_name(param).parse::<TokenStream>().unwrap(),
+        None => param.ty.0.parse::<TokenStream>().unwrap(),
+    }
+}
+
+/// Returns the named lifetimes used by the parameters of a unit, without
+/// duplicates.
+fn unit_lifetimes(unit: &ast::TransUnit) -> Vec<String> {
+    unit.params.iter()
+        .flat_map(|v| v)
+        .flat_map(|param| param.ty.lifetimes())
+        .fold(Vec::new(), |mut acc, lt| {
+            if !acc.contains(&lt) {
+                acc.push(lt);
             }
+            acc
+        })
+}
+
+/// Generates the `#[deprecated]` attribute of a deprecated unit.
+fn gen_deprecated(unit: &ast::TransUnit) -> TokenStream {
+    match unit.deprecated {
+        None => quote! {},
+        Some(None) => quote! { #[deprecated] },
+        Some(Some(ref note)) => {
+            let note = TokenNode::Literal(Literal::string(note));
+            quote! { #[deprecated(note = $note)] }
         }
-    })
+    }
 }
 
 /// Generates the *matcher* (the left side) of a match arm.
+///
+/// Some patterns bind variables which can't be expressed as Rust pattern.
+/// Those are returned as second token stream containing `let` statements,
+/// which have to be put at the beginning of the arm's body.
 fn gen_arm_pattern(
     pattern: ast::ArmPattern,
     usage: &mut PatternUsage,
     locale: &ast::LocaleDef
-) -> Result<TokenStream> {
+) -> Result<(TokenStream, TokenStream)> {
     let locale_ident = locale.name();
 
     let out = match pattern {
@@ -278,7 +2411,7 @@ fn gen_arm_pattern(
             // binding.
             if let Some(lang) = locale.get_lang(&lang_name) {
                 // It is referring to a variant of the `Locale` enum
-                usage.use_lang(&lang_name)?;
+                usage.use_lang(lang_name)?;
 
                 let lang_ident = lang.name;
                 if lang.has_regions() {
@@ -288,7 +2421,7 @@ fn gen_arm_pattern(
                 }
             } else {
                 // It is a name for a variable binding
-                usage.use_wildcard(lang_name.span().unwrap(), Some(&lang_name))?;
+                usage.use_wildcard(lang_name.diag_span(), Some(&lang_name))?;
 
                 quote! { $lang_name }
             }
@@ -297,116 +2430,163 @@ fn gen_arm_pattern(
         // The user matches against language and region (or at least wants to
         // bind the region to a variable).
         ast::ArmPattern::WithRegion { lang: lang_name, region: region_name } => {
-            // This time, the language has to be a variant of the `Locale`
-            // enum. If not we're gonna emit an error.
+            // If the language is not a variant of the `Locale` enum and both
+            // names are lowercase, both are variable bindings: `lang(region)`
+            // matches every locale. Since the region types differ between
+            // languages, we can't bind the region in the pattern. Instead we
+            // bind the language tag and the optional region tag in the body.
+            // A capitalized name is most likely a typo, e.g. `Fr(Ch)` without
+            // `Fr` in the locale definition.
             let lang = match locale.get_lang(&lang_name) {
                 Some(l) => l,
+                None if !is_binding_name(&lang_name) || !is_binding_name(&region_name) => {
+                    return Err(lang_name.diag_span()
+                        .error(format!("{} is not a valid language!", lang_name))
+                        .help("to match all locales and bind their language and region tags, \
+                            use lowercase names, e.g. `lang(region)`"));
+                }
                 None => {
-                    return err!(
-                        lang_name.span().unwrap(),
-                        "{} is not a valid language!",
-                        lang_name.as_str()
-                    );
+                    let span = lang_name.diag_span();
+                    let binding = format!("{}({})", lang_name, region_name);
+                    usage.use_wildcard(span, Some(&binding))?;
+
+                    let language_tag = Ident::exported("language_tag");
+                    let region_tag = Ident::exported("region_tag");
+                    let bindings = quote! {
+                        let $lang_name = self.locale.$language_tag();
+                        let $region_name = self.locale.$region_tag();
+                    };
+
+                    return Ok((quote! { _ }, bindings));
                 }
             };
 
-            let lang_name = lang.name;
+            // A region group matches all of its regions which weren't
+            // matched by earlier arms, so we generate an or-pattern.
+            if let Some(group) = lang.get_group(&region_name) {
+                let regions = usage.use_group(&lang_name, region_name, &group.regions)?;
+
+                let lang_name = lang.name;
+                let region_ty = region_ty_name(lang);
+                let alternatives = regions.into_iter().enumerate().map(|(i, region)| {
+                    let sep = if i == 0 { quote! {} } else { quote! { | } };
+                    quote! { $sep $locale_ident::$lang_name($region_ty::$region) }
+                }).collect::<TokenStream>();
+
+                return Ok((alternatives, quote! {}));
+            }
+
+            // A language without regions can't be matched with a region.
+            if !lang.has_regions() {
+                return err!(
+                    region_name.diag_span(),
+                    "language `{}` has no regions, so `{}` can't be matched",
+                    lang_name,
+                    region_name
+                );
+            }
 
             // Next we need to again figure out whether the user provided a
             // region constant or a variable name to bind to.
             if lang.contains_region(&region_name) {
                 // Constant region to match against...
-                usage.use_region(&lang_name, &region_name)?;
+                usage.use_region(&lang_name, region_name)?;
 
-                let region_ty = region_ty_name(&lang_name);
+                let lang_name = lang.name;
+                let region_ty = region_ty_name(lang);
                 quote! { $locale_ident::$lang_name($region_ty::$region_name) }
-            } else {
+            } else if is_binding_name(&region_name) {
                 // Variable to bind to
-                usage.use_lang(&lang_name)?;
+                usage.use_lang(lang_name)?;
 
+                let lang_name = lang.name;
                 quote! { $locale_ident::$lang_name($region_name) }
+            } else {
+                return Err(region_name.diag_span()
+                    .error(format!("`{}` is not a region of language `{}`", region_name, lang_name))
+                    .help(format!(
+                        "to bind the region to a variable, use a lowercase name, e.g. `{}(r)`",
+                        lang_name,
+                    )));
             }
         }
     };
 
-    Ok(out)
+    Ok((out, quote! {}))
+}
+
+/// Returns `true` if the pattern matches the base language (or its default
+/// region) without binding anything.
+fn is_base_pattern(pattern: &ast::ArmPattern, locale: &ast::LocaleDef) -> bool {
+    let base = match locale.base_lang() {
+        Some(base) => base,
+        None => return false,
+    };
+
+    match *pattern {
+        ast::ArmPattern::Underscore(_) => false,
+        ast::ArmPattern::Lang(lang) => lang.as_str() == base.name.as_str(),
+        ast::ArmPattern::WithRegion { lang, region } => {
+            lang.as_str() == base.name.as_str()
+                && base.default_region().map(|r| r.name.as_str() == region.as_str())
+                    .unwrap_or(false)
+        }
+    }
 }
 
 /// Generates the body of a match arm.
-fn gen_arm_body(body: Spanned<ast::ArmBody>) -> Result<TokenStream> {
+///
+/// If `formatter` is given, the body writes into that `fmt::Formatter` and
+/// evaluates to `fmt::Result`. Otherwise the body evaluates to a `String`.
+///
+/// Raw bodies are spliced into the method verbatim. They can contain
+/// arbitrary statements, including loops and early `return`s; every `return`
+/// and the trailing expression have to be of the unit's return type. In the
+/// `_fmt` method, the raw body is wrapped in a closure, so that `return`
+/// still produces the `String` instead of returning from the `_fmt` method.
+/// Inside of raw bodies, `self` is the `Dict` of the unit's module, so other
+/// units of the module (and of its submodules) can be called via `self`.
+///
+/// `delims` are the custom placeholder delimiters of the unit, if any.
+/// `params` are the names of the unit's parameters, which are used for
+/// positional (`{}`) and indexed (`{0}`) placeholders. `number_locale` is an
+/// expression evaluating to the `Locale` whose conventions are used for
+/// `{expr:n}` placeholders. `format_macro` is the macro used instead of
+/// `format!()` for string bodies (see `Config::format_macro`).
+fn gen_arm_body(
+    body: Spanned<ast::ArmBody>,
+    delims: Option<&(String, String)>,
+    params: &[Ident],
+    formatter: Option<Ident>,
+    number_locale: &TokenStream,
+    format_macro: Option<Ident>,
+) -> Result<TokenStream> {
     let body_span = body.span;
     match body.obj {
-        ast::ArmBody::Raw(ts) => Ok(ts),
+        ast::ArmBody::Raw(ts) => match formatter {
+            Some(f) => Ok(quote! { $f.write_str(&(|| -> String { $ts })()) }),
+            None => Ok(ts),
+        },
         ast::ArmBody::Str(s) => {
             // We need to convert the fancy placeholder string into a
-            // `format!()` expression. We do this by first going through the
-            // fancy format string with an FSA like algorithm, splitting it
-            // into the real format string and the arguments.
-
-            #[derive(Clone, Copy)]
-            enum State {
-                /// The last char we read belonged to the real format string
-                /// and will be printed verbatim, or (special case) we just
-                /// exited a placeholder.
-                Normal,
-                /// The last char we read was part of a placeholder, or
-                /// (special case) we just entered a placeholder.
-                InPlaceholder,
-            }
-
-            let mut state = State::Normal;
-            let mut it = s.chars().peekable();
-
-            // We will pass `format_str` as the first argument of `format!()`
-            // later. `args` contains all other arguments.
-            let mut format_str = String::new();
-            let mut args = Vec::new();
-
-            while let Some(c) = it.next() {
-                match (state, c) {
-                    // Entering a placeholder
-                    (State::Normal, '{') => {
-                        // If the next one is `{` it's an escaped brace and we
-                        // shall copy both braces verbatim to the format
-                        // string.
-                        if let Some(&'{') = it.peek() {
-                            it.next();
-                            format_str.push_str("{{");
-                        } else {
-                            // Start a new argument and change the state.
-                            args.push(String::new());
-                            state = State::InPlaceholder;
-                        }
-                    }
-                    // Outside of a placeholder, just copying
-                    (State::Normal, _) => {
-                        format_str.push(c);
-                    }
-                    // Exiting a placeholder
-                    (State::InPlaceholder, '}') => {
-                        format_str.push_str("{}");
-                        state = State::Normal;
-                    }
-                    // Inside of a placeholder, copying to the last argument
-                    (State::InPlaceholder, _) => {
-                        args.last_mut().unwrap().push(c);
-                    }
-                }
-            }
+            // `format!()` expression.
+            let (format_str, args) = split_format_str(&s, delims);
 
             // We have to parse all argument as token stream: we don't want to
             // pass them to `format!()` as string literal, but as Rust
             // expression. We concat all arguments into one token stream.
+            // Empty placeholders are replaced by the next parameter. Their
+            // number was already checked in `check`.
+            let mut positional = params.iter();
             let format_args = args.into_iter().map(|arg_s| {
-                // Try to parse.
-                arg_s.parse::<TokenStream>()
-                    .map_err(|e| {
-                        // TODO: we should construct the span of the actual
-                        // argument
-                        body_span.error(
-                            format!("not a valid Rust expression in placeholder: {:?}", e)
-                        )
-                    })
+                if arg_s.trim().is_empty() {
+                    return match positional.next() {
+                        Some(&name) => Ok(quote! { , $name }),
+                        None => err!(body_span, "more positional placeholders than parameters"),
+                    };
+                }
+
+                gen_placeholder(&arg_s, body_span, params, number_locale)
                     // Add a leading comma for concatting all arguments.
                     .map(|ts| quote! { , $ts })
             }).collect::<Result<TokenStream>>()?;
@@ -414,9 +2594,329 @@ fn gen_arm_body(body: Spanned<ast::ArmBody>) -> Result<TokenStream> {
             // We pass the format string as a literal to `format!()`.
             let format_str = TokenNode::Literal(Literal::string(&format_str));
 
-            Ok(quote! {
-                format!($format_str $format_args)
-            })
+            // A custom format macro returns a `String` we write into the
+            // formatter.
+            match (formatter, format_macro) {
+                (Some(f), Some(m)) => Ok(quote! { $f.write_str(&$m!($format_str $format_args)) }),
+                (Some(f), None) => Ok(quote! { write!($f, $format_str $format_args) }),
+                (None, Some(m)) => Ok(quote! { $m!($format_str $format_args) }),
+                (None, None) => Ok(quote! { format!($format_str $format_args) }),
+            }
+        }
+    }
+}
+
+/// Generates the expression of one placeholder which is passed to
+/// `format!()`.
+///
+/// Usually, the placeholder is just a Rust expression. But a placeholder can
+/// also join a list with a separator: `{items | ", "}` is translated to
+/// `items.join(", ")`. We can't check whether the expression actually is a
+/// slice, so we leave it to rustc to complain.
+///
+/// There are a few special forms as well:
+///
+/// - `{@env:NAME}` is replaced by the value of the environment variable
+///   `NAME` at compile time, via `env!("NAME")`.
+/// - `{expr:n}` formats a number according to the locale (see
+///   `mauzi::format_number`).
+/// - `{name?fallback text}` renders an `Option<&str>`, using the fallback
+///   text for `None`. A trailing `?` without text (`{value?}`) is the try
+///   operator instead, used in units returning `Result<String, _>`.
+/// - `{@unit(args)}` calls another unit of the same module and inserts its
+///   translation.
+/// - `{0}` refers to the first of the unit's `params`, so that a parameter
+///   can be used multiple times without naming it.
+fn gen_placeholder(
+    arg_s: &str,
+    body_span: Span,
+    params: &[Ident],
+    number_locale: &TokenStream,
+) -> Result<TokenStream> {
+    // Placeholders like `{@env:CARGO_PKG_VERSION}` refer to environment
+    // variables at compile time. If the variable is not set, `env!` emits
+    // an error.
+    if let Some(var) = env_placeholder(arg_s) {
+        if var.is_empty() {
+            return Err(body_span.error("missing variable name in placeholder")
+                .note(format!("the invalid placeholder is `{{{}}}`", arg_s)));
+        }
+
+        let var = TokenNode::Literal(Literal::string(var));
+        return Ok(quote! { env!($var) });
+    }
+
+    // Placeholders like `{@greet(name)}` call another unit of the same
+    // module, which was already checked in `check`. Since `self` is the
+    // `Dict` of the module, the locale is passed on automatically.
+    if let Some((name, args)) = unit_call_placeholder(arg_s) {
+        let name = Ident::exported(name);
+        let args = args.parse::<TokenStream>().map_err(|e| {
+            body_span.error(format!("not a valid Rust expression in placeholder: {:?}", e))
+                .note(format!("the invalid placeholder is `{{{}}}`", arg_s))
+        })?;
+        return Ok(quote! { self.$name($args) });
+    }
+
+    // Placeholders like `{name?unknown}` show the text after `?` if the
+    // option is `None`.
+    if let Some((expr, fallback)) = fallback_placeholder(arg_s) {
+        let expr = gen_placeholder(expr, body_span, params, number_locale)?;
+        let fallback = TokenNode::Literal(Literal::string(fallback));
+        return Ok(quote! { ($expr).unwrap_or($fallback) });
+    }
+
+    // Placeholders like `{count:n}` format a number with the conventions of
+    // the locale (e.g. `1.234,5` in German). The number is passed by
+    // reference, like the other arguments of `format!`, so that parameters
+    // which aren't `Copy` (e.g. the `impl Display` parameters of units from
+    // CSV files) can be used more than once.
+    if let Some(expr) = number_placeholder(arg_s) {
+        let expr = gen_placeholder(expr, body_span, params, number_locale)?;
+        let number_locale = number_locale.clone();
+        let format_number = Ident::exported("format_number");
+        return Ok(quote! { ($number_locale).$format_number(&($expr)) });
+    }
+
+    // Placeholders like `{0}` refer to a parameter by its index. The index
+    // was already checked in `check`.
+    if let Ok(index) = arg_s.trim().parse::<usize>() {
+        return match params.get(index) {
+            Some(&name) => Ok(name.into()),
+            None => err!(body_span, "parameter index {} out of range", index),
+        };
+    }
+
+    // Try to parse. Since we don't have a span for the placeholder itself, we
+    // show the placeholder as the user wrote it.
+    let ts = arg_s.parse::<TokenStream>()
+        .map_err(|e| {
+            // TODO: we should construct the span of the actual argument
+            body_span.error(format!("not a valid Rust expression in placeholder: {:?}", e))
+                .note(format!("the invalid placeholder is `{{{}}}`", arg_s))
+        })?;
+
+    // Check if the placeholder ends with `| "separator"`.
+    let mut tokens = ts.clone().into_iter().collect::<Vec<_>>();
+    let sep = match tokens.len() {
+        len if len >= 3 => {
+            match (&tokens[len - 2].kind, &tokens[len - 1].kind) {
+                (&TokenNode::Op('|', Spacing::Alone), &TokenNode::Literal(ref lit)) => {
+                    lit.parse_string()
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    match sep {
+        Some(sep) => {
+            tokens.truncate(tokens.len() - 2);
+            let expr = tokens.into_iter().collect::<TokenStream>();
+            let sep = TokenNode::Literal(Literal::string(&sep));
+
+            Ok(quote! { ($expr).join($sep) })
+        }
+        None => Ok(ts),
+    }
+}
+
+/// Generates the body of a match arm of a `const` unit. Those bodies are
+/// simple string literals.
+fn gen_const_arm_body(
+    body: Spanned<ast::ArmBody>,
+    delims: Option<&(String, String)>,
+) -> Result<TokenStream> {
+    let s = match body.obj {
+        ast::ArmBody::Str(s) => s,
+
+        // This is already checked in `check`.
+        ast::ArmBody::Raw(_) => unreachable!(),
+    };
+
+    let (format_str, args) = split_format_str(&s, delims);
+    if !args.is_empty() {
+        return err!(
+            body.span,
+            "units marked with `#[mauzi(const)]` can't use placeholders"
+        );
+    }
+
+    // The string doesn't contain placeholders, so we only need to unescape
+    // the braces.
+    let s = format_str.replace("{{", "{").replace("}}", "}");
+    Ok(TokenNode::Literal(Literal::string(&s)).into())
+}
+
+/// Turns the given string body into its pseudo-localized version: all letters
+/// outside of placeholders are replaced by accented ones and the whole string
+/// is wrapped in markers, e.g. `"Hello {name}"` becomes
+/// `"[!!! Ĥéļļö {name} !!!]"`. The result uses the default delimiters.
+fn pseudo_localize(s: &str, delims: Option<&(String, String)>) -> String {
+    fn accented(c: char) -> char {
+        match c {
+            'a' => 'á', 'c' => 'ç', 'e' => 'é', 'g' => 'ĝ', 'h' => 'ĥ', 'i' => 'í',
+            'l' => 'ļ', 'n' => 'ñ', 'o' => 'ö', 'r' => 'ŕ', 's' => 'š', 'u' => 'ü',
+            'y' => 'ý', 'z' => 'ž',
+            'A' => 'Á', 'C' => 'Ç', 'E' => 'É', 'G' => 'Ĝ', 'H' => 'Ĥ', 'I' => 'Í',
+            'L' => 'Ļ', 'N' => 'Ñ', 'O' => 'Ö', 'R' => 'Ŕ', 'S' => 'Š', 'U' => 'Ü',
+            'Y' => 'Ý', 'Z' => 'Ž',
+            other => other,
+        }
+    }
+
+    let (format_str, args) = split_format_str(s, delims);
+    let mut args = args.into_iter();
+    let mut chars = format_str.chars().peekable();
+    let mut out = String::from("[!!! ");
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().cloned()) {
+            // Escaped braces stay as they are ...
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                out.push(c);
+                out.push(c);
+            }
+            // ... and placeholders are put back in.
+            ('{', Some('}')) => {
+                chars.next();
+                out.push('{');
+                out.push_str(&args.next().unwrap());
+                out.push('}');
+            }
+            _ => out.push(accented(c)),
+        }
+    }
+    out.push_str(" !!!]");
+
+    out
+}
+
+/// Splits the fancy placeholder string into the real format string and the
+/// arguments (still as strings) which are passed to `format!()`.
+///
+/// If `delims` is given, those are used instead of `{` and `}` to delimit
+/// placeholders. In that case, braces are just normal characters.
+///
+/// We do this by going through the fancy format string with an FSA like
+/// algorithm. `check` uses this as well, to count positional placeholders.
+pub fn split_format_str(s: &str, delims: Option<&(String, String)>) -> (String, Vec<String>) {
+    if let Some(&(ref open, ref close)) = delims {
+        return split_format_str_with(s, open, close);
+    }
+
+    #[derive(Clone, Copy)]
+    enum State {
+        /// The last char we read belonged to the real format string and will
+        /// be printed verbatim, or (special case) we just exited a
+        /// placeholder.
+        Normal,
+        /// The last char we read was part of a placeholder, or (special case)
+        /// we just entered a placeholder.
+        InPlaceholder,
+    }
+
+    let mut state = State::Normal;
+    let mut it = s.chars().peekable();
+
+    // `format_str` is the first argument of `format!()`. `args` contains all
+    // other arguments.
+    let mut format_str = String::new();
+    let mut args = Vec::new();
+
+    while let Some(c) = it.next() {
+        match (state, c) {
+            // Entering a placeholder
+            (State::Normal, '{') => {
+                // If the next one is `{` it's an escaped brace and we shall
+                // copy both braces verbatim to the format string.
+                if let Some(&'{') = it.peek() {
+                    it.next();
+                    format_str.push_str("{{");
+                } else {
+                    // Start a new argument and change the state.
+                    args.push(String::new());
+                    state = State::InPlaceholder;
+                }
+            }
+            // Outside of a placeholder, just copying
+            (State::Normal, _) => {
+                format_str.push(c);
+            }
+            // Exiting a placeholder
+            (State::InPlaceholder, '}') => {
+                format_str.push_str("{}");
+                state = State::Normal;
+            }
+            // Inside of a placeholder, copying to the last argument
+            (State::InPlaceholder, _) => {
+                args.last_mut().unwrap().push(c);
+            }
+        }
+    }
+
+    (format_str, args)
+}
+
+/// Like `split_format_str`, but with custom placeholder delimiters. Those
+/// can't be escaped, but braces don't need to be escaped either.
+fn split_format_str_with(s: &str, open: &str, close: &str) -> (String, Vec<String>) {
+    let mut format_str = String::new();
+    let mut args = Vec::new();
+
+    let mut rest = s;
+    while !rest.is_empty() {
+        if rest.starts_with(open) {
+            let after_open = &rest[open.len()..];
+            let (arg, after) = match after_open.find(close) {
+                Some(end) => (&after_open[..end], &after_open[end + close.len()..]),
+
+                // An unclosed placeholder extends to the end of the string,
+                // like with the default delimiters.
+                None => (after_open, ""),
+            };
+
+            format_str.push_str("{}");
+            args.push(arg.to_string());
+            rest = after;
+        } else {
+            let c = rest.chars().next().unwrap();
+            match c {
+                '{' => format_str.push_str("{{"),
+                '}' => format_str.push_str("}}"),
+                c => format_str.push(c),
+            }
+            rest = &rest[c.len_utf8()..];
         }
     }
+
+    (format_str, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missing() -> Vec<String> {
+        vec!["De".to_string(), "En(Us)".to_string(), "Pseudo".to_string()]
+    }
+
+    // All in one test, as the environment is shared between test threads.
+    #[test]
+    fn report_missing() {
+        ::std::env::remove_var(REPORT_MISSING_VAR);
+        assert!(reported_missing(missing()).is_empty());
+
+        ::std::env::set_var(REPORT_MISSING_VAR, "");
+        assert!(reported_missing(missing()).is_empty());
+        ::std::env::set_var(REPORT_MISSING_VAR, "0");
+        assert!(reported_missing(missing()).is_empty());
+
+        ::std::env::set_var(REPORT_MISSING_VAR, "1");
+        assert_eq!(reported_missing(missing()), vec!["De", "En(Us)"]);
+        assert!(reported_missing(vec!["Pseudo".to_string()]).is_empty());
+
+        ::std::env::remove_var(REPORT_MISSING_VAR);
+    }
 }
@@ -3,10 +3,11 @@
 //! You shouldn't use this crate directly, but use `mauzi` instead. The macro
 //! is reexported there.
 
-#![feature(proc_macro, catch_expr)]
+#![feature(proc_macro)]
 
 extern crate literalext;
 extern crate proc_macro;
+extern crate toml;
 
 
 #[macro_use]
@@ -14,8 +15,12 @@ mod util;
 
 mod ast;
 mod check;
+mod csv;
+mod export;
 mod gen;
 mod parse;
+mod phf;
+mod phf_shared;
 
 
 use proc_macro::{Diagnostic, TokenStream};
@@ -31,16 +36,89 @@ type Result<T> = StdResult<T, Diagnostic>;
 /// **TODO**: documentation
 #[proc_macro]
 pub fn mauzi(input: TokenStream) -> TokenStream {
-    use check::check;
-    use gen::gen;
-    use parse::parse;
+    expand(input).unwrap_or_else(|e| {
+        e.emit();
+        TokenStream::empty()
+    })
+}
 
-    do catch {
-        let ast = parse(input)?;
-        check(&ast)?;
-        gen(ast)
-    }.unwrap_or_else(|e| {
+/// Defines only the locale types (`Locale`, `Language` and the `*Region`
+/// enums), so that they can be shared by multiple dictionaries.
+///
+/// The input is the locale definition, like in `mauzi!`. Dictionaries then
+/// start with e.g. `use locale::Locale;` instead of a locale definition, which
+/// makes `mauzi!` use this definition without generating the locale types
+/// again. The path (relative to the crate root) names the module containing
+/// `mauzi_locale!`, whose file is read by `mauzi!`. The locale types still
+/// have to be in scope of the dictionary.
+#[proc_macro]
+pub fn mauzi_locale(input: TokenStream) -> TokenStream {
+    expand_locale(input).unwrap_or_else(|e| {
         e.emit();
         TokenStream::empty()
     })
 }
+
+/// Expands to a string literal containing the code `mauzi!` would generate
+/// for the same input.
+///
+/// This is meant for debugging the macro and for tests asserting on the
+/// generated code (see `tests/expand.rs` for the snapshot tests), like this:
+///
+/// ```ignore
+/// let code = mauzi_expand! {
+///     enum Locale { De, En }
+///     unit cat { De => "Katze", En => "cat" }
+/// };
+/// assert!(code.contains("pub fn cat"));
+/// ```
+///
+/// Unlike `mauzi!`, this never writes the exports for translators (see
+/// `MAUZI_EXPORT_DIR`).
+#[proc_macro]
+pub fn mauzi_expand(input: TokenStream) -> TokenStream {
+    use gen::gen;
+    use proc_macro::{Literal, TokenNode};
+
+    prepare(input)
+        .and_then(gen)
+        .map(|out| TokenNode::Literal(Literal::string(&out.to_string())).into())
+        .unwrap_or_else(|e| {
+            e.emit();
+            TokenStream::empty()
+        })
+}
+
+/// Runs the whole pipeline (parse, check, export and code generation) for
+/// the given macro input and returns the generated code.
+fn expand(input: TokenStream) -> Result<TokenStream> {
+    use export::export;
+    use gen::gen;
+
+    let ast = prepare(input)?;
+    export(&ast)?;
+    gen(ast)
+}
+
+/// Parses and checks the given macro input and returns the AST with the
+/// flattened modules already merged into their parents.
+fn prepare(input: TokenStream) -> Result<ast::Dict> {
+    use check::check;
+    use parse::parse;
+
+    let mut ast = parse(input)?;
+    check(&ast)?;
+    ast.flatten_modules();
+    Ok(ast)
+}
+
+/// Like `expand`, but for the input of `mauzi_locale!`.
+fn expand_locale(input: TokenStream) -> Result<TokenStream> {
+    use check::check;
+    use gen::gen_locale_only;
+    use parse::parse_locale;
+
+    let ast = parse_locale(input)?;
+    check(&ast)?;
+    gen_locale_only(ast)
+}
@@ -1,17 +1,26 @@
 use std::io;
 use std::iter::Peekable;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use proc_macro::{
-    Delimiter, Diagnostic, Level, Literal, Spacing, Span, TokenNode,
+    Delimiter, Diagnostic, Level, Literal, Spacing, Span, Term, TokenNode,
     TokenStream, TokenTree, TokenTreeIter
 };
 use literalext::LiteralExt;
 
 use ast::{self, Ident};
+use csv;
 use Result;
-use util::Spanned;
+use util::{
+    self, env_placeholder, fallback_placeholder, is_valid_ident, number_placeholder, Spanned,
+};
+
 
+/// The maximum nesting depth of modules. Modules are loaded recursively, as
+/// are most passes over the module tree, so pathologically deep nesting (e.g.
+/// a symlink loop in the module directories) could overflow the stack of the
+/// compiler. Checking it while parsing keeps all later passes safe as well.
+const MAX_MODULE_DEPTH: usize = 32;
 
 /// Parses the input token stream into an abstract intermediate representation.
 pub fn parse(input: TokenStream) -> Result<ast::Dict> {
@@ -25,24 +34,435 @@ pub fn parse(input: TokenStream) -> Result<ast::Dict> {
     let src_dir = Path::new(&manifest_dir).join("src");
 
     let mut iter = Iter::new(input);
-    let locale_def = parse_locale_def(&mut iter)?;
-    let (modules, trans_units) = parse_items(&mut iter, &src_dir)?;
+    let (config, locale_def) = parse_head(&mut iter, &src_dir)?;
+    let context = parse_context(&mut iter)?;
+    let (modules, trans_units, helpers)
+        = parse_items(&mut iter, &src_dir, &locale_def, &[], 0)?;
+
+    Ok(ast::Dict { config, locale_def, modules, trans_units, helpers, context })
+}
+
+/// Parses the attributes and the locale definition at the start of the input.
+/// With `use locale::Locale;`, the locale definition of the `mauzi_locale!`
+/// invocation in that module is used.
+fn parse_head(iter: &mut Iter, src_dir: &Path) -> Result<(ast::Config, ast::LocaleDef)> {
+    let attrs = parse_attrs(iter)?;
+    let (mut config, locale_attrs) = parse_config(attrs)?;
+
+    if iter.peek_keyword("use") {
+        iter.bump();
+        let path = parse_locale_path(iter)?;
+
+        // The `Pseudo` language is part of the shared locale types, so the
+        // flag is taken from there.
+        let (shared_config, mut locale_def) = parse_shared_locale(&path, src_dir)?;
+        config.pseudo = shared_config.pseudo;
+        locale_def.is_external = true;
+        parse_base_lang(iter, &mut locale_def)?;
+        return Ok((config, locale_def));
+    }
+
+    let mut locale_def = parse_locale_def(iter, src_dir)?;
+    locale_def.attrs = locale_attrs;
+    if config.pseudo {
+        locale_def.langs.push(ast::LocaleLang {
+            name: Ident::exported("Pseudo"),
+            display_name: Some("Pseudo locale".into()),
+            regions: vec![],
+            groups: vec![],
+            region_set: None,
+        });
+    }
+
+    Ok((config, locale_def))
+}
+
+/// Parses the path of `use locale::Locale;` (the `use` was already consumed)
+/// and returns the module names, e.g. `["locale"]`. The path is relative to
+/// the crate root and has to end with `Locale`.
+///
+/// ```
+/// locale_path := (<ident> "::")+ "Locale" ";"
+/// ```
+fn parse_locale_path(iter: &mut Iter) -> Result<Spanned<Vec<Ident>>> {
+    let mut modules = vec![];
+    let mut segment = iter.eat_term()?;
+    while iter.peek_op(':') {
+        iter.bump();
+        iter.eat_op_if(':')?;
+        modules.push(segment);
+        segment = iter.eat_term()?;
+    }
+    iter.eat_op_if(';')?;
+
+    if modules.is_empty() || segment.as_str() != "Locale" {
+        return Err(segment.diag_span()
+            .error("expected the path of the `Locale` type defined via `mauzi_locale!`")
+            .help("use e.g. `use locale::Locale;` if `mauzi_locale!` is used in `src/locale.rs`"));
+    }
+
+    Ok(Spanned::new(modules, segment.diag_span()))
+}
+
+/// Parses the locale definition of the `mauzi_locale!` invocation in the
+/// module with the given path for `use locale::Locale;`.
+///
+/// Macro invocations can't see each other, so we read the source file of the
+/// module and parse the input of `mauzi_locale!` again. It was already
+/// checked when `mauzi_locale!` was expanded.
+fn parse_shared_locale(
+    path: &Spanned<Vec<Ident>>,
+    src_dir: &Path,
+) -> Result<(ast::Config, ast::LocaleDef)> {
+    let names = path.obj.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+    let dir = names[..names.len() - 1].iter().fold(src_dir.to_owned(), |dir, name| dir.join(name));
+    let name = names[names.len() - 1];
+
+    // Both valid paths of the module, like for the modules of the dictionary.
+    let p0 = dir.join(name).join("mod.rs");
+    let p1 = dir.join(format!("{}.rs", name));
+    let file = match (p0.exists(), p1.exists()) {
+        (true, false) => p0,
+        (false, true) => p1,
+        (false, false) => {
+            return err!(
+                path.span,
+                "cannot find the module '{}': neither '{}' nor '{}' exist",
+                names.join("::"),
+                p0.display(),
+                p1.display()
+            );
+        }
+        (true, true) => {
+            return err!(
+                path.span,
+                "ambiguity when loading module '{}': both '{}' and '{}' exist",
+                names.join("::"),
+                p0.display(),
+                p1.display()
+            );
+        }
+    };
+
+    let content = read_source_file(&file).map_err(|e| {
+        path.span
+            .error(format!("error reading '{}'", file.display()))
+            .note(e.to_string())
+    })?;
+    let tokens = content.parse::<TokenStream>()
+        .map_err(|e| path.span.error(format!("invalid shared locale definition: {:?}", e)))?;
+
+    // Find `mauzi_locale! { ... }` in the items of the module.
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut inputs = tokens.windows(3).filter_map(|window| {
+        match (&window[0].kind, &window[1].kind, &window[2].kind) {
+            (&TokenNode::Term(term), &TokenNode::Op('!', _), &TokenNode::Group(_, ref input))
+                if term.as_str() == "mauzi_locale" => Some(input.clone()),
+            _ => None,
+        }
+    });
+    let input = match (inputs.next(), inputs.next()) {
+        (Some(input), None) => input,
+        (None, _) => {
+            return Err(path.span
+                .error(format!("'{}' doesn't contain a `mauzi_locale!` invocation", file.display()))
+                .note("`use` refers to the module defining the locale types via `mauzi_locale!`"));
+        }
+        (Some(_), Some(_)) => {
+            return err!(
+                path.span,
+                "'{}' contains more than one `mauzi_locale!` invocation",
+                file.display()
+            );
+        }
+    };
+
+    // `mauzi_locale!` itself reports `use` in its input, but we would recurse
+    // before that.
+    if find_use(&input).is_some() {
+        return err!(path.span, "invalid shared locale definition in '{}'", file.display());
+    }
+
+    parse_head(&mut Iter::new(input), src_dir)
+}
+
+/// Returns the first `use` token in the given stream (not recursively), if
+/// any.
+fn find_use(tokens: &TokenStream) -> Option<TokenTree> {
+    tokens.clone().into_iter().find(|tt| match tt.kind {
+        TokenNode::Term(term) => term.as_str() == "use",
+        _ => false,
+    })
+}
+
+/// Parses the optional context of the dictionary, e.g.
+/// `context(user: Rc<User>);`. The values are stored in the `Dict`s, so they
+/// can't be references.
+///
+/// ```
+/// context := ["context" "(" <unit_param>,* ")" ";"]
+/// ```
+fn parse_context(iter: &mut Iter) -> Result<Vec<ast::UnitParam>> {
+    if !iter.peek_keyword("context") {
+        return Ok(vec![]);
+    }
+
+    iter.bump();
+    let group = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
+    let params = parse_unit_params(group.obj)?;
+    iter.eat_op_if(';')?;
+
+    for param in &params {
+        if param.ty.0.trim_left().starts_with('&') {
+            return Err(param.name.diag_span()
+                .error(format!("context value '{}' can't be a reference", param.name))
+                .note("the context is stored in the `Dict` of every module")
+                .help(format!(
+                    "use an owned type which is cheap to clone, e.g. `{}: Rc<...>`",
+                    param.name,
+                )));
+        }
+    }
+
+    Ok(params)
+}
+
+/// Parses the input of `mauzi_locale!`: like the input of `mauzi!`, but
+/// without any items. The resulting dictionary is thus empty. Dictionaries
+/// refer to it via `use path::to::Locale;`, see `parse_shared_locale`.
+pub fn parse_locale(input: TokenStream) -> Result<ast::Dict> {
+    if let Some(tt) = find_use(&input) {
+        return Err(tt.span.error("`use` is not allowed in `mauzi_locale!`")
+            .note("`mauzi_locale!` defines the locale types which `use` in `mauzi!` refers to"));
+    }
+
+    let dict = parse(input)?;
+    let item = dict.trans_units.first().map(|unit| unit.name.diag_span())
+        .or(dict.modules.first().map(|module| module.name.diag_span()))
+        .or(dict.helpers.first().map(|helpers| helpers.span))
+        .or(dict.context.first().map(|param| param.name.diag_span()));
+    if let Some(span) = item {
+        return Err(span.error("`mauzi_locale!` only defines the locale")
+            .help("define translation units in a `mauzi!` invocation which `use`s this locale"));
+    }
+
+    Ok(dict)
+}
+
+/// Interprets the attributes in front of the locale definition.
+/// `#[mauzi(...)]` configures the whole dictionary. All other attributes are
+/// returned to be passed through to the generated `Locale` enum.
+///
+/// ```
+/// config := ["#" "[" "mauzi" "(" <attribute_args> ")" "]"]*
+/// ```
+fn parse_config(attrs: Vec<Attr>) -> Result<(ast::Config, Vec<TokenStream>)> {
+    let mut config = ast::Config::default();
+    let mut locale_attrs = Vec::new();
+    for attr in attrs {
+        if attr.name.as_str() != "mauzi" {
+            locale_attrs.push(attr.tokens);
+            continue;
+        }
+
+        for arg in attr.expect_args()? {
+            match arg.name.as_str() {
+                "crate" => {
+                    let path = arg.expect_value()?;
+                    if path.obj.parse::<TokenStream>().is_err() {
+                        return err!(path.span, "'{}' is not a valid path", path.obj);
+                    }
+                    config.crate_path = path.obj;
+                }
+                "strict" => {
+                    arg.expect_flag()?;
+                    config.strict = true;
+                }
+                "strict_patterns" => {
+                    arg.expect_flag()?;
+                    config.strict_patterns = true;
+                }
+                "on_missing" => {
+                    let mode = arg.expect_value()?;
+                    config.log_missing = match mode.obj.as_str() {
+                        "text" => false,
+                        "log" => true,
+                        other => {
+                            return Err(mode.span
+                                .error(format!("unknown `on_missing` mode '{}'", other))
+                                .help("valid modes are \"text\" and \"log\""));
+                        }
+                    };
+                }
+                "ffi" => {
+                    if arg.value.is_none() {
+                        return Err(arg.name.diag_span()
+                            .error("`ffi` needs a prefix for the names of the C functions")
+                            .help("use e.g. `ffi = \"myapp\"` to generate \
+                                `myapp_locale_from_code()`"));
+                    }
+                    let prefix = arg.expect_value()?;
+                    if !is_valid_ident(&prefix.obj) {
+                        return Err(prefix.span
+                            .error(format!("'{}' is not a valid prefix", prefix.obj))
+                            .help("use an identifier, e.g. \"myapp\""));
+                    }
+                    config.ffi = Some(prefix.obj);
+                }
+                "dynamic" => {
+                    arg.expect_flag()?;
+                    config.dynamic = true;
+                }
+                "global" => {
+                    arg.expect_flag()?;
+                    config.global = true;
+                }
+                "pseudo" => {
+                    arg.expect_flag()?;
+                    config.pseudo = true;
+                }
+                "hash" => {
+                    arg.expect_flag()?;
+                    config.hash = true;
+                }
+                "warn_todo" => {
+                    config.todo_markers = if arg.value.is_none() {
+                        vec!["TODO".into(), "FIXME".into()]
+                    } else {
+                        let markers = arg.expect_value()?;
+                        let list = markers.obj.split(',')
+                            .map(|m| m.trim().to_string())
+                            .filter(|m| !m.is_empty())
+                            .collect::<Vec<_>>();
+                        if list.is_empty() {
+                            return Err(markers.span
+                                .error("`warn_todo` needs at least one marker")
+                                .help("use e.g. `warn_todo = \"TODO, FIXME\"`"));
+                        }
+                        list
+                    };
+                }
+                "format_macro" => {
+                    let name = arg.expect_value()?;
+                    let name_str = name.obj.trim().trim_right_matches('!').to_string();
+                    if !is_valid_ident(&name_str) {
+                        return Err(name.span
+                            .error(format!("'{}' is not a valid macro name", name.obj))
+                            .help("give the name of a macro in scope, e.g. \"html_format\""));
+                    }
+                    config.format_macro = Some(name_str);
+                }
+                "schema_version" => {
+                    config.schema_version = Some(arg.expect_int()?.obj);
+                }
+                "export_name" => {
+                    let name = arg.expect_value()?;
+                    let is_valid = !name.obj.is_empty() && name.obj.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                    if !is_valid {
+                        return Err(name.span
+                            .error(format!("'{}' is not a valid export name", name.obj))
+                            .help("use only letters, digits, `_` and `-`, e.g. \"errors\""));
+                    }
+                    config.export_name = name.obj;
+                }
+                "pub_crate" => {
+                    arg.expect_flag()?;
+                    config.vis = "pub(crate)".into();
+                }
+                "vis" => {
+                    let vis = arg.expect_value()?;
+                    let is_valid = vis.obj.trim().starts_with("pub")
+                        && vis.obj.parse::<TokenStream>().is_ok();
+                    if !is_valid {
+                        return Err(vis.span
+                            .error(format!("'{}' is not a valid visibility", vis.obj))
+                            .help("use e.g. \"pub(crate)\" or \"pub(in path::to::module)\""));
+                    }
+                    config.vis = vis.obj;
+                }
+                _ => return arg.unknown(),
+            }
+        }
+    }
 
-    Ok(ast::Dict { locale_def, modules, trans_units })
+    Ok((config, locale_attrs))
 }
 
-fn parse_locale_def(iter: &mut Iter) -> Result<ast::LocaleDef> {
-    // We require `enum Locale` in the very beginning.
+/// Parses the locale definition, which is either given inline or loaded from
+/// a file. The base language can be declared afterwards, e.g. `base En;`.
+/// Instead of a locale definition, the dictionary can also start with
+/// `use locale::Locale;` to use the types defined via `mauzi_locale!`, see
+/// `parse_head`.
+///
+/// ```
+/// locale_def := <locale_source> <base_lang>
+/// locale_source :=
+///     "enum" "Locale" "{" <locale_variant>,* "}"
+///     | "locales" "from" <string_literal> ";"
+/// base_lang := ["base" <ident> ";"]
+/// ```
+fn parse_locale_def(iter: &mut Iter, root_path: &Path) -> Result<ast::LocaleDef> {
+    let mut locale_def = parse_locale_source(iter, root_path)?;
+    parse_base_lang(iter, &mut locale_def)?;
+    Ok(locale_def)
+}
+
+fn parse_base_lang(iter: &mut Iter, locale_def: &mut ast::LocaleDef) -> Result<()> {
+    if iter.peek_keyword("base") {
+        iter.bump();
+        locale_def.base = Some(iter.eat_term()?);
+        iter.eat_op_if(';')?;
+    }
+
+    Ok(())
+}
+
+fn parse_locale_source(iter: &mut Iter, root_path: &Path) -> Result<ast::LocaleDef> {
+    if iter.peek_keyword("locales") {
+        iter.bump();
+        iter.eat_keyword("from")?;
+        let path = iter.eat_string_literal()?;
+        iter.eat_op_if(';')?;
+
+        return parse_locale_file(&root_path.join(&path.obj), path.span);
+    }
+
+    // Forgetting the locale definition is an easy mistake, so we explain
+    // what's missing instead of just complaining about the item.
+    for &item in &["unit", "mod", "helpers"] {
+        if iter.peek_keyword(item) {
+            let span = iter.peek_curr()?.span;
+            return Err(span.error(format!(
+                "the `mauzi!` body must begin with `enum Locale {{ ... }}`, found `{}`",
+                item,
+            )).help("did you forget the locale definition?"));
+        }
+    }
+
+    // Otherwise, we require `enum Locale` in the very beginning.
     iter.eat_keyword("enum")?;
     iter.eat_keyword("Locale")?;
 
     let body = iter.eat_group_delimited_by(Delimiter::Brace)?;
     let mut body_iter = Iter::new(body.obj);
 
-    // Collect all langs.
+    // Collect all region sets and langs.
+    let mut region_sets = Vec::new();
     let mut langs = Vec::new();
     while !body_iter.is_exhausted() {
-        langs.push(parse_locale_variant(&mut body_iter)?);
+        if body_iter.peek_keyword("regions") {
+            body_iter.bump();
+            let name = body_iter.eat_term()?;
+            let body = body_iter.eat_group_delimited_by(Delimiter::Brace)?;
+            let regions = parse_region_list(body.obj, name)?;
+            if region_sets.iter().any(|&(n, _): &(Ident, _)| n.as_str() == name.as_str()) {
+                return err!(name.diag_span(), "region set '{}' is defined multiple times", name);
+            }
+            region_sets.push((name, regions));
+        } else {
+            langs.push(parse_locale_variant(&mut body_iter, &region_sets)?);
+        }
 
         // Maybe eat comma, if haven't reached the end
         if !body_iter.is_exhausted() {
@@ -51,60 +471,387 @@ fn parse_locale_def(iter: &mut Iter) -> Result<ast::LocaleDef> {
     }
 
 
-    Ok(ast::LocaleDef { langs })
+    Ok(ast::LocaleDef { langs, attrs: vec![], is_external: false, base: None, source_file: None })
 }
 
-fn parse_locale_variant(iter: &mut Iter) -> Result<ast::LocaleLang> {
-    let name = iter.eat_term()?;
+/// Loads the locale definition from a TOML file. The file has to contain an
+/// array of languages, to preserve their order:
+///
+/// ```toml
+/// [[language]]
+/// name = "De"
+///
+/// [[language]]
+/// name = "En"
+/// display_name = "English"
+/// regions = ["Gb", "Us"]
+/// default_region = "Us"
+/// ```
+///
+/// All idents created from the file get the span of the path in the macro
+/// invocation, so that errors point there.
+fn parse_locale_file(path: &Path, span: Span) -> Result<ast::LocaleDef> {
+    use toml::Value;
+
+    let content = read_source_file(path).map_err(|e| {
+        span.error(format!("error reading locale file '{}'", path.display()))
+            .note(e.to_string())
+    })?;
+
+    let value = content.parse::<Value>().map_err(|e| {
+        span.error(format!("locale file '{}' is not valid TOML", path.display()))
+            .note(e.to_string())
+    })?;
+
+    let invalid = |msg: &str| -> Diagnostic {
+        span.error(format!("invalid locale file '{}': {}", path.display(), msg))
+    };
+    let ident = |s: &str| Ident::new(Term::intern(s), span);
+
+    let langs = value.get("language")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| invalid("expected an array `[[language]]`"))?;
+
+    langs.iter().map(|lang| {
+        let name = lang.get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| invalid("each language needs a `name` string"))?;
+        let display_name = lang.get("display_name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let mut regions: Vec<ast::LocaleRegion> = match lang.get("regions") {
+            None => vec![],
+            Some(regions) => {
+                let regions = regions.as_array()
+                    .ok_or_else(|| invalid("`regions` has to be an array of strings"))?;
+
+                regions.iter().map(|region| {
+                    region.as_str()
+                        .map(|name| ast::LocaleRegion {
+                            name: ident(name),
+                            display_name: None,
+                            is_default: false,
+                            numeric_code: None,
+                        })
+                        .ok_or_else(|| invalid("`regions` has to be an array of strings"))
+                }).collect::<Result<_>>()?
+            }
+        };
 
-    let mut regions = Vec::new();
-    if let Ok(&TokenTree { kind: TokenNode::Group(Delimiter::Brace, _), .. }) = iter.peek_curr() {
-        let body = iter.eat_group_delimited_by(Delimiter::Brace)?;
-        let mut body_iter = Iter::new(body.obj);
+        if let Some(default) = lang.get("default_region") {
+            let pos = default.as_str()
+                .and_then(|default| regions.iter().position(|r| r.name.as_str() == default))
+                .ok_or_else(|| invalid("`default_region` has to be one of `regions`"))?;
+            regions[pos].is_default = true;
+        }
 
-        // Collect all regions.
-        while !body_iter.is_exhausted() {
-            regions.push(body_iter.eat_term()?);
+        Ok(ast::LocaleLang {
+            name: ident(name),
+            display_name,
+            regions,
+            groups: vec![],
+            region_set: None,
+        })
+    }).collect::<Result<_>>().map(|langs| {
+        ast::LocaleDef {
+            langs,
+            attrs: vec![],
+            is_external: false,
+            base: None,
+            source_file: Some(path.to_path_buf()),
+        }
+    })
+}
+
+/// Parses one language of the locale definition.
+///
+/// ```
+/// locale_variant :=
+///     <term> [<display_name>] [("{" <locale_region>,* "}" | "(" <term> ")") [<region_groups>]]
+/// locale_region := ["default"] <term> ["=" <integer_literal>] [<display_name>]
+/// display_name := "as" <string_literal>
+/// region_groups := "groups" "{" (<term> ":" "[" <term>,* "]"),* "}"
+/// ```
+///
+/// Instead of listing its regions, a language can use a region set declared
+/// earlier, e.g. `En(Eu)` with `regions Eu { De, Fr }`.
+fn parse_locale_variant(
+    iter: &mut Iter,
+    region_sets: &[(Ident, Vec<ast::LocaleRegion>)],
+) -> Result<ast::LocaleLang> {
+    let name = iter.eat_term()?;
+    let display_name = parse_display_name(iter)?;
 
-            // Maybe eat comma, if haven't reached the end
-            if !body_iter.is_exhausted() {
-                let _ = body_iter.eat_op_if(',');
+    let mut regions = Vec::new();
+    let mut groups = Vec::new();
+    let mut region_set = None;
+    match iter.peek_curr() {
+        Ok(&TokenTree { kind: TokenNode::Group(Delimiter::Brace, _), .. }) => {
+            let body = iter.eat_group_delimited_by(Delimiter::Brace)?;
+            regions = parse_region_list(body.obj, name)?;
+        }
+        Ok(&TokenTree { kind: TokenNode::Group(Delimiter::Parenthesis, _), .. }) => {
+            let group = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
+            let set_name = Iter::new(group.obj).eat_term()?;
+            match region_sets.iter().find(|&&(n, _)| n.as_str() == set_name.as_str()) {
+                Some(&(_, ref set_regions)) => regions = set_regions.clone(),
+                None => {
+                    return Err(set_name.diag_span()
+                        .error(format!("unknown region set '{}'", set_name))
+                        .help(format!(
+                            "declare it before this language: `regions {} {{ ... }}`",
+                            set_name,
+                        )));
+                }
             }
+            region_set = Some(set_name);
         }
+        _ => {}
+    }
+
+    if !regions.is_empty() && iter.peek_keyword("groups") {
+        iter.bump();
+        groups = parse_region_groups(iter)?;
     }
 
     Ok(ast::LocaleLang {
         name,
+        display_name,
         regions,
+        groups,
+        region_set,
     })
 }
 
-fn parse_items(iter: &mut Iter, root_path: &Path) -> Result<(Vec<ast::Mod>, Vec<ast::TransUnit>)> {
-    // Collect all translation units and modules.
+/// Parses the regions of a language or region set, e.g.
+/// `default Gb as "British English", Us`. `owner` is the name of the language
+/// or region set, used in error messages.
+fn parse_region_list(body: TokenStream, owner: Ident) -> Result<Vec<ast::LocaleRegion>> {
+    let mut body_iter = Iter::new(body);
+    let mut regions = Vec::new();
+    while !body_iter.is_exhausted() {
+        let is_default = body_iter.peek_keyword("default");
+        if is_default {
+            body_iter.bump();
+        }
+
+        let name = body_iter.eat_term()?;
+        if is_default && regions.iter().any(|r: &ast::LocaleRegion| r.is_default) {
+            return err!(
+                name.diag_span(),
+                "'{}' has more than one default region",
+                owner
+            );
+        }
+
+        // Regions can have a numeric UN M49 code, e.g. `Gb = 826`.
+        let numeric_code = if body_iter.peek_op('=') {
+            body_iter.bump();
+            let lit = body_iter.eat_literal()?;
+            match lit.obj.to_string().parse::<u16>() {
+                Ok(code) if code <= 999 => Some(Spanned::new(code, lit.span)),
+                _ => {
+                    return Err(lit.span
+                        .error(format!("invalid numeric region code '{}'", lit.obj))
+                        .note("numeric region codes are UN M49 codes with up to three digits")
+                        .help(format!("use e.g. `{} = 826`", name)));
+                }
+            }
+        } else {
+            None
+        };
+
+        let display_name = parse_display_name(&mut body_iter)?;
+        regions.push(ast::LocaleRegion { name, display_name, is_default, numeric_code });
+
+        // Maybe eat comma, if haven't reached the end
+        if !body_iter.is_exhausted() {
+            let _ = body_iter.eat_op_if(',');
+        }
+    }
+
+    Ok(regions)
+}
+
+/// Parses the region groups of a language, e.g.
+/// `{ Europe: [Gb, Ie], Americas: [Us] }`. The `groups` keyword was already
+/// eaten.
+fn parse_region_groups(iter: &mut Iter) -> Result<Vec<ast::RegionGroup>> {
+    let body = iter.eat_group_delimited_by(Delimiter::Brace)?;
+    let mut body_iter = Iter::new(body.obj);
+
+    let mut groups = Vec::new();
+    while !body_iter.is_exhausted() {
+        let name = body_iter.eat_term()?;
+        body_iter.eat_op_if(':')?;
+
+        let members = body_iter.eat_group_delimited_by(Delimiter::Bracket)?;
+        let mut members_iter = Iter::new(members.obj);
+        let mut regions = Vec::new();
+        while !members_iter.is_exhausted() {
+            regions.push(members_iter.eat_term()?);
+
+            if !members_iter.is_exhausted() {
+                members_iter.eat_op_if(',')?;
+            }
+        }
+
+        groups.push(ast::RegionGroup { name, regions });
+
+        // Maybe eat comma, if haven't reached the end
+        if !body_iter.is_exhausted() {
+            let _ = body_iter.eat_op_if(',');
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Parses an optional human readable name of a language or region, e.g.
+/// `as "British English"`.
+fn parse_display_name(iter: &mut Iter) -> Result<Option<String>> {
+    if !iter.peek_keyword("as") {
+        return Ok(None);
+    }
+
+    iter.bump();
+    iter.eat_string_literal().map(|s| Some(s.obj))
+}
+
+/// A parameter list which can be shared by several units, declared via
+/// `params UserCtx(user: &User);` and used via `unit greet uses UserCtx`.
+#[derive(Clone)]
+struct ParamTemplate {
+    name: Ident,
+    params: Vec<ast::UnitParam>,
+}
+
+/// Parses all items of a module. Parameter templates have to be declared
+/// before they are used. `templates` are the templates of the parent modules,
+/// which can be used, too. `depth` is the nesting depth of the module, 0 for
+/// the `mauzi!` body.
+fn parse_items(
+    iter: &mut Iter,
+    root_path: &Path,
+    locale: &ast::LocaleDef,
+    templates: &[ParamTemplate],
+    depth: usize,
+) -> Result<(Vec<ast::Mod>, Vec<ast::TransUnit>, Vec<Spanned<TokenStream>>)> {
+    // Collect all translation units, modules and helper blocks.
     let mut trans_units = Vec::new();
     let mut modules = Vec::new();
+    let mut helpers = Vec::new();
+    let mut templates = templates.to_vec();
     while !iter.is_exhausted() {
+        let attrs = parse_attrs(iter)?;
         let item_kind = iter.eat_term()?;
         match item_kind.as_str() {
-            "unit" => trans_units.push(parse_trans_unit(iter)?),
-            "mod" => modules.push(parse_module(iter, root_path)?),
+            "unit" => {
+                trans_units.push(parse_trans_unit(iter, attrs, root_path, locale, &templates)?);
+            }
+            "params" => {
+                if let Some(attr) = attrs.first() {
+                    return err!(attr.span, "attributes are not allowed on parameter templates");
+                }
+
+                let name = iter.eat_term()?;
+                let group = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
+                let params = parse_unit_params(group.obj)?;
+                iter.eat_op_if(';')?;
+
+                if templates.iter().any(|t| t.name.as_str() == name.as_str()) {
+                    return err!(
+                        name.diag_span(),
+                        "parameter template '{}' is defined multiple times",
+                        name
+                    );
+                }
+                templates.push(ParamTemplate { name, params });
+            }
+            "mod" => {
+                let mut flatten = false;
+                for attr in attrs {
+                    match attr.name.as_str() {
+                        "flatten" => {
+                            attr.expect_flag()?;
+                            flatten = true;
+                        }
+                        other => {
+                            return err!(attr.name.diag_span(), "unknown attribute '{}'", other);
+                        }
+                    }
+                }
+
+                let mut module = parse_module(iter, root_path, locale, &templates, depth + 1)?;
+                module.flatten = flatten;
+                modules.push(module);
+            }
+            "helpers" => {
+                if let Some(attr) = attrs.first() {
+                    return err!(attr.span, "attributes are not allowed on helpers");
+                }
+                helpers.push(iter.eat_group_delimited_by(Delimiter::Brace)?);
+            }
+            "context" => {
+                return Err(item_kind.diag_span()
+                    .error("the context must be declared right after the locale definition")
+                    .note("the context is shared by all modules of the dictionary"));
+            }
+            // A locale definition (`enum Locale { ... }` or `use locale::Locale;`)
+            // after the first one, e.g. in a module file.
+            "enum" | "use" => {
+                return Err(item_kind.diag_span()
+                    .error("the locale definition must only appear in the top-level `mauzi!` \
+                        body, not in module files")
+                    .note("all modules share the locale defined at the start of `mauzi!`")
+                    .help(format!("remove this `{}` item", item_kind)));
+            }
             s => {
-                return err!(item_kind.span().unwrap(), "expected item, found identifier '{}'", s);
+                return err!(item_kind.diag_span(), "expected item, found identifier '{}'", s);
             }
         }
     }
 
-    Ok((modules, trans_units))
+    Ok((modules, trans_units, helpers))
 }
 
-fn parse_module(iter: &mut Iter, root_path: &Path) -> Result<ast::Mod> {
-    use std::fs::File;
-    use std::io::Read;
-
-    // A module declaration has the form `mod name;`. The `mod` keyword was
-    // already consumed by the calling function.
+/// Parses a module declaration and loads the module. `depth` is the nesting
+/// depth of the module, starting with 1 for modules in the `mauzi!` body.
+fn parse_module(
+    iter: &mut Iter,
+    root_path: &Path,
+    locale: &ast::LocaleDef,
+    templates: &[ParamTemplate],
+    depth: usize,
+) -> Result<ast::Mod> {
+
+    // A module declaration has the form `mod name;` or
+    // `mod name from csv("file.csv");`. The `mod` keyword was already
+    // consumed by the calling function.
     let name = iter.eat_term()?;
-    let name_span = name.span().unwrap();
+    let name_span = name.diag_span();
+    if depth > MAX_MODULE_DEPTH {
+        return Err(name_span
+            .error(format!("module '{}' is nested too deeply", name))
+            .note(format!("modules can be nested at most {} levels deep", MAX_MODULE_DEPTH)));
+    }
+
+    if iter.peek_keyword("from") {
+        iter.bump();
+        iter.eat_keyword("csv")?;
+        let args = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
+        let path = Iter::new(args.obj).eat_string_literal()?;
+        iter.eat_op_if(';')?;
+
+        let trans_units = parse_csv_file(&root_path.join(&path.obj), path.span, locale)?;
+        return Ok(ast::Mod {
+            name,
+            modules: vec![],
+            trans_units,
+            flatten: false,
+        });
+    }
     iter.eat_op_if(';')?;
 
     // Both valid paths.
@@ -124,56 +871,658 @@ fn parse_module(iter: &mut Iter, root_path: &Path) -> Result<ast::Mod> {
             );
         }
         (true, true) => {
-            return err!(
-                name_span,
-                "Ambiguity when loading module: both, '{}' and '{}' exist",
+            // Submodules of `foo` live in `foo/`, so if there are any, the
+            // `mod.mauzi.rs` next to them should be kept. Otherwise, the
+            // directory isn't needed at all.
+            let has_submodules = p0.parent().unwrap().read_dir()
+                .map(|entries| {
+                    entries.filter_map(|e| e.ok()).any(|e| {
+                        let file_name = e.file_name();
+                        let file_name = file_name.to_string_lossy();
+                        file_name.ends_with(".mauzi.rs") && file_name != "mod.mauzi.rs"
+                    })
+                })
+                .unwrap_or(false);
+            let (keep, delete) = if has_submodules { (&p0, &p1) } else { (&p1, &p0) };
+
+            return Err(name_span.error(format!(
+                "ambiguity when loading module '{}': both '{}' and '{}' exist",
+                name,
                 p0.display(),
-                p1.display()
+                p1.display(),
+            ))
+                .help(format!("delete '{}' and keep '{}'", delete.display(), keep.display()))
+                .note(format!(
+                    "the preferred style is '{0}.mauzi.rs' for modules without submodules \
+                        and '{0}/mod.mauzi.rs' for modules with submodules",
+                    name,
+                ))
             );
         }
-        (true, false) => p0,
-        (false, true) => p1,
+        (true, false) => {
+            check_file_name_case(root_path, name.as_str(), name_span)?;
+            p0
+        }
+        (false, true) => {
+            check_file_name_case(root_path, &format!("{}.mauzi.rs", name), name_span)?;
+            p1
+        }
     };
 
     // Read the file's content.
-    let content = {
-        let map_err = |e: io::Error| {
-            name_span
-                .error(format!("error reading module file '{}'", name.as_str()))
-                .note(e.to_string())
-        };
-
-        let mut file = File::open(&p).map_err(&map_err)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content).map_err(map_err)?;
-        content
-    };
-
-    // Parse item in file.
+    let content = read_source_file(&p).map_err(|e| {
+        name_span
+            .error(format!("error reading module file '{}'", name.as_str()))
+            .note(e.to_string())
+    })?;
+
+    // Parse item in file. Tokens parsed from a string don't have a useful
+    // span, so errors (e.g. type errors in raw bodies) would point at the
+    // whole macro invocation. Ideally they would point into the module file,
+    // but `proc_macro` has no way to create a span for a file other than the
+    // one being compiled. So we give them the span of the module name
+    // instead, so at least the module is clear (see
+    // `tests/compile-fail/module-type-error.rs`).
     let tokens: TokenStream = content.parse().map_err(|e| name_span.error(format!("{:?}", e)))?;
+    let tokens = respan(tokens, name_span);
     let mut iter = Iter::new(tokens);
-    let (modules, trans_units) = parse_items(&mut iter, p.parent().unwrap())?;
+    let (modules, trans_units, helpers)
+        = parse_items(&mut iter, p.parent().unwrap(), locale, templates, depth)?;
+    if let Some(helpers) = helpers.first() {
+        return Err(helpers.span.error("helpers can only be defined in the root of the dictionary")
+            .note(format!("found helpers in the file of module '{}'", name)));
+    }
 
     Ok(ast::Mod {
         name,
         modules,
         trans_units,
+        flatten: false,
     })
 }
 
+/// Checks the case of the file (or directory) `file_name` in `dir` which is
+/// known to exist.
+///
+/// On case-insensitive file systems (usually on macOS and Windows), a module
+/// `foo` would load `Foo.mauzi.rs`, too, breaking the build on other systems.
+/// That's an error. On case-sensitive file systems, files only differing in
+/// case would collide when checked out on the other systems, so we warn.
+fn check_file_name_case(dir: &Path, file_name: &str, span: Span) -> Result<()> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        // If we can't list the directory, we can't check anything.
+        Err(_) => return Ok(()),
+    };
+    let names = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.to_lowercase() == file_name.to_lowercase())
+        .collect::<Vec<_>>();
+
+    let variants = names.iter().filter(|name| *name != file_name).collect::<Vec<_>>();
+    if variants.is_empty() {
+        return Ok(());
+    }
+
+    let variants = variants.iter()
+        .map(|name| format!("'{}'", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !names.iter().any(|name| name == file_name) {
+        return Err(span.error(format!(
+            "the module file '{}' differs in case from the module name",
+            dir.join(file_name).display(),
+        ))
+            .note(format!("found {} in '{}'", variants, dir.display()))
+            .help(format!("rename the file to '{}'", file_name))
+        );
+    }
+
+    span.warning(format!(
+        "the module file '{}' only differs in case from {}",
+        file_name,
+        variants,
+    ))
+        .note("those files collide on case-insensitive file systems (e.g. on macOS and Windows)")
+        .help("rename or delete one of them")
+        .emit();
+
+    Ok(())
+}
+
+/// Loads translation units from a CSV file. The first column of the header
+/// has to be `key`; all other columns are locale patterns like in unit arms:
+///
+/// ```text
+/// key,De,En(Gb),_
+/// greeting,"Hallo, {name}!","Hello, {name}!","Hi, {name}!"
+/// ```
+///
+/// Each data row becomes a unit with one arm per non-empty cell. All
+/// placeholders used in a row become generic parameters of type
+/// `impl ::std::fmt::Display` (or `Option<&str>` for placeholders with a
+/// fallback, like `{name?someone}`), in the order they first appear. Like
+/// with the locale file, all idents created from the file get the span of the
+/// path. The arms remember the file, so that it's included as dependency.
+fn parse_csv_file(
+    path: &Path,
+    span: Span,
+    locale: &ast::LocaleDef,
+) -> Result<Vec<ast::TransUnit>> {
+    let content = read_source_file(path).map_err(|e| {
+        span.error(format!("error reading CSV file '{}'", path.display()))
+            .note(e.to_string())
+    })?;
+
+    let invalid = |msg: String| -> Diagnostic {
+        span.error(format!("invalid CSV file '{}': {}", path.display(), msg))
+    };
+    let ident = |s: &str| Ident::new(Term::intern(s), span);
+
+    let records = csv::parse(&content).map_err(&invalid)?;
+    let mut records = records.into_iter();
+    let header = records.next().ok_or_else(|| invalid("missing header row".into()))?;
+    if header[0].trim() != "key" {
+        return Err(invalid("the first column of the header has to be `key`".into()));
+    }
+
+    let patterns = header[1..].iter().map(|col| {
+        parse_csv_column(col.trim(), span, locale).ok_or_else(|| {
+            invalid(format!("column '{}' is not a locale of this dictionary", col))
+        })
+    }).collect::<Result<Vec<_>>>()?;
+
+    records.map(|record| {
+        let key = record[0].trim();
+        if !is_valid_ident(key) {
+            return Err(invalid(format!("'{}' is not a valid unit name", key)));
+        }
+        if record.len() > header.len() {
+            return Err(invalid(format!("row '{}' has more cells than the header", key)));
+        }
+
+        let mut params = Vec::new();
+        let mut arms = Vec::new();
+        for (cell, pattern) in record[1..].iter().zip(&patterns) {
+            if cell.trim().is_empty() {
+                continue;
+            }
+            let body = util::unescape_sigils(cell.clone()).ok_or_else(|| {
+                invalid(format!("the '{}' cell of row '{}' starts with a single `@`", pattern, key))
+                    .note("the start of a body is reserved for sigils")
+                    .help("write `@@` for a literal `@`")
+            })?;
+
+            for (name, optional) in csv_placeholders(cell) {
+                if env_placeholder(&name).is_some() {
+                    continue;
+                }
+                if !is_valid_ident(&name) {
+                    return Err(invalid(format!(
+                        "placeholder '{{{}}}' in row '{}' is not a valid parameter name",
+                        name,
+                        key,
+                    )));
+                }
+                if !params.iter().any(|&(ref n, _)| *n == name) {
+                    params.push((name, optional));
+                }
+            }
+
+            arms.push(ast::UnitArm {
+                cfgs: vec![],
+                pattern: pattern.clone(),
+                guard: None,
+                is_count_condition: false,
+                format_as: None,
+                body: Spanned::new(ast::ArmBody::Str(body), span),
+                source_file: Some(path.to_path_buf()),
+            });
+        }
+
+        let params = if params.is_empty() {
+            None
+        } else {
+            Some(params.iter().map(|&(ref name, optional)| ast::UnitParam {
+                name: ident(name),
+                ty: if optional {
+                    ast::Ty("Option<&str>".into())
+                } else {
+                    ast::Ty("impl ::std::fmt::Display".into())
+                },
+            }).collect())
+        };
+
+        Ok(ast::TransUnit {
+            name: ident(key),
+            note: None,
+            category: None,
+            key: None,
+            context: None,
+            placeholders: None,
+            is_const: false,
+            deprecated: None,
+            no_translate: false,
+            max_len: None,
+            cache: false,
+            is_async: false,
+            params,
+            return_type: None,
+            body: ast::UnitBody { arms },
+        })
+    }).collect()
+}
+
+/// Interprets a column of a CSV header as arm pattern: `_`, `Lang` or
+/// `Lang(Region)` (where `Region` can also be a region group). Returns `None`
+/// if the column doesn't name a locale of the dictionary.
+fn parse_csv_column(col: &str, span: Span, locale: &ast::LocaleDef) -> Option<ast::ArmPattern> {
+    if col == "_" {
+        return Some(ast::ArmPattern::Underscore(span));
+    }
+
+    let (lang, region) = match col.find('(') {
+        Some(pos) if col.ends_with(')') => {
+            (col[..pos].trim(), Some(col[pos + 1..col.len() - 1].trim()))
+        }
+        Some(_) => return None,
+        None => (col, None),
+    };
+
+    let lang_def = locale.get_lang(lang)?;
+    let lang = Ident::new(Term::intern(lang), span);
+    match region {
+        None => Some(ast::ArmPattern::Lang(lang)),
+        Some(region)
+            if lang_def.contains_region(region) || lang_def.get_group(region).is_some() =>
+        {
+            Some(ast::ArmPattern::WithRegion {
+                lang,
+                region: Ident::new(Term::intern(region), span),
+            })
+        }
+        Some(_) => None,
+    }
+}
+
+/// Returns the names of all placeholders in the given cell, e.g. `["name"]`
+/// for `"Hello {name}!"`. `{{` is an escaped brace. The `:n` suffix of number
+/// placeholders is not part of the name. The flag is `true` if the
+/// placeholder has a fallback text (`{name?someone}`).
+fn csv_placeholders(cell: &str) -> Vec<(String, bool)> {
+    let mut out = Vec::new();
+    let mut chars = cell.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            continue;
+        }
+
+        let name = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+        let (name, optional) = match fallback_placeholder(&name) {
+            Some((name, _)) => (name.trim(), true),
+            None => (name.trim(), false),
+        };
+        let name = number_placeholder(name).unwrap_or(name);
+        out.push((name.trim().to_string(), optional));
+    }
+
+    out
+}
+
+/// An attribute in front of an item, e.g. `#[note = "..."]`.
+///
+/// Attributes are only parsed generically here. The functions parsing the
+/// specific items interpret them.
+struct Attr {
+    name: Ident,
+    value: AttrValue,
+    span: Span,
+    /// All tokens inside of `#[...]`, used to pass the attribute through.
+    tokens: TokenStream,
+}
+
+/// Everything that comes after the name of an attribute.
+enum AttrValue {
+    /// `#[name]`
+    None,
+    /// `#[name = "string"]`
+    Str(Spanned<String>),
+    /// `#[name = 42]`
+    Int(Spanned<u64>),
+    /// `#[name(...)]`
+    List(TokenStream),
+}
+
+impl Attr {
+    /// Returns an error if the attribute is not of the form `#[name]`.
+    fn expect_flag(self) -> Result<()> {
+        match self.value {
+            AttrValue::None => Ok(()),
+            _ => err!(self.span, "expected attribute of the form `#[{}]`", self.name),
+        }
+    }
+
+    /// Returns the string value of an attribute of the form
+    /// `#[name = "string"]` or an error if the attribute has another form.
+    fn expect_str(self) -> Result<Spanned<String>> {
+        match self.value {
+            AttrValue::Str(s) => Ok(s),
+            _ => err!(self.span, "expected attribute of the form `#[{} = \"...\"]`", self.name),
+        }
+    }
+
+    /// Returns the integer value of an attribute of the form `#[name = 42]`
+    /// or an error if the attribute has another form.
+    fn expect_int(self) -> Result<Spanned<u64>> {
+        match self.value {
+            AttrValue::Int(i) => Ok(i),
+            _ => err!(self.span, "expected attribute of the form `#[{} = 42]`", self.name),
+        }
+    }
+
+    /// Parses the arguments of an attribute of the form `#[name(...)]` or
+    /// returns an error if the attribute has another form.
+    ///
+    /// ```
+    /// attribute_args := <attribute_arg>,*
+    /// attribute_arg := <term> ["=" <string_literal>]
+    /// ```
+    fn expect_args(self) -> Result<Vec<AttrArg>> {
+        let ts = match self.value {
+            AttrValue::List(ts) => ts,
+            _ => return err!(self.span, "expected attribute of the form `#[{}(...)]`", self.name),
+        };
+
+        let mut iter = Iter::new(ts);
+        let mut args = Vec::new();
+        while !iter.is_exhausted() {
+            let name = iter.eat_term()?;
+            let value = if iter.eat_op_if('=').is_ok() {
+                Some(parse_literal_value(&mut iter)?)
+            } else {
+                None
+            };
+            args.push(AttrArg { name, value });
+
+            // Eat one comma, if haven't reached the end.
+            if !iter.is_exhausted() {
+                iter.eat_op_if(',')?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// One argument of an attribute like `#[mauzi(...)]`, e.g. `const` or
+/// `crate = "my_i18n"`. The value is either `AttrValue::Str` or
+/// `AttrValue::Int`.
+struct AttrArg {
+    name: Ident,
+    value: Option<AttrValue>,
+}
+
+impl AttrArg {
+    /// Returns an error if this argument has a value, i.e. is not of the form
+    /// `name`.
+    fn expect_flag(&self) -> Result<()> {
+        match self.value {
+            None => Ok(()),
+            Some(_) => err!(
+                self.name.diag_span(),
+                "argument '{}' doesn't take a value",
+                self.name
+            ),
+        }
+    }
+
+    /// Returns the string value of this argument or an error if it doesn't
+    /// have one.
+    fn expect_value(self) -> Result<Spanned<String>> {
+        match self.value {
+            Some(AttrValue::Str(s)) => Ok(s),
+            _ => err!(
+                self.name.diag_span(),
+                "expected argument of the form `{} = \"...\"`",
+                self.name
+            ),
+        }
+    }
+
+    /// Returns the integer value of this argument or an error if it doesn't
+    /// have one.
+    fn expect_int(self) -> Result<Spanned<u64>> {
+        match self.value {
+            Some(AttrValue::Int(i)) => Ok(i),
+            _ => err!(self.name.diag_span(), "expected argument of the form `{} = 1`", self.name),
+        }
+    }
+
+    /// Returns an error saying that this argument is unknown.
+    fn unknown<T>(&self) -> Result<T> {
+        err!(self.name.diag_span(), "unknown argument '{}'", self.name)
+    }
+}
+
+/// Parses zero or more attributes from the given iterator.
+///
+/// ```
+/// attribute := "#" "[" <term> ["=" <literal> | "(" ... ")"] "]"
+/// ```
+fn parse_attrs(iter: &mut Iter) -> Result<Vec<Attr>> {
+    let mut attrs = Vec::new();
+    while let Ok((_, span)) = iter.eat_op_if('#') {
+        let group = iter.eat_group_delimited_by(Delimiter::Bracket)?;
+        let tokens = group.obj.clone();
+        let mut inner = Iter::new(group.obj);
+
+        let name = inner.eat_term()?;
+        let value = if inner.is_exhausted() {
+            AttrValue::None
+        } else if inner.eat_op_if('=').is_ok() {
+            parse_literal_value(&mut inner)?
+        } else {
+            AttrValue::List(inner.eat_group_delimited_by(Delimiter::Parenthesis)?.obj)
+        };
+
+        if let Ok(tok) = inner.eat_curr() {
+            return err!(tok.span, "unexpected token '{}' in attribute", tok);
+        }
+
+        attrs.push(Attr { name, value, span, tokens });
+    }
+
+    Ok(attrs)
+}
+
+/// Parses a string or integer literal as value of an attribute or of an
+/// attribute argument.
+fn parse_literal_value(iter: &mut Iter) -> Result<AttrValue> {
+    let lit = iter.eat_literal()?;
+    let int = lit.obj.to_string().replace('_', "").parse::<u64>();
+    match (lit.obj.parse_string(), int) {
+        (Some(s), _) => Ok(AttrValue::Str(Spanned::new(s, lit.span))),
+        (None, Ok(i)) => Ok(AttrValue::Int(Spanned::new(i, lit.span))),
+        (None, Err(_)) => {
+            err!(lit.span, "expected string or integer literal, found '{}'", lit.obj)
+        }
+    }
+}
+
+/// Reads a file which is part of the dictionary (e.g. a module file). The
+/// content is normalized with `normalize_source`.
+fn read_source_file(path: &Path) -> io::Result<String> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+
+    Ok(normalize_source(&content))
+}
+
+/// Files saved by some editors (mostly on Windows) start with a UTF-8 BOM
+/// and use CRLF line endings, which confuses the tokenizer. So we remove the
+/// BOM and normalize all line endings to `\n`. Since we don't have proper
+/// spans for tokens in these files anyway, this doesn't make any spans worse.
+fn normalize_source(content: &str) -> String {
+    content.trim_left_matches('\u{feff}').replace("\r\n", "\n")
+}
+
+/// Sets the span of all tokens in the given stream (recursively) to `span`.
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens.into_iter().map(|tt| {
+        let kind = match tt.kind {
+            TokenNode::Group(delim, inner) => TokenNode::Group(delim, respan(inner, span)),
+            other => other,
+        };
+        TokenTree { kind, span }
+    }).collect()
+}
+
 /// Parses one translation unit from the given iterator.
 ///
 /// ```
 /// translation_unit :=
-///     "unit" <term> [<unit_parameters>] [<return_type>] "{" <unit_body> "}"
+///     <attribute>* "unit" <term> <attribute>* ["uses" <term>] [<unit_parameters>]
+///     [<return_type>] "{" <unit_body> "}"
 ///```
-fn parse_trans_unit(iter: &mut Iter) -> Result<ast::TransUnit> {
+fn parse_trans_unit(
+    iter: &mut Iter,
+    mut attrs: Vec<Attr>,
+    root_path: &Path,
+    locale: &ast::LocaleDef,
+    templates: &[ParamTemplate],
+) -> Result<ast::TransUnit> {
     // Each translation unit starts with the `unit` keyword followed by a name.
     // The keyword was already eaten by the calling function.
     let name = iter.eat_term()?;
 
-    // Check if there is a paramter list and parse it if that's the case.
-    let params = match *iter.peek_curr()? {
+    // Attributes can also be written after the name, e.g.
+    // `unit post #[context = "verb"] { ... }`.
+    attrs.extend(parse_attrs(iter)?);
+
+    // Interpret all attributes of this unit.
+    let mut note = None;
+    let mut category = None;
+    let mut key = None;
+    let mut context = None;
+    let mut placeholders = None;
+    let mut is_const = false;
+    let mut deprecated = None;
+    let mut no_translate = false;
+    let mut max_len = None;
+    let mut dedent = false;
+    let mut cache = false;
+    let mut is_async = false;
+    for attr in attrs {
+        match attr.name.as_str() {
+            "note" => note = Some(attr.expect_str()?.obj),
+            "category" => category = Some(attr.expect_str()?.obj),
+            "key" => {
+                let value = attr.expect_str()?;
+                if value.obj.trim().is_empty() {
+                    return err!(value.span, "the key of translation unit '{}' is empty", name);
+                }
+                key = Some(value);
+            }
+            "no_translate" => {
+                attr.expect_flag()?;
+                no_translate = true;
+            }
+            "context" => context = Some(attr.expect_str()?),
+            "max_len" => max_len = Some(attr.expect_int()?),
+            "dedent" => {
+                attr.expect_flag()?;
+                dedent = true;
+            }
+            "cache" => {
+                attr.expect_flag()?;
+                cache = true;
+            }
+            // Futures come from the `futures` crate, which is only a
+            // dependency of `mauzi` with the `async` feature.
+            "async" => {
+                attr.expect_flag()?;
+                if !cfg!(feature = "async") {
+                    return Err(attr.name.diag_span()
+                        .error(format!(
+                            "translation unit '{}' is marked as `#[async]`, but the `async` \
+                                feature of mauzi is disabled",
+                            name,
+                        ))
+                        .help("enable it in your `Cargo.toml`: `features = [\"async\"]`"));
+                }
+                is_async = true;
+            }
+            "placeholders" => {
+                let delims = attr.expect_str()?;
+                let parts = delims.obj.split_whitespace().collect::<Vec<_>>();
+                if parts.len() != 2 {
+                    return err!(
+                        delims.span,
+                        "expected opening and closing delimiter separated by a space, \
+                            e.g. \"%{{ }}%\""
+                    );
+                }
+                placeholders = Some((parts[0].to_string(), parts[1].to_string()));
+            }
+            "deprecated" => {
+                deprecated = match attr.value {
+                    AttrValue::None => Some(None),
+                    AttrValue::Str(s) => Some(Some(s.obj)),
+                    AttrValue::Int(_) | AttrValue::List(_) => {
+                        return err!(
+                            attr.span,
+                            "expected `#[deprecated]` or `#[deprecated = \"...\"]`"
+                        );
+                    }
+                };
+            }
+            "mauzi" => {
+                for arg in attr.expect_args()? {
+                    match arg.name.as_str() {
+                        "const" => {
+                            arg.expect_flag()?;
+                            is_const = true;
+                        }
+                        _ => return arg.unknown(),
+                    }
+                }
+            }
+            other => {
+                return err!(attr.name.diag_span(), "unknown attribute '{}'", other);
+            }
+        }
+    }
+
+    // The unit can use the parameters of a template (`uses UserCtx`), ...
+    let template_params = if iter.peek_keyword("uses") {
+        iter.bump();
+        let name = iter.eat_term()?;
+        match templates.iter().find(|t| t.name.as_str() == name.as_str()) {
+            Some(template) => Some(template.params.clone()),
+            None => {
+                return Err(name.diag_span()
+                    .error(format!("cannot find parameter template '{}'", name))
+                    .help(format!(
+                        "declare it before this unit, e.g. `params {}(user: &User);`",
+                        name,
+                    )));
+            }
+        }
+    } else {
+        None
+    };
+
+    // ... and its own parameters after those. Check if there is a paramter
+    // list and parse it if that's the case.
+    let own_params = match *iter.peek_curr()? {
         TokenTree { kind: TokenNode::Group(Delimiter::Parenthesis, _), .. } => {
             let param_group = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
             let params = parse_unit_params(param_group.obj)?;
@@ -181,29 +1530,58 @@ fn parse_trans_unit(iter: &mut Iter) -> Result<ast::TransUnit> {
         }
         _ => None,
     };
+    let params = match (template_params, own_params) {
+        (Some(mut template_params), Some(own_params)) => {
+            template_params.extend(own_params);
+            Some(template_params)
+        }
+        (template_params, own_params) => template_params.or(own_params),
+    };
 
     // Check if there is a custom return type and parse it if that's the case.
     let return_type = match *iter.peek_curr()? {
         TokenTree { kind: TokenNode::Op('-', spacing), span } => {
             // Consume the '->' operator and emit errors if it isn't found
             // correctly.
+            iter.eat_op_if('-')?;
             if spacing == Spacing::Alone {
-                return err!(span, "expected '->' or '{{', found '-'");
+                // A `-` followed by a `>` is most likely a misspelled arrow.
+                let is_spaced_arrow = match iter.peek_curr() {
+                    Ok(&TokenTree { kind: TokenNode::Op('>', _), .. }) => true,
+                    _ => false,
+                };
+
+                return if is_spaced_arrow {
+                    Err(span.error("expected '->', found '- >'")
+                        .help("remove the space between '-' and '>'"))
+                } else {
+                    err!(span, "expected '->' or '{{', found '-'")
+                };
+            }
+            let (_, arrow_span) = iter.eat_op_if('>')?;
+
+            // The arrow has to be followed by a type.
+            if let Ok(&TokenTree { kind: TokenNode::Group(Delimiter::Brace, _), .. })
+                = iter.peek_curr()
+            {
+                return err!(arrow_span, "expected return type after '->', found '{{'");
             }
-            iter.eat_op_if('-')?;
-            iter.eat_op_if('>')?;
 
             // Parse actual return type.
-            Some(parse_type(iter)?)
+            Some(parse_type(iter, arrow_span)?)
+        }
+        TokenTree { kind: TokenNode::Op('>', _), span } => {
+            return Err(span.error("expected '->' or '{', found '>'")
+                .help("return types are written as `-> Type`"));
         }
         _ => None,
     };
 
     // Parse the body or emit errors if the next token is not a group delimited
     // by a brace.
-    let body = match iter.eat_curr()? {
+    let mut body = match iter.eat_curr()? {
         TokenTree { kind: TokenNode::Group(Delimiter::Brace, ts), .. } => {
-            parse_unit_body(ts)?
+            parse_unit_body(ts, root_path, locale)?
         }
         ref other if return_type.is_some() => {
             return err!(
@@ -228,8 +1606,29 @@ fn parse_trans_unit(iter: &mut Iter) -> Result<ast::TransUnit> {
         }
     };
 
+    // With `#[dedent]`, the common indentation is removed from all string
+    // bodies, so that all other checks and the exports see the final text.
+    if dedent {
+        for arm in &mut body.arms {
+            if let ast::ArmBody::Str(ref mut s) = arm.body.obj {
+                *s = util::dedent(s);
+            }
+        }
+    }
+
     Ok(ast::TransUnit {
         name,
+        note,
+        category,
+        key,
+        context,
+        placeholders,
+        is_const,
+        deprecated,
+        no_translate,
+        max_len,
+        cache,
+        is_async,
         params,
         return_type,
         body,
@@ -237,6 +1636,9 @@ fn parse_trans_unit(iter: &mut Iter) -> Result<ast::TransUnit> {
 }
 
 /// Parse the given group as parameters of a translation unit.
+///
+/// The type of a parameter can be omitted (e.g. `unit count(n)`), in which
+/// case it's `i64`. That's handy for translators not familiar with Rust.
 fn parse_unit_params(group: TokenStream) -> Result<Vec<ast::UnitParam>> {
     let mut iter = Iter::new(group);
 
@@ -245,10 +1647,15 @@ fn parse_unit_params(group: TokenStream) -> Result<Vec<ast::UnitParam>> {
     while !iter.is_exhausted() {
         // A parameter needs a name ...
         let name = iter.eat_term()?;
-        // ... followed by a colon ...
-        iter.eat_op_if(':')?;
-        // ... followed by a type.
-        let ty = parse_type(&mut iter)?;
+
+        // ... optionally followed by a colon and a type.
+        let untyped = iter.is_exhausted() || iter.peek_op(',');
+        let ty = if untyped {
+            ast::Ty("i64".into())
+        } else {
+            let (_, colon_span) = iter.eat_op_if(':')?;
+            parse_type(&mut iter, colon_span)?
+        };
 
         params.push(ast::UnitParam { name, ty });
 
@@ -264,51 +1671,149 @@ fn parse_unit_params(group: TokenStream) -> Result<Vec<ast::UnitParam>> {
 /// Parses a Rust type from the given iterator.
 ///
 /// Note that this is actually not really parsing a Rust type. It simply adds
-/// all potentially valid tokens (all except `,` and '{') to a string buffer.
-/// Duplicating the Rust type parsing algorithm would be overkill. Thus we
-/// won't detect syntax errors at this stage.
-fn parse_type(iter: &mut Iter) -> Result<ast::Ty> {
+/// all potentially valid tokens (all except `,` outside of `<>` and '{') to a
+/// string buffer. Duplicating the Rust type parsing algorithm would be
+/// overkill. We only catch a few obvious mistakes (empty type, `=>`, trailing
+/// `&`, unbalanced `<>`) to emit an error here instead of deep in the
+/// generated code.
+///
+/// `prev_span` is the span of the token before the type (e.g. `:`), which is
+/// used for errors if the type is missing.
+fn parse_type(iter: &mut Iter, prev_span: Span) -> Result<ast::Ty> {
     use std::fmt::Write;
 
     let mut ty = String::new();
+    let mut last: Option<TokenTree> = None;
+
+    // How many `<` are currently open and where the first one was opened.
+    let mut depth = 0;
+    let mut open_span = None;
+
     loop {
         // We want to stop when we reached the end of the iterator or when we
-        // reach a comma. However, we don't want to consume the comma.
+        // reach a comma (which is not part of generic parameters). However,
+        // we don't want to consume the comma.
         match iter.peek_curr() {
             Err(_) => break,
-            Ok(&TokenTree { kind: TokenNode::Op(op, _), .. }) if op == ',' => break,
+            Ok(&TokenTree { kind: TokenNode::Op(',', _), .. }) if depth == 0 => break,
             Ok(&TokenTree { kind: TokenNode::Group(Delimiter::Brace, _), ..}) => break,
             _ => {},
         }
 
         // Apparantly we didn't stop, so we will add this token to our string.
-        write!(ty, "{}", iter.eat_curr().unwrap()).unwrap();
+        let tok = iter.eat_curr().unwrap();
+        let after_joint = match last {
+            Some(TokenTree { kind: TokenNode::Op(op, Spacing::Joint), .. }) => Some(op),
+            _ => None,
+        };
+        match (after_joint, &tok.kind) {
+            (Some('='), &TokenNode::Op('>', _)) => {
+                return err!(tok.span, "expected type, found '=>'");
+            }
+
+            // The `>` of `->` in function types doesn't close anything.
+            (Some('-'), &TokenNode::Op('>', _)) => {}
+
+            (_, &TokenNode::Op('<', _)) => {
+                if depth == 0 {
+                    open_span = Some(tok.span);
+                }
+                depth += 1;
+            }
+            (_, &TokenNode::Op('>', _)) => {
+                if depth == 0 {
+                    return err!(tok.span, "unbalanced '>' in type");
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+
+        // We separate tokens by spaces, as otherwise tokens like `&mut T`
+        // would be merged into `&mutT`. But we must not separate operators
+        // which are joint with the next one (like `->`).
+        write!(ty, "{}", tok).unwrap();
+        match tok.kind {
+            TokenNode::Op(_, Spacing::Joint) => {}
+            _ => ty.push(' '),
+        }
+        last = Some(tok);
+    }
+
+    match last {
+        None => return err!(prev_span, "expected type"),
+        Some(TokenTree { kind: TokenNode::Op('&', _), span }) => {
+            return err!(span, "expected type after '&'");
+        }
+        _ => {}
+    }
+
+    if depth != 0 {
+        return err!(open_span.unwrap(), "unclosed '<' in type");
     }
 
-    Ok(ast::Ty(ty))
+    Ok(ast::Ty(ty.trim_right().to_string()))
 }
 
-/// Parses a translation unit's body from the given group.
-fn parse_unit_body(group: TokenStream) -> Result<ast::UnitBody> {
+/// Parses a translation unit's body from the given group. Files referred to
+/// by arm bodies are relative to `root_path`.
+fn parse_unit_body(
+    group: TokenStream,
+    root_path: &Path,
+    locale: &ast::LocaleDef,
+) -> Result<ast::UnitBody> {
     let mut iter = Iter::new(group);
 
     // Collect all arms.
     let mut arms = Vec::new();
     while !iter.is_exhausted() {
-        // Each arm starts with a pattern/matcher ...
-        let pattern = parse_arm_pattern(&mut iter)?;
+        // Each arm can start with `#[cfg(...)]` attributes, to only use it
+        // if some cargo feature is enabled or the like ...
+        let mut cfgs = Vec::new();
+        for attr in parse_attrs(&mut iter)? {
+            if attr.name.as_str() != "cfg" {
+                return Err(attr.name.diag_span()
+                    .error(format!("unknown attribute '{}'", attr.name))
+                    .note("only `#[cfg(...)]` is allowed in front of an arm's pattern"));
+            }
 
-        // ... followed by a `=>` ...
-        {
+            match attr.value {
+                AttrValue::List(_) => cfgs.push(Spanned::new(attr.tokens, attr.span)),
+                _ => return err!(attr.span, "expected attribute of the form `#[cfg(...)]`"),
+            }
+        }
+
+        // ... followed by a pattern/matcher, optionally followed by
+        // attributes ...
+        let pattern = parse_arm_pattern(&mut iter, locale)?;
+        let mut format_as = None;
+        for attr in parse_attrs(&mut iter)? {
+            match attr.name.as_str() {
+                "format_as" => format_as = Some(attr.expect_str()?),
+                other => {
+                    return err!(attr.name.diag_span(), "unknown attribute '{}'", other);
+                }
+            }
+        }
+
+        // ... optionally followed by a guard or a count condition (like
+        // `De, count == 1`), which already eats the `=>` ...
+        let is_count_condition = iter.peek_op(',');
+        let guard = if iter.peek_keyword("if") || is_count_condition {
+            Some(parse_arm_guard(&mut iter)?)
+        } else {
+            // ... or directly followed by a `=>` ...
             let (spacing, span) = iter.eat_op_if('=')?;
             if spacing == Spacing::Alone {
                 return err!(span, "expected '=>', found '='");
             }
             iter.eat_op_if('>')?;
-        }
+
+            None
+        };
 
         // ... followed by the actual body.
-        let body = parse_arm_body(&mut iter)?;
+        let (body, source_file) = parse_arm_body(&mut iter, root_path)?;
 
         // Maybe eat comma, if haven't reached the end
         if !iter.is_exhausted() {
@@ -323,7 +1828,7 @@ fn parse_unit_body(group: TokenStream) -> Result<ast::UnitBody> {
         }
 
         arms.push(ast::UnitArm {
-            pattern, body
+            cfgs, pattern, guard, is_count_condition, format_as, body, source_file
         });
     }
 
@@ -331,10 +1836,18 @@ fn parse_unit_body(group: TokenStream) -> Result<ast::UnitBody> {
 }
 
 /// Parses one arm's pattern from the given iterator.
-fn parse_arm_pattern(iter: &mut Iter) -> Result<ast::ArmPattern> {
+///
+/// ```
+/// arm_pattern := "_" | <lang> | <lang> "(" <region> ")" | <string_literal>
+/// ```
+fn parse_arm_pattern(iter: &mut Iter, locale: &ast::LocaleDef) -> Result<ast::ArmPattern> {
     if let Ok((_, span)) = iter.eat_op_if('_') {
         // The pattern is a wildcard pattern.
         Ok(ast::ArmPattern::Underscore(span))
+    } else if let Ok(&TokenTree { kind: TokenNode::Literal(_), .. }) = iter.peek_curr() {
+        // The pattern is a language tag like `"en-US"`.
+        let tag = iter.eat_string_literal()?;
+        parse_tag_pattern(&tag.obj, tag.span, locale)
     } else {
         // The pattern has at least the language component which starts with
         // a term.
@@ -363,23 +1876,142 @@ fn parse_arm_pattern(iter: &mut Iter) -> Result<ast::ArmPattern> {
     }
 }
 
-/// Parses the body of one arm.
-fn parse_arm_body(iter: &mut Iter) -> Result<Spanned<ast::ArmBody>> {
+/// Interprets a language tag like `en-US` (or `en_US`) as arm pattern, which
+/// is `En(Us)` in this case. Language and region are compared
+/// case-insensitively and have to be defined in the locale definition.
+fn parse_tag_pattern(tag: &str, span: Span, locale: &ast::LocaleDef) -> Result<ast::ArmPattern> {
+    let mut parts = tag.splitn(2, |c| c == '-' || c == '_');
+    let lang_part = parts.next().unwrap();
+    let region_part = parts.next();
+
+    let lang_part = lang_part.to_lowercase();
+    let lang = match locale.langs.iter().find(|l| l.name.to_lowercase() == lang_part) {
+        Some(lang) => lang,
+        None => {
+            let langs = locale.langs.iter()
+                .map(|l| format!("'{}'", l.name.to_lowercase()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(span.error(format!("unknown language in tag '{}'", tag))
+                .note(format!("the languages of this dictionary are: {}", langs)));
+        }
+    };
+    let lang_ident = Ident::new(Term::intern(lang.name.as_str()), span);
+
+    let region_part = match region_part {
+        None => return Ok(ast::ArmPattern::Lang(lang_ident)),
+        Some(region) => region,
+    };
+    match lang.regions.iter().find(|r| r.name.to_lowercase() == region_part.to_lowercase()) {
+        Some(region) => Ok(ast::ArmPattern::WithRegion {
+            lang: lang_ident,
+            region: Ident::new(Term::intern(region.name.as_str()), span),
+        }),
+        None if lang.regions.is_empty() => {
+            Err(span.error(format!("unknown region in tag '{}'", tag))
+                .note(format!("the language '{}' has no regions", lang.name))
+                .help(format!("use the tag '{}' instead", lang.name.to_lowercase())))
+        }
+        None => {
+            let regions = lang.regions.iter()
+                .map(|r| format!("'{}-{}'", lang.name.to_lowercase(), r.name.to_uppercase()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(span.error(format!("unknown region in tag '{}'", tag))
+                .note(format!("the regions of '{}' are: {}", lang.name, regions)))
+        }
+    }
+}
+
+/// Parses the guard of an arm (e.g. `if role == Role::Admin` or
+/// `, count == 1`) including the following `=>`. The guard is an arbitrary
+/// Rust expression, so we simply collect all tokens up to the `=>`.
+fn parse_arm_guard(iter: &mut Iter) -> Result<Spanned<TokenStream>> {
+    let start = iter.eat_curr()?;
+    let if_span = start.span;
+
+    let mut tokens = Vec::new();
+    loop {
+        let tok = iter.eat_curr()
+            .map_err(|_| if_span.error("expected '=>' after the guard of this arm"))?;
+
+        // A `=` which is joint with the next token is either part of `==` or
+        // the start of `=>`.
+        if let TokenNode::Op('=', Spacing::Joint) = tok.kind {
+            if let Ok(&TokenTree { kind: TokenNode::Op('>', _), .. }) = iter.peek_curr() {
+                iter.bump();
+                break;
+            }
+        }
+
+        tokens.push(tok);
+    }
+
+    if tokens.is_empty() {
+        return err!(if_span, "expected expression after '{}'", start);
+    }
+
+    Ok(Spanned::new(tokens.into_iter().collect(), if_span))
+}
+
+/// Parses the body of one arm. If it's a string body read from a file, the
+/// path of that file is returned as well.
+///
+/// ```
+/// arm_body := "{" <raw_rust> "}" | <string_literal> | "file" "(" <string_literal> ")"
+/// ```
+fn parse_arm_body(
+    iter: &mut Iter,
+    root_path: &Path,
+) -> Result<(Spanned<ast::ArmBody>, Option<PathBuf>)> {
     // If we encounter a group next, we know the body is raw Rust.
     if iter.peek_curr()?.kind.is_group() {
         // Raw Rust body
         let group = iter.eat_group_delimited_by(Delimiter::Brace)?;
-        Ok(Spanned::new(ast::ArmBody::Raw(group.obj), group.span))
+        Ok((Spanned::new(ast::ArmBody::Raw(group.obj), group.span), None))
+    } else if iter.peek_keyword("file") {
+        // A string body whose content is read from a file. The file is
+        // relative to the file of the current module. One trailing newline
+        // is removed, since most editors add one.
+        iter.bump();
+        let args = iter.eat_group_delimited_by(Delimiter::Parenthesis)?;
+        let path = Iter::new(args.obj).eat_string_literal()?;
+        let full_path = root_path.join(&path.obj);
+        let content = read_source_file(&full_path).map_err(|e| {
+            path.span
+                .error(format!("error reading file '{}'", full_path.display()))
+                .note(e.to_string())
+        })?;
+
+        let content = if content.ends_with('\n') {
+            content[..content.len() - 1].to_string()
+        } else {
+            content
+        };
+        let content = util::unescape_sigils(content).ok_or_else(|| {
+            leading_sigil_error(path.span)
+                .note(format!("in file '{}'", full_path.display()))
+        })?;
+        let body = ast::ArmBody::Str(content);
+        Ok((Spanned::new(body, path.span), Some(full_path)))
     } else {
         // A standard body consisting of a single literal.
-        let lit = iter.eat_literal()?;
-        match lit.obj.parse_string() {
-            Some(s) => Ok(Spanned::new(ast::ArmBody::Str(s), lit.span)),
-            None => err!(lit.span, "expected string literal, found '{}'", lit.obj),
-        }
+        let lit = iter.eat_string_literal()?;
+        let span = lit.span;
+        let content = util::unescape_sigils(lit.obj).ok_or_else(|| leading_sigil_error(span))?;
+        let body = ast::ArmBody::Str(content);
+        Ok((Spanned::new(body, span), None))
     }
 }
 
+/// The error for a string body starting with a single `@`, which is reserved
+/// for sigils (see `util::unescape_sigils`).
+fn leading_sigil_error(span: Span) -> Diagnostic {
+    span.error("a string body must not start with a single `@`")
+        .note("the start of a body is reserved for sigils")
+        .help("write `@@` for a literal `@`")
+}
+
 /// A helper type wrapping an iterator over token-trees. Has many helper
 /// methods for retreiving specific token kinds from the iterator.
 struct Iter(Peekable<TokenTreeIter>);
@@ -411,7 +2043,7 @@ impl Iter {
             Ok(keyword) if keyword.as_str() == expected => Ok(()),
             Ok(keyword) => {
                 err!(
-                    keyword.span().unwrap(),
+                    keyword.diag_span(),
                     "expected keyword '{}', found identifier '{}'",
                     expected,
                     keyword
@@ -459,6 +2091,36 @@ impl Iter {
         }
     }
 
+    /// Consumes the next tt if it is a string literal and returns its
+    /// (unescaped) value. Otherwise an `Err` is returned.
+    fn eat_string_literal(&mut self) -> Result<Spanned<String>> {
+        let lit = self.eat_literal()?;
+        match lit.obj.parse_string() {
+            Some(s) => Ok(Spanned::new(s, lit.span)),
+            None => err!(lit.span, "expected string literal, found '{}'", lit.obj),
+        }
+    }
+
+    /// Returns `true` if the current tt is a term with the value `expected`.
+    /// Nothing is consumed.
+    fn peek_keyword(&mut self, expected: &str) -> bool {
+        match self.0.peek() {
+            Some(&TokenTree { kind: TokenNode::Term(term), .. }) => {
+                term.as_str() == expected
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the current tt is the operator `expected`. Nothing
+    /// is consumed.
+    fn peek_op(&mut self, expected: char) -> bool {
+        match self.0.peek() {
+            Some(&TokenTree { kind: TokenNode::Op(op, _), .. }) => op == expected,
+            _ => false,
+        }
+    }
+
     /// Consumes and returns the next tt if it equals the given operator.
     /// Otherwise an `Err` is returned.
     fn eat_op_if(&mut self, op: char) -> Result<(Spacing, Span)> {
@@ -534,3 +2196,26 @@ impl TokenNodeExt for TokenNode {
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_source(""), "");
+        assert_eq!(normalize_source("unit a {}\n"), "unit a {}\n");
+
+        // The BOM is only removed at the start.
+        assert_eq!(normalize_source("\u{feff}unit a {}"), "unit a {}");
+        assert_eq!(normalize_source("a => \"\u{feff}\""), "a => \"\u{feff}\"");
+
+        let crlf = "unit a {\r\n    De => \"a\",\r\n}\r\n";
+        assert_eq!(normalize_source(crlf), "unit a {\n    De => \"a\",\n}\n");
+        assert_eq!(normalize_source("\u{feff}a\r\nb\nc\r\n"), "a\nb\nc\n");
+
+        // Lone carriage returns are kept.
+        assert_eq!(normalize_source("a\rb"), "a\rb");
+    }
+}
@@ -0,0 +1,187 @@
+//! Builds perfect hash tables at compile time, used for the string key lookup
+//! of dictionaries in dynamic mode (`#[mauzi(dynamic)]`).
+//!
+//! This uses the "hash and displace" algorithm (like the `phf` crate): all
+//! keys are hashed into a few buckets. For each bucket, starting with the
+//! biggest one, we search for a pair of displacements that moves all keys of
+//! the bucket into free slots of the table. The runtime only has to hash the
+//! key once and look up the displacements of its bucket.
+//!
+//! The hash function and the slot calculation are shared with
+//! `mauzi::phf_index()` (see `phf_shared`).
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use phf_shared::{hash, slot};
+
+
+/// Average number of keys per bucket.
+const LAMBDA: usize = 4;
+
+/// The number of seeds to try before giving up. With distinct keys, one of
+/// the first few seeds practically always works.
+const MAX_SEEDS: u64 = 1000;
+
+/// A perfect hash table for a fixed set of keys.
+pub struct Table {
+    /// The seed of the hash function for which displacements for all buckets
+    /// were found.
+    pub seed: u64,
+
+    /// The displacements `(d1, d2)` of each bucket.
+    pub disps: Vec<(u32, u32)>,
+
+    /// The index of the key stored in each slot. The table has exactly one
+    /// slot per key.
+    pub slots: Vec<usize>,
+}
+
+/// The reasons why no table could be built.
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// The key is contained multiple times, so there is no perfect hash
+    /// function for the keys.
+    DuplicateKey(String),
+    /// None of the first `MAX_SEEDS` seeds worked.
+    NoSeedFound,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::DuplicateKey(ref key) => {
+                write!(f, "key '{}' is used multiple times", key)
+            }
+            BuildError::NoSeedFound => {
+                write!(f, "no perfect hash function found after {} seeds", MAX_SEEDS)
+            }
+        }
+    }
+}
+
+/// Builds a perfect hash table for the given keys, which have to be distinct.
+pub fn build(keys: &[String]) -> Result<Table, BuildError> {
+    // Two equal keys always have the same slot, so no seed would work.
+    let mut sorted = keys.iter().collect::<Vec<_>>();
+    sorted.sort();
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(BuildError::DuplicateKey(pair[0].clone()));
+    }
+
+    (0..MAX_SEEDS)
+        .filter_map(|seed| try_build(keys, seed))
+        .next()
+        .ok_or(BuildError::NoSeedFound)
+}
+
+fn try_build(keys: &[String], seed: u64) -> Option<Table> {
+    let bucket_count = (keys.len() + LAMBDA - 1) / LAMBDA;
+    let bucket_count = if bucket_count == 0 { 1 } else { bucket_count };
+    let table_len = keys.len();
+
+    let hashes = keys.iter().map(|k| hash(k, seed)).collect::<Vec<_>>();
+    let mut buckets = vec![Vec::new(); bucket_count];
+    for (i, &(g, _, _)) in hashes.iter().enumerate() {
+        buckets[(g % bucket_count as u32) as usize].push(i);
+    }
+
+    // Big buckets are the hardest to place, so we start with them.
+    let mut order = (0..bucket_count).collect::<Vec<_>>();
+    order.sort_by_key(|&b| Reverse(buckets[b].len()));
+
+    let mut disps = vec![(0, 0); bucket_count];
+    let mut slots = vec![None; table_len];
+
+    // To detect collisions within one bucket, we mark the slots used by the
+    // current attempt with its generation.
+    let mut attempt = vec![0u64; table_len];
+    let mut generation = 0;
+
+    'buckets: for b in order {
+        if buckets[b].is_empty() {
+            continue;
+        }
+
+        for d1 in 0..table_len as u32 {
+            for d2 in 0..table_len as u32 {
+                generation += 1;
+                let fits = buckets[b].iter().all(|&key| {
+                    let (_, f1, f2) = hashes[key];
+                    let idx = slot(f1, f2, d1, d2, table_len);
+                    if slots[idx].is_some() || attempt[idx] == generation {
+                        return false;
+                    }
+                    attempt[idx] = generation;
+                    true
+                });
+
+                if fits {
+                    for &key in &buckets[b] {
+                        let (_, f1, f2) = hashes[key];
+                        slots[slot(f1, f2, d1, d2, table_len)] = Some(key);
+                    }
+                    disps[b] = (d1, d2);
+                    continue 'buckets;
+                }
+            }
+        }
+
+        // No displacement works for this bucket, we need another seed.
+        return None;
+    }
+
+    Some(Table {
+        seed,
+        disps,
+        slots: slots.into_iter().map(|s| s.unwrap()).collect(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use phf_shared::index;
+
+    fn keys(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("module_{}.unit_{}", i % 7, i)).collect()
+    }
+
+    /// Every key has to be found in its slot with the lookup of the runtime
+    /// crate.
+    fn assert_round_trip(keys: &[String]) {
+        let table = build(keys).unwrap();
+        assert_eq!(table.slots.len(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let idx = index(key, table.seed, &table.disps, table.slots.len());
+            assert_eq!(table.slots[idx], i, "key '{}' is not in its slot", key);
+        }
+    }
+
+    #[test]
+    fn round_trip_small() {
+        assert_round_trip(&keys(1));
+        assert_round_trip(&keys(2));
+        assert_round_trip(&keys(5));
+        assert_round_trip(&["greet".to_string(), "bar.baz.bye_world".to_string()]);
+    }
+
+    #[test]
+    fn round_trip_large() {
+        assert_round_trip(&keys(5000));
+    }
+
+    #[test]
+    fn empty() {
+        let table = build(&[]).unwrap();
+        assert!(table.slots.is_empty());
+        assert_eq!(table.disps.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let keys = vec!["a".to_string(), "b".to_string(), "a".to_string()];
+        assert_eq!(build(&keys).err(), Some(BuildError::DuplicateKey("a".to_string())));
+    }
+}
@@ -0,0 +1,40 @@
+//! The hash function and slot calculation of the perfect hash tables, shared
+//! by the table construction in `mauzi_macros` and the lookup in `mauzi`.
+//!
+//! Both have to agree exactly, so this file is compiled into both crates
+//! (`mauzi` includes it via `#[path]`). It must not use anything but `std`.
+
+
+/// Returns the bucket hash `g` and the two slot hashes `f1` and `f2` of the
+/// given key.
+pub fn hash(key: &str, seed: u64) -> (u32, u32, u32) {
+    // FNV-1a, followed by the finalizer of MurmurHash3. Without the latter,
+    // the low bits hardly depend on the seed.
+    let mix = |start: u64| {
+        let h = key.bytes().fold(start, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+        let h = (h ^ (h >> 33)).wrapping_mul(0xff51afd7ed558ccd);
+        let h = (h ^ (h >> 33)).wrapping_mul(0xc4ceb9fe1a85ec53);
+        h ^ (h >> 33)
+    };
+
+    let a = mix(0xcbf29ce484222325 ^ seed);
+    let b = mix(0x84222325cbf29ce4 ^ seed);
+    ((a >> 32) as u32, a as u32, b as u32)
+}
+
+/// Returns the slot of a key with the slot hashes `f1` and `f2` in a bucket
+/// with the displacements `d1` and `d2`.
+pub fn slot(f1: u32, f2: u32, d1: u32, d2: u32, table_len: usize) -> usize {
+    (f2.wrapping_mul(d1).wrapping_add(f1).wrapping_add(d2) as usize) % table_len
+}
+
+/// Returns the slot of `key` in the table with the given seed and
+/// displacements. The table must not be empty.
+///
+/// The macro crate only uses this in tests.
+#[allow(dead_code)]
+pub fn index(key: &str, seed: u64, disps: &[(u32, u32)], table_len: usize) -> usize {
+    let (g, f1, f2) = hash(key, seed);
+    let (d1, d2) = disps[(g % disps.len() as u32) as usize];
+    slot(f1, f2, d1, d2, table_len)
+}
@@ -1,4 +1,4 @@
-use proc_macro::Span;
+use proc_macro::{Diagnostic, Span};
 
 use Result;
 use ast::{self, Ident};
@@ -39,24 +39,279 @@ macro_rules! note {
 
 
 
+/// Returns `true` if the given string is a valid Rust identifier.
+///
+/// Non-ASCII characters are allowed, as long as they are alphabetic (or
+/// alphanumeric for all but the first character). This is an approximation
+/// of the `XID_Start`/`XID_Continue` rules used by rustc. Keywords are not
+/// valid identifiers (see `is_keyword`).
+pub fn is_valid_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => {}
+        _ => return false,
+    }
+
+    s != "_" && !is_keyword(s) && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// Returns `true` if the given string is a keyword of Rust, including the
+/// ones reserved for future use. Those can't be used as names.
+pub fn is_keyword(s: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate",
+        "do", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+        "let", "loop", "macro", "match", "mod", "move", "mut", "offsetof", "override", "priv",
+        "proc", "pub", "pure", "ref", "return", "Self", "self", "sizeof", "static", "struct",
+        "super", "trait", "true", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+        "where", "while", "yield",
+    ];
+
+    KEYWORDS.contains(&s)
+}
+
+/// Returns `true` if the given name in an arm pattern looks like a variable
+/// binding, i.e. starts with a lowercase letter or `_`. Capitalized names
+/// refer to languages and regions.
+pub fn is_binding_name(s: &str) -> bool {
+    s.chars().next().map(|c| c == '_' || c.is_lowercase()).unwrap_or(false)
+}
+
+/// Returns the name of the environment variable if the given placeholder has
+/// the form `@env:NAME`.
+pub fn env_placeholder(arg_s: &str) -> Option<&str> {
+    let arg_s = arg_s.trim();
+    if arg_s.starts_with("@env:") {
+        Some(arg_s["@env:".len()..].trim())
+    } else {
+        None
+    }
+}
+
+/// Returns the expression if the given placeholder has the form `expr:n`,
+/// which formats a number according to the locale. Paths like `{x::n}` don't
+/// count.
+pub fn number_placeholder(arg_s: &str) -> Option<&str> {
+    let arg_s = arg_s.trim();
+    if arg_s.ends_with(":n") && !arg_s.ends_with("::n") {
+        Some(arg_s[..arg_s.len() - 2].trim())
+    } else {
+        None
+    }
+}
+
+/// Returns the name of the unit and the arguments if the given placeholder
+/// has the form `@unit(args)`, e.g. `("greet", "name")` for `@greet(name)`.
+pub fn unit_call_placeholder(arg_s: &str) -> Option<(&str, &str)> {
+    let arg_s = arg_s.trim();
+    if !arg_s.starts_with('@') || !arg_s.ends_with(')') {
+        return None;
+    }
+
+    match arg_s.find('(') {
+        Some(open) if is_valid_ident(arg_s[1..open].trim()) => {
+            Some((arg_s[1..open].trim(), &arg_s[open + 1..arg_s.len() - 1]))
+        }
+        _ => None,
+    }
+}
+
+/// Splits the arguments of a call at all commas outside of brackets and
+/// string literals. Empty arguments (e.g. after a trailing comma) are
+/// skipped.
+pub fn split_call_args(args: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_str = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_str = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(&args[start..]);
+
+    out.into_iter().filter(|arg| !arg.trim().is_empty()).collect()
+}
+
+/// Handles the escaping of sigils in a string body.
+///
+/// Sigils like `@env:` and `@unit(args)` are only special at the start of a
+/// placeholder (e.g. `{@env:USER}`). Everywhere else, `@` is a normal
+/// character, so e-mail addresses and the like just work. The start of a body
+/// is reserved for sigils as well: to start a body with a literal `@`, it has
+/// to be written as `@@`. Thus a leading `@@` is replaced by `@`, while `None`
+/// is returned for a body starting with a single `@`.
+pub fn unescape_sigils(s: String) -> Option<String> {
+    if s.starts_with("@@") {
+        Some(s[1..].to_string())
+    } else if s.starts_with('@') {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Splits a placeholder of the form `{expr?fallback text}` into the
+/// expression and the fallback text. Only a `?` outside of brackets and string
+/// literals counts, so `{names[i]?none}` works as expected. The fallback text
+/// starts after the last such `?` which isn't followed by `.`, `[` or `?`:
+/// those are try operators inside the expression, like in `{user()?.name}`. A
+/// `?` at the end (`{value?}`) is the try operator as well, see
+/// `try_placeholder`.
+pub fn fallback_placeholder(arg_s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut split = None;
+    for (i, c) in arg_s.char_indices() {
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_str = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_str = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '?' if depth == 0 => {
+                let rest = &arg_s[i + 1..];
+                let is_try = rest.starts_with('.') || rest.starts_with('[')
+                    || rest.starts_with('?');
+                if !is_try && !rest.trim().is_empty() {
+                    split = Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    split.map(|i| (&arg_s[..i], &arg_s[i + 1..]))
+}
+
+/// Returns `true` if the expression of the placeholder uses the try operator,
+/// like `{value?}` or `{user()?.name}`. That's only allowed in units returning
+/// `Result<String, _>`.
+pub fn try_placeholder(arg_s: &str) -> bool {
+    let expr = fallback_placeholder(arg_s).map(|(expr, _)| expr).unwrap_or(arg_s);
+    let mut in_str = false;
+    let mut escaped = false;
+    for c in expr.chars() {
+        match c {
+            _ if in_str && escaped => escaped = false,
+            '\\' if in_str => escaped = true,
+            '"' => in_str = !in_str,
+            '?' if !in_str => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Removes the common indentation of all lines of the given string, as well
+/// as a leading line break and the whitespace after the last line break. This
+/// allows to indent multi-line strings like the surrounding code:
+///
+/// ```text
+/// De => "
+///     Erste Zeile
+///       Zweite Zeile
+/// ",
+/// ```
+///
+/// ... results in `"Erste Zeile\n  Zweite Zeile"`. Lines containing only
+/// whitespace don't count for the indentation and end up empty.
+pub fn dedent(s: &str) -> String {
+    let s = if s.starts_with('\n') { &s[1..] } else { s };
+    let s = match s.rfind('\n') {
+        Some(pos) if s[pos..].trim().is_empty() => &s[..pos],
+        _ => s,
+    };
+
+    let is_indent = |c: char| c == ' ' || c == '\t';
+    let indent = s.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_left_matches(is_indent).len())
+        .min()
+        .unwrap_or(0);
+
+    s.lines()
+        .map(|line| if line.trim().is_empty() { "" } else { &line[indent..] })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the given type as it's usually written, e.g. `Option<&str>`. The
+/// tokens of parsed types are separated by spaces (`Option < & str >`), which
+/// is fine for the compiler, but looks odd in exports.
+pub fn pretty_type(ty: &str) -> String {
+    // Keywords after which `::` and `(` start a new path or type.
+    let is_keyword = |s: &str| ["impl", "dyn", "mut", "as"].contains(&s);
+
+    let mut out = String::new();
+    let mut prev = "";
+    for token in ty.split_whitespace() {
+        let glue_after = prev.ends_with(|c| "&<([".contains(c)) || prev == "::";
+        let glue_before = token.starts_with(|c| ">,;)]<".contains(c))
+            || ((token == "::" || token == "(") && !prev.is_empty() && !is_keyword(prev)
+                && prev.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '>'));
+
+        if !out.is_empty() && !glue_after && !glue_before {
+            out.push(' ');
+        }
+        out.push_str(token);
+        prev = token;
+    }
+
+    out
+}
+
+
 /// Holds information about which locale-pattern were already exhausted.
 ///
 /// Is used to check for unreachable patterns, and to check whether a match
 /// has been exhausted.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PatternUsage {
     root: UsageNode<Ident>,
+
+    /// The span of the wildcard pattern, if one was used already. Used to
+    /// improve the error message for patterns after the wildcard.
+    wildcard: Option<Span>,
 }
 
 impl PatternUsage {
-    /// All idents in the given `LocaleDef` need to have valid spans!
     pub fn new(locale: &ast::LocaleDef) -> Self {
         let children = locale.langs.iter().map(|lang| {
-            let children = lang.regions.iter().map(|reg_name| {
+            let children = lang.regions.iter().map(|region| {
                 UsageNode {
                     used: false,
                     children: vec![],
-                    data: *reg_name,
+                    data: region.name,
                 }
             }).collect();
 
@@ -72,7 +327,8 @@ impl PatternUsage {
                 used: false,
                 children,
                 data: Ident::internal("Locale"),
-            }
+            },
+            wildcard: None,
         }
     }
 
@@ -84,18 +340,21 @@ impl PatternUsage {
     /// Checks if the given language can still be used. If that language has
     /// been exhausted already, an error is returned. Otherwise the language
     /// is marked as used.
-    pub fn use_lang(&mut self, lang: &str) -> Result<()> {
+    ///
+    /// `lang` is the ident used in the arm's pattern. Its span is used for
+    /// error messages.
+    pub fn use_lang(&mut self, lang: Ident) -> Result<()> {
         let is_exhausted = self.is_exhausted();
-        let lang_node = self.lang_mut(lang);
+        let is_used = {
+            let lang_node = self.lang_mut(&lang);
+            let is_used = lang_node.is_used();
+            lang_node.used = true;
+            is_used
+        };
 
-        if lang_node.is_used() || is_exhausted {
-            err!(
-                lang_node.data.span().unwrap(),
-                "unreachable pattern '{}'",
-                lang
-            )
+        if is_used || is_exhausted {
+            Err(self.unreachable_error(lang.diag_span(), &lang))
         } else {
-            lang_node.used = true;
             Ok(())
         }
     }
@@ -103,29 +362,77 @@ impl PatternUsage {
     /// Checks if the given language-region pair can still be used. If that
     /// language-region pair has been used already, an Error is returned.
     /// Otherwise the pair is marked as used.
-    pub fn use_region(&mut self, lang: &str, region: &str) -> Result<()> {
+    ///
+    /// `region` is the ident used in the arm's pattern. Its span is used for
+    /// error messages.
+    pub fn use_region(&mut self, lang: &str, region: Ident) -> Result<()> {
         let is_exhausted = self.is_exhausted();
+        let is_used = {
+            let lang_node = self.lang_mut(lang);
+            let is_lang_used = lang_node.is_used();
 
-        let lang_node = self.lang_mut(lang);
-        let is_lang_used = lang_node.is_used();
+            let region_node = match lang_node.children.iter_mut()
+                .find(|r| r.data.as_str() == region.as_str())
+            {
+                Some(node) => node,
+                None => {
+                    return err!(
+                        region.diag_span(),
+                        "language `{}` has no region `{}`",
+                        lang,
+                        region
+                    );
+                }
+            };
+            let is_used = region_node.is_used();
+            region_node.used = true;
 
-        let region_node = lang_node.children.iter_mut()
-            .find(|r| r.data.as_str() == region)
-            .unwrap();
+            is_used || is_lang_used
+        };
 
-        if region_node.is_used() || is_lang_used || is_exhausted {
-            err!(
-                region_node.data.span().unwrap(),
-                "unreachable pattern '{}({})'",
-                lang,
-                region
-            )
+        if is_used || is_exhausted {
+            let pattern = format!("{}({})", lang, region);
+            Err(self.unreachable_error(region.diag_span(), &pattern))
         } else {
-            region_node.used = true;
             Ok(())
         }
     }
 
+    /// Checks if the given region group can still be used, i.e. if at least
+    /// one of its regions wasn't used yet. If so, all regions of the group
+    /// are marked as used and the regions which weren't used before are
+    /// returned. Otherwise an error is returned.
+    ///
+    /// `group` is the ident used in the arm's pattern. Its span is used for
+    /// error messages.
+    pub fn use_group(&mut self, lang: &str, group: Ident, regions: &[Ident]) -> Result<Vec<Ident>> {
+        let is_exhausted = self.is_exhausted();
+        let unused = {
+            let lang_node = self.lang_mut(lang);
+            let is_lang_used = lang_node.is_used();
+
+            let mut unused = Vec::new();
+            for region_node in &mut lang_node.children {
+                let in_group = regions.iter().any(|r| r.as_str() == region_node.data.as_str());
+                if in_group {
+                    if !region_node.is_used() && !is_lang_used {
+                        unused.push(region_node.data);
+                    }
+                    region_node.used = true;
+                }
+            }
+
+            unused
+        };
+
+        if unused.is_empty() || is_exhausted {
+            let pattern = format!("{}({})", lang, group);
+            Err(self.unreachable_error(group.diag_span(), &pattern))
+        } else {
+            Ok(unused)
+        }
+    }
+
     /// If the pattern has been exhausted already, an error is returned.
     /// Otherwise the whole pattern is set as used.
     ///
@@ -134,17 +441,89 @@ impl PatternUsage {
     /// was a binding to `name`.
     pub fn use_wildcard(&mut self, span: Span, binding: Option<&str>) -> Result<()> {
         if self.is_exhausted() {
-            err!(
-                span,
-                "unreachable pattern '{}': match is already is_exhausted",
-                binding.unwrap_or("_")
-            )
+            let pattern = binding.unwrap_or("_");
+            let e = self.unreachable_error(span, pattern);
+            Err(e.note("match is already exhausted"))
         } else {
             self.root.used = true;
+            self.wildcard = Some(span);
             Ok(())
         }
     }
 
+    /// Returns an error saying that the given pattern at `span` is
+    /// unreachable. If that's due to an earlier wildcard pattern, we point at
+    /// the wildcard, too.
+    fn unreachable_error(&self, span: Span, pattern: &str) -> Diagnostic {
+        let e = span.error(format!("unreachable pattern '{}'", pattern));
+        match self.wildcard {
+            Some(wildcard) => e.span_note(
+                wildcard,
+                "this pattern already matches every locale; move it to the end",
+            ),
+            None => e,
+        }
+    }
+
+    /// Returns all locales which are not handled yet, e.g. `De` or `En(Us)`.
+    pub fn missing(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for lang in self.root.children.iter().filter(|l| !l.is_used()) {
+            if lang.children.is_empty() {
+                out.push(lang.data.to_string());
+            } else {
+                for region in lang.children.iter().filter(|r| !r.is_used()) {
+                    out.push(format!("{}({})", lang.data, region.data));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Returns all languages of which only some regions are handled, together
+    /// with the regions which are not handled yet. Languages which are not
+    /// handled at all are not included.
+    pub fn partially_covered_langs(&self) -> Vec<(Ident, Vec<Ident>)> {
+        if self.root.used {
+            return vec![];
+        }
+
+        self.root.children.iter()
+            .filter(|lang| !lang.is_used() && lang.children.iter().any(|r| r.is_used()))
+            .map(|lang| {
+                let missing = lang.children.iter()
+                    .filter(|r| !r.is_used())
+                    .map(|r| r.data)
+                    .collect();
+                (lang.data, missing)
+            })
+            .collect()
+    }
+
+    /// Returns all locales which are already handled by an arm naming their
+    /// language, as language and optional region, e.g. `(De, None)` or
+    /// `(En, Some(Us))`. Locales only handled by a wildcard arm (`_` or a
+    /// catch-all binding) are not included.
+    pub fn covered(&self) -> Vec<(Ident, Option<Ident>)> {
+        let mut out = Vec::new();
+        for lang in &self.root.children {
+            if lang.children.is_empty() {
+                if lang.used {
+                    out.push((lang.data, None));
+                }
+            } else {
+                for region in &lang.children {
+                    if lang.used || region.used {
+                        out.push((lang.data, Some(region.data)));
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
     fn lang_mut(&mut self, lang: &str) -> &mut UsageNode<Ident> {
         self.root.children.iter_mut()
             .find(|l| l.data.as_str() == lang)
@@ -152,7 +531,7 @@ impl PatternUsage {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct UsageNode<T> {
     used: bool,
     children: Vec<UsageNode<T>>,
@@ -168,3 +547,137 @@ impl<T> UsageNode<T> {
     }
 
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idents() {
+        assert!(is_valid_ident("greet"));
+        assert!(is_valid_ident("_greet2"));
+        assert!(is_valid_ident("Gb"));
+        assert!(is_valid_ident("grüße"));
+        assert!(is_valid_ident("привет"));
+
+        assert!(!is_valid_ident(""));
+        assert!(!is_valid_ident("_"));
+        assert!(!is_valid_ident("2fa"));
+        assert!(!is_valid_ident("greet-user"));
+        assert!(!is_valid_ident("a.b"));
+
+        // Keywords can't be used as names, but names containing them can.
+        for &keyword in &["match", "type", "fn", "self", "Self", "mod", "yield"] {
+            assert!(is_keyword(keyword));
+            assert!(!is_valid_ident(keyword), "'{}' is a keyword", keyword);
+        }
+        assert!(is_valid_ident("types"));
+        assert!(is_valid_ident("Type"));
+    }
+
+    #[test]
+    fn fallback() {
+        assert_eq!(fallback_placeholder("name?someone"), Some(("name", "someone")));
+        assert_eq!(fallback_placeholder("a?b"), Some(("a", "b")));
+        assert_eq!(fallback_placeholder("names[i]?none"), Some(("names[i]", "none")));
+        assert_eq!(fallback_placeholder("f(\"?\")?x"), Some(("f(\"?\")", "x")));
+        assert_eq!(fallback_placeholder("f()?.x?fallback"), Some(("f()?.x", "fallback")));
+        assert_eq!(fallback_placeholder("a??b"), Some(("a?", "b")));
+
+        assert_eq!(fallback_placeholder("name"), None);
+        assert_eq!(fallback_placeholder("a?"), None);
+        assert_eq!(fallback_placeholder("a? "), None);
+        assert_eq!(fallback_placeholder("foo()?.name"), None);
+        assert_eq!(fallback_placeholder("list()?[0]"), None);
+        assert_eq!(fallback_placeholder("f(a?b)"), None);
+    }
+
+    #[test]
+    fn try_operator() {
+        assert!(try_placeholder("a?"));
+        assert!(try_placeholder("a? "));
+        assert!(try_placeholder("f()?.x"));
+        assert!(try_placeholder("f()?.x?fallback"));
+        assert!(!try_placeholder("a?b"));
+        assert!(!try_placeholder("a"));
+        assert!(!try_placeholder("f(\"?\")"));
+
+        // Placeholders are either a fallback or use the try operator.
+        assert_eq!(fallback_placeholder("a?"), None);
+        assert_eq!(fallback_placeholder("a? "), None);
+        assert_eq!(fallback_placeholder("a?b"), Some(("a", "b")));
+        assert_eq!(fallback_placeholder("f()?.x"), None);
+    }
+
+    #[test]
+    fn number() {
+        assert_eq!(number_placeholder("count:n"), Some("count"));
+        assert_eq!(number_placeholder(" x.len() :n "), Some("x.len()"));
+        assert_eq!(number_placeholder("count"), None);
+        assert_eq!(number_placeholder("std::f64::consts::n"), None);
+    }
+
+    #[test]
+    fn unit_call() {
+        assert_eq!(unit_call_placeholder("@greet(name)"), Some(("greet", "name")));
+        assert_eq!(unit_call_placeholder(" @greet ( a, b ) "), Some(("greet", " a, b ")));
+        assert_eq!(unit_call_placeholder("@app_name()"), Some(("app_name", "")));
+
+        assert_eq!(unit_call_placeholder("greet(name)"), None);
+        assert_eq!(unit_call_placeholder("@env:USER"), None);
+        assert_eq!(unit_call_placeholder("@(name)"), None);
+        assert_eq!(unit_call_placeholder("@a.b(name)"), None);
+    }
+
+    #[test]
+    fn call_args() {
+        assert_eq!(split_call_args("a, b"), vec!["a", " b"]);
+        assert_eq!(split_call_args("f(a, b), [1, 2]"), vec!["f(a, b)", " [1, 2]"]);
+        assert_eq!(split_call_args(r#""a, b", c"#), vec![r#""a, b""#, " c"]);
+        assert_eq!(split_call_args(r#""\", (", c"#), vec![r#""\", (""#, " c"]);
+        assert_eq!(split_call_args("a, "), vec!["a"]);
+        assert!(split_call_args("").is_empty());
+    }
+
+    #[test]
+    fn sigils() {
+        let unescape = |s: &str| unescape_sigils(s.to_string());
+        assert_eq!(unescape("@@mauzi"), Some("@mauzi".into()));
+        assert_eq!(unescape("@@@"), Some("@@".into()));
+        assert_eq!(unescape("@@"), Some("@".into()));
+
+        // Only the start of a body is reserved.
+        assert_eq!(unescape("mail@example.com"), Some("mail@example.com".into()));
+        assert_eq!(unescape("mail@@example.com"), Some("mail@@example.com".into()));
+        assert_eq!(unescape(""), Some("".into()));
+
+        assert_eq!(unescape("@mauzi"), None);
+        assert_eq!(unescape("@"), None);
+    }
+
+    #[test]
+    fn pretty_types() {
+        assert_eq!(pretty_type("i64"), "i64");
+        assert_eq!(pretty_type("& str"), "&str");
+        assert_eq!(pretty_type("& 'a mut String"), "&'a mut String");
+        assert_eq!(pretty_type("Option < & str >"), "Option<&str>");
+        assert_eq!(pretty_type("Result < String , ( ) >"), "Result<String, ()>");
+        assert_eq!(pretty_type("Vec < Vec < u32 > >"), "Vec<Vec<u32>>");
+        assert_eq!(pretty_type("[ u8 ; 4 ]"), "[u8; 4]");
+        assert_eq!(pretty_type("impl :: std :: fmt :: Display"), "impl ::std::fmt::Display");
+        assert_eq!(pretty_type("& dyn Fn ( & str ) -> String"), "&dyn Fn(&str) -> String");
+        assert_eq!(pretty_type("& mut ( u32 , u32 )"), "&mut (u32, u32)");
+    }
+
+    #[test]
+    fn dedent_lines() {
+        let s = "\n    Erste Zeile\n      Zweite Zeile\n";
+        assert_eq!(dedent(s), "Erste Zeile\n  Zweite Zeile");
+        assert_eq!(dedent("\n    a\n\n    b\n  "), "a\n\nb");
+        assert_eq!(dedent("\n\ta\n\t\tb"), "a\n\tb");
+        assert_eq!(dedent("  a\n b"), " a\nb");
+        assert_eq!(dedent("a"), "a");
+        assert_eq!(dedent(""), "");
+    }
+}
@@ -2,6 +2,24 @@
 #![feature(proc_macro)]
 
 extern crate mauzi_macros;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+/// The `futures` crate used by units marked with `#[async]`, reexported so
+/// that the generated code and raw bodies can use the same version.
+#[cfg(feature = "async")]
+pub extern crate futures;
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, Once, RwLock, ONCE_INIT};
+
+// The hash function of the lookup tables in dynamic mode has to be exactly
+// the same as the one used to build them in `mauzi_macros`.
+#[path = "../mauzi_macros/src/phf_shared.rs"]
+mod phf_shared;
+
+mod number;
 
 
 // Currently, proc-macros can't be defined in a crate together with non-proc-
@@ -11,4 +29,455 @@ extern crate mauzi_macros;
 // stuff into `mauzi_runtime`.
 //
 // In this main crate, we just reexport everything from those crates.
-pub use mauzi_macros::mauzi;
+pub use mauzi_macros::{mauzi, mauzi_expand, mauzi_locale};
+pub use number::format_number;
+
+
+/// Implemented by all `Locale` types generated by `mauzi!`.
+///
+/// The generated code refers to this trait via `::mauzi::Locale`. If you
+/// reexport or rename this crate, tell the macro about it with
+/// `#[mauzi(crate = "my_i18n")]` in front of the locale definition.
+pub trait Locale: Copy + fmt::Debug {
+    /// Returns a human readable name of this locale, e.g. "British English".
+    fn display_name(self) -> &'static str;
+}
+
+
+/// Returns the language tags of the POSIX locale environment variables
+/// `LC_ALL`, `LC_MESSAGES` and `LANG` (in that order), e.g. `en_US` for
+/// `LANG=en_US.UTF-8`.
+///
+/// The encoding and modifier (`.UTF-8`, `@euro`) are stripped. Unset or empty
+/// variables and the special locales `C` and `POSIX` are skipped. Generated
+/// `Locale` types have a `from_env()` method using this function.
+pub fn env_language_tags() -> Vec<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .filter_map(|value| language_tag_from_posix(&value))
+        .collect()
+}
+
+/// Extracts the language tag from the value of a POSIX locale variable, e.g.
+/// `de_DE` from `de_DE.UTF-8@euro`.
+fn language_tag_from_posix(value: &str) -> Option<String> {
+    let tag = value.split(|c| c == '.' || c == '@').next().unwrap_or("").trim();
+    match tag {
+        "" | "C" | "POSIX" => None,
+        tag => Some(tag.to_string()),
+    }
+}
+
+/// Returns the value of the `locale` key in the simple `key=value` config
+/// file at `path`, e.g. `en-US` for a line `locale = en-US`.
+///
+/// Empty lines and lines starting with `#` are ignored. Returns `None` if the
+/// file can't be read or doesn't contain the key. Generated `Locale` types
+/// have a `from_config_file()` method using this function if the
+/// `config_file` feature is enabled.
+#[cfg(feature = "config_file")]
+pub fn config_file_language_tag<P: AsRef<std::path::Path>>(path: P) -> Option<String> {
+    use std::io::Read;
+
+    let mut content = String::new();
+    if std::fs::File::open(path).and_then(|mut f| f.read_to_string(&mut content)).is_err() {
+        return None;
+    }
+
+    content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap().trim();
+            parts.next().map(|value| (key, value.trim()))
+        })
+        .find(|&(key, _)| key == "locale")
+        .and_then(|(_, value)| if value.is_empty() { None } else { Some(value.to_string()) })
+}
+
+/// The error type of all fallible functions generated by `mauzi!`, e.g.
+/// parsing a `Locale` or `try_greet()`. `L` is the generated `Locale` type.
+///
+/// The generated code reexports it next to `Locale`, so it can also be used
+/// as `MauziError` from there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MauziError<L> {
+    /// The given language tag or id doesn't belong to any locale.
+    UnknownLocale(String),
+    /// The unit has no translation for the locale.
+    MissingTranslation {
+        unit: &'static str,
+        locale: L,
+    },
+    /// There is no unit with the given key.
+    UnknownUnit(String),
+    /// The unit was rendered with the wrong number of arguments.
+    ArgumentCount {
+        unit: &'static str,
+        expected: usize,
+        given: usize,
+    },
+    /// The unit can't be rendered with arguments given as `Display`.
+    NotRenderable(&'static str),
+}
+
+impl<L: fmt::Debug> fmt::Display for MauziError<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MauziError::UnknownLocale(ref tag) => {
+                write!(f, "unknown or unsupported locale '{}'", tag)
+            }
+            MauziError::MissingTranslation { unit, ref locale } => {
+                write!(f, "missing translation of unit '{}' for locale {:?}", unit, locale)
+            }
+            MauziError::UnknownUnit(ref key) => write!(f, "unknown translation unit '{}'", key),
+            MauziError::ArgumentCount { unit, expected, given } => {
+                write!(
+                    f,
+                    "translation unit '{}' takes {} argument(s), but {} were given",
+                    unit,
+                    expected,
+                    given,
+                )
+            }
+            MauziError::NotRenderable(unit) => {
+                write!(f, "translation unit '{}' can't be rendered dynamically", unit)
+            }
+        }
+    }
+}
+
+impl<L: fmt::Debug> ::std::error::Error for MauziError<L> {
+    fn description(&self) -> &str {
+        match *self {
+            MauziError::UnknownLocale(_) => "unknown or unsupported locale",
+            MauziError::MissingTranslation { .. } => "missing translation",
+            MauziError::UnknownUnit(_) => "unknown translation unit",
+            MauziError::ArgumentCount { .. } => "wrong number of arguments",
+            MauziError::NotRenderable(_) => "translation unit can't be rendered dynamically",
+        }
+    }
+}
+
+/// A function called for each use of a missing translation, with the name of
+/// the unit and the locale (e.g. `"En(Us)"`).
+pub type MissingHook = fn(unit: &str, locale: &str);
+
+/// The hook set via `set_missing_hook`, if any. A `RwLock` can't be created
+/// in a `static`, so it's created on first use and never freed.
+fn missing_hook() -> &'static RwLock<Option<MissingHook>> {
+    static INIT: Once = ONCE_INIT;
+    static mut HOOK: Option<&'static RwLock<Option<MissingHook>>> = None;
+
+    unsafe {
+        INIT.call_once(|| HOOK = Some(&*Box::into_raw(Box::new(RwLock::new(None)))));
+        HOOK.unwrap()
+    }
+}
+
+/// Sets the function which is called whenever a missing translation is used
+/// by a dictionary with `#[mauzi(on_missing = "log")]`. This is usually done
+/// once at startup.
+///
+/// Without a hook, missing translations are logged as warnings via the `log`
+/// crate if the `log` feature is enabled, and ignored otherwise.
+pub fn set_missing_hook(hook: MissingHook) {
+    *missing_hook().write().unwrap_or_else(|e| e.into_inner()) = Some(hook);
+}
+
+/// Reports a missing translation to the hook set via `set_missing_hook`.
+/// Called by the code generated by `mauzi!`.
+#[doc(hidden)]
+pub fn report_missing(unit: &str, locale: &str) {
+    // The lock is released before calling the hook, so that the hook can
+    // set another hook.
+    let hook = *missing_hook().read().unwrap_or_else(|e| e.into_inner());
+    match hook {
+        Some(hook) => hook(unit, locale),
+        None => default_missing_hook(unit, locale),
+    }
+}
+
+#[cfg(feature = "log")]
+fn default_missing_hook(unit: &str, locale: &str) {
+    warn!(target: "mauzi", "missing translation of unit '{}' for locale {}", unit, locale);
+}
+
+#[cfg(not(feature = "log"))]
+fn default_missing_hook(_unit: &str, _locale: &str) {}
+
+/// The results of a unit marked with `#[cache]`, stored in a field of the
+/// generated `Dict`. Not part of the public API.
+///
+/// The cache doesn't change the translations, so it's ignored by the traits
+/// derived for `Dict` in `hash` mode: all caches are equal and don't
+/// contribute to the hash.
+#[doc(hidden)]
+pub struct UnitCache<T>(Mutex<T>);
+
+impl<T> UnitCache<T> {
+    pub fn new(value: T) -> Self {
+        UnitCache(Mutex::new(value))
+    }
+
+    /// Locks the cache. A panic while the lock was held (e.g. in a raw body)
+    /// doesn't leave the cache in an invalid state, so poisoning is ignored.
+    pub fn lock(&self) -> MutexGuard<T> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T: Default> Default for UnitCache<T> {
+    fn default() -> Self {
+        UnitCache::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for UnitCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UnitCache")
+    }
+}
+
+impl<T> PartialEq for UnitCache<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for UnitCache<T> {}
+
+impl<T> Hash for UnitCache<T> {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+/// The error of the futures returned by units marked with `#[async]`. Raw
+/// bodies can return futures with any error converting into it.
+#[cfg(feature = "async")]
+pub type AsyncError = Box<::std::error::Error + Send + Sync>;
+
+/// The future returned by units marked with `#[async]` (e.g. `remote_tip()`
+/// returns `UnitFuture<String>`).
+///
+/// Mauzi doesn't depend on a runtime: the generated methods just create the
+/// future, which doesn't do anything until it is polled. String arms return
+/// futures which are ready immediately, raw bodies return anything
+/// implementing `IntoFuture` (e.g. the future of an HTTP client fetching a
+/// fallback translation). The future is not `Send`, since raw bodies can
+/// return any future. So it has to be run on the current thread, e.g. with
+/// the `current_thread` executor of `tokio`, or by blocking on it with
+/// `Future::wait()`.
+#[cfg(feature = "async")]
+pub type UnitFuture<T> = Box<futures::Future<Item = T, Error = AsyncError>>;
+
+/// The plural categories defined by the [CLDR][1].
+///
+/// [1]: http://cldr.unicode.org/index/cldr-spec/plural-rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Returns the plural category of the integer `n` in the language with the
+/// given language tag (e.g. `"en"`).
+///
+/// Generated `Locale` types have a `plural_category()` method calling this
+/// function, which is useful in raw bodies.
+///
+/// Only the cardinal rules of a few languages are implemented. For all other
+/// languages, `n == 1` is `One` and everything else is `Other`, which is
+/// correct for many European languages.
+pub fn plural_category(lang_tag: &str, n: u64) -> PluralCategory {
+    use PluralCategory::*;
+
+    let lang = lang_tag.split(|c| c == '-' || c == '_').next().unwrap_or("");
+    let (n10, n100) = (n % 10, n % 100);
+
+    match &*lang.to_lowercase() {
+        // No plural forms at all
+        "ja" | "ko" | "th" | "vi" | "zh" => Other,
+
+        // 0 and 1 are singular
+        "fr" | "hi" | "pt" => if n <= 1 { One } else { Other },
+
+        "cs" | "sk" => match n {
+            1 => One,
+            2...4 => Few,
+            _ => Other,
+        },
+
+        "pl" => match (n, n10, n100) {
+            (1, _, _) => One,
+            (_, 2...4, n100) if n100 < 12 || n100 > 14 => Few,
+            _ => Many,
+        },
+
+        "ru" | "uk" => match (n10, n100) {
+            (1, n100) if n100 != 11 => One,
+            (2...4, n100) if n100 < 12 || n100 > 14 => Few,
+            _ => Many,
+        },
+
+        "ar" => match (n, n100) {
+            (0, _) => Zero,
+            (1, _) => One,
+            (2, _) => Two,
+            (_, 3...10) => Few,
+            (_, 11...99) => Many,
+            _ => Other,
+        },
+
+        // English, German and many others
+        _ => if n == 1 { One } else { Other },
+    }
+}
+
+/// Returns the slot of `key` in a perfect hash table generated by `mauzi!` in
+/// dynamic mode. Not part of the public API.
+#[doc(hidden)]
+pub fn phf_index(key: &str, seed: u64, disps: &[(u32, u32)], table_len: usize) -> usize {
+    phf_shared::index(key, seed, disps, table_len)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// The missing translations reported to the hook or the logger.
+        static REPORTED: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+
+    fn take_reported() -> Vec<String> {
+        REPORTED.with(|r| r.replace(Vec::new()))
+    }
+
+    #[cfg(feature = "log")]
+    struct Logger;
+
+    #[cfg(feature = "log")]
+    impl ::log::Log for Logger {
+        fn enabled(&self, _: &::log::LogMetadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &::log::LogRecord) {
+            let msg = format!("{}: {}", record.target(), record.args());
+            REPORTED.with(|r| r.borrow_mut().push(msg));
+        }
+    }
+
+    // The hook and the logger are global, so they are tested in one test.
+    #[test]
+    fn report_missing_translations() {
+        // Without a hook, missing translations are only logged.
+        #[cfg(feature = "log")]
+        {
+            ::log::set_logger(|max| {
+                max.set(::log::LogLevelFilter::Warn);
+                Box::new(Logger)
+            }).unwrap();
+            report_missing("greet", "De");
+            assert_eq!(
+                take_reported(),
+                vec!["mauzi: missing translation of unit 'greet' for locale De"],
+            );
+        }
+        #[cfg(not(feature = "log"))]
+        {
+            report_missing("greet", "De");
+            assert!(take_reported().is_empty());
+        }
+
+        set_missing_hook(|unit, locale| {
+            REPORTED.with(|r| r.borrow_mut().push(format!("{} {}", unit, locale)));
+        });
+        report_missing("greet", "De");
+        report_missing("bye", "En(Us)");
+        assert_eq!(take_reported(), vec!["greet De", "bye En(Us)"]);
+    }
+
+    #[test]
+    fn posix_language_tag() {
+        let tag = |value| language_tag_from_posix(value);
+        assert_eq!(tag("de_DE.UTF-8"), Some("de_DE".to_string()));
+        assert_eq!(tag("en_US@euro"), Some("en_US".to_string()));
+        assert_eq!(tag("de_DE.UTF-8@euro"), Some("de_DE".to_string()));
+        assert_eq!(tag("fr"), Some("fr".to_string()));
+        assert_eq!(tag("C"), None);
+        assert_eq!(tag("C.UTF-8"), None);
+        assert_eq!(tag("POSIX"), None);
+        assert_eq!(tag(""), None);
+    }
+
+    #[cfg(feature = "config_file")]
+    #[test]
+    fn config_file_tag() {
+        use std::io::Write;
+
+        let path = ::std::env::temp_dir().join("mauzi-test-config-file.conf");
+        let tag = |content: &str| {
+            ::std::fs::File::create(&path).and_then(|mut f| f.write_all(content.as_bytes()))
+                .unwrap();
+            config_file_language_tag(&path)
+        };
+        assert_eq!(tag("locale=de-DE"), Some("de-DE".to_string()));
+        assert_eq!(tag("# settings\n\ntheme = dark\n  locale = en-US  \n"), Some("en-US".into()));
+        assert_eq!(tag("# locale = de\nlocale = fr\n"), Some("fr".to_string()));
+        assert_eq!(tag("locale =\n"), None);
+        assert_eq!(tag("theme = dark\nlocale\n"), None);
+        assert_eq!(tag(""), None);
+
+        let _ = ::std::fs::remove_file(&path);
+        assert_eq!(config_file_language_tag(&path), None);
+    }
+
+    #[test]
+    fn plural_category_en_de() {
+        use PluralCategory::*;
+
+        for &lang in &["en", "en-US", "de", "de_CH"] {
+            assert_eq!(plural_category(lang, 0), Other);
+            assert_eq!(plural_category(lang, 1), One);
+            for &n in &[2, 4, 5, 11, 14, 21, 101] {
+                assert_eq!(plural_category(lang, n), Other);
+            }
+        }
+    }
+
+    #[test]
+    fn plural_category_ru() {
+        use PluralCategory::*;
+
+        for &n in &[1, 21, 101] {
+            assert_eq!(plural_category("ru", n), One);
+        }
+        for &n in &[2, 3, 4, 22, 24, 102] {
+            assert_eq!(plural_category("ru-RU", n), Few);
+        }
+        for &n in &[0, 5, 11, 12, 13, 14, 20, 111, 112] {
+            assert_eq!(plural_category("ru", n), Many);
+        }
+    }
+
+    #[test]
+    fn plural_category_pl() {
+        use PluralCategory::*;
+
+        assert_eq!(plural_category("pl", 1), One);
+        for &n in &[2, 3, 4, 22, 24, 102] {
+            assert_eq!(plural_category("pl_PL", n), Few);
+        }
+        for &n in &[0, 5, 11, 12, 13, 14, 21, 25, 112] {
+            assert_eq!(plural_category("pl", n), Many);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Formatting of numbers according to the conventions of a language, used by
+//! `{expr:n}` placeholders.
+
+use std::fmt;
+
+
+/// Formats the number `n` with the digit grouping and decimal separator of
+/// the language with the given language tag, e.g. `1,234.5` for `"en"` and
+/// `1.234,5` for `"de"`. The region is only considered where it changes the
+/// separators, like the apostrophe in Switzerland (`1’234.5` for `"de-CH"`).
+///
+/// The number is formatted via `Display` first, so this works for all
+/// primitive number types. Generated `Locale` types have a `format_number()`
+/// method calling this function, which is used for `{expr:n}` placeholders.
+pub fn format_number<N: fmt::Display>(lang_tag: &str, n: N) -> String {
+    let mut subtags = lang_tag.split(|c| c == '-' || c == '_');
+    let lang = subtags.next().unwrap_or("").to_lowercase();
+    let region = subtags.find(|tag| tag.len() == 2).unwrap_or("").to_uppercase();
+    let swiss = match (&*lang, &*region) {
+        ("de", "CH") | ("de", "LI") | ("it", "CH") => true,
+        _ => false,
+    };
+
+    let (group_sep, decimal_sep) = match &*lang {
+        _ if swiss => ("\u{2019}", "."),
+        "da" | "de" | "es" | "id" | "it" | "nl" | "pt" | "tr" => (".", ","),
+        "cs" | "fi" | "fr" | "nb" | "pl" | "ru" | "sk" | "sv" | "uk" => ("\u{a0}", ","),
+
+        // English and many others
+        _ => (",", "."),
+    };
+
+    // Things like `inf` and `NaN` are returned unchanged.
+    let s = n.to_string();
+    if !s.trim_left_matches('-').chars().all(|c| c.is_digit(10) || c == '.') {
+        return s;
+    }
+
+    let (sign, digits) = if s.starts_with('-') { ("-", &s[1..]) } else { ("", &s[..]) };
+    let (int, frac) = match digits.find('.') {
+        Some(pos) => (&digits[..pos], Some(&digits[pos + 1..])),
+        None => (digits, None),
+    };
+
+    let mut out = sign.to_string();
+    for (i, c) in int.chars().enumerate() {
+        if i != 0 && (int.len() - i) % 3 == 0 {
+            out.push_str(group_sep);
+        }
+        out.push(c);
+    }
+    if let Some(frac) = frac {
+        out.push_str(decimal_sep);
+        out.push_str(frac);
+    }
+
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators() {
+        assert_eq!(format_number("en", 1234567), "1,234,567");
+        assert_eq!(format_number("en-US", 123), "123");
+        assert_eq!(format_number("de", 1234567), "1.234.567");
+        assert_eq!(format_number("de_CH", 1234), "1\u{2019}234");
+        assert_eq!(format_number("de-Latn-LI", 1234), "1\u{2019}234");
+        assert_eq!(format_number("de-AT", 1234), "1.234");
+        assert_eq!(format_number("fr", 1234), "1\u{a0}234");
+        assert_eq!(format_number("tlh", 1234), "1,234");
+    }
+
+    #[test]
+    fn negatives() {
+        assert_eq!(format_number("en", -1234), "-1,234");
+        assert_eq!(format_number("en", -123), "-123");
+        assert_eq!(format_number("de", -1234567), "-1.234.567");
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(format_number("en", 1234.5), "1,234.5");
+        assert_eq!(format_number("de", 1234.5), "1.234,5");
+        assert_eq!(format_number("de", -0.25), "-0,25");
+        assert_eq!(format_number("de-CH", 1234.5), "1\u{2019}234.5");
+        assert_eq!(format_number("fr", 12345.75), "12\u{a0}345,75");
+    }
+
+    #[test]
+    fn not_finite() {
+        use std::f64;
+
+        assert_eq!(format_number("de", f64::INFINITY), "inf");
+        assert_eq!(format_number("de", f64::NEG_INFINITY), "-inf");
+        assert_eq!(format_number("de", f64::NAN), "NaN");
+    }
+}
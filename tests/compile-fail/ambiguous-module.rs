@@ -0,0 +1,18 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    // Both `ambiguous.mauzi.rs` and `ambiguous/mod.mauzi.rs` exist.
+    mod ambiguous; //~ ERROR ambiguity when loading module 'ambiguous'
+}
+
+fn main() {}
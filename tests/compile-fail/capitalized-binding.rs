@@ -0,0 +1,22 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    #[mauzi(strict_patterns)]
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet {
+        De => "Hallo",
+        Eng => "Hello", //~ ERROR unknown language `Eng` in unit 'greet'
+        //~| HELP to bind the language to a variable, use a lowercase name
+    }
+}
+
+fn main() {}
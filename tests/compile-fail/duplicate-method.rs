@@ -0,0 +1,27 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    #[context = "verb"]
+    unit post {
+        De => "posten",
+        En => "post",
+    }
+
+    // The method of `post` with context `verb` is called `post_verb()`, too.
+    unit post_verb { //~ ERROR both generate the method 'post_verb'
+        De => "posten",
+        En => "post",
+    }
+}
+
+fn main() {}
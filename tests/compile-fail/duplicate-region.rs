@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En { Gb, Us, Gb }, //~ ERROR region 'Gb' of language 'En' is defined multiple times
+    }
+
+    unit greet {
+        De => "Hallo",
+        En => "Hello",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet(name:) { //~ ERROR expected type
+        De => "Hallo {name}",
+        En => "Hello {name}",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit not_found {
+        De => "Nicht da",
+        En => "Not there",
+    }
+
+    // The CSV file defines `not_found` as well.
+    #[flatten]
+    mod errors from csv("../errors.csv");
+    //~^ ERROR unit 'not_found' of flattened module 'errors' collides with another unit
+}
+
+fn main() {}
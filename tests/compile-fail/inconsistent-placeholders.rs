@@ -0,0 +1,33 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit new_emails(name: &str, count: u32) {
+        De => "{name}, du hast {count} neue E-Mails",
+        En => "{name}, you have new emails", //~ WARN arm 'En' of translation unit 'new_emails'
+        //~| NOTE missing: {count}
+    }
+
+    // Positional and indexed placeholders refer to the same parameters as
+    // named ones, and guarded arms are special cases.
+    unit greet(name: &str) {
+        De if name.is_empty() => "Hallo!",
+        De => "Hallo {}!",
+        En => "Hello {0}!",
+    }
+}
+
+// The warnings only show up in the output of a failing compilation.
+compile_error!("the warnings above are expected");
+//~^ ERROR the warnings above are expected
+
+fn main() {}
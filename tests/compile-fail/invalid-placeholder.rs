@@ -0,0 +1,23 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    // The note shows the placeholder as written, since the error can only
+    // point at the whole string.
+    unit version {
+        De => "Version {@env:CARGO_PKG_VERSION}",
+        En => "Version {@env:}", //~ ERROR missing variable name in placeholder
+        //~| NOTE the invalid placeholder is `{@env:}`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En { Gb, Us },
+    }
+
+    unit greet {
+        De => "Hallo",
+        En(Gb) => "Hello",
+        En(Xy) => "Hi", //~ ERROR `Xy` is not a region of language `En`
+    }
+}
+
+fn main() {}
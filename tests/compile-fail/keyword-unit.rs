@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit type { //~ ERROR 'type' is not a valid unit name: it's a Rust keyword
+        De => "Art",
+        En => "Type",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    // The start of a body is reserved for sigils, so a literal `@` has to be
+    // written as `@@` there.
+    unit contact {
+        De => "@@mauzi auf Twitter",
+        En => "@mauzi on Twitter", //~ ERROR a string body must not start with a single `@`
+        //~| HELP write `@@` for a literal `@`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    #[max_len = 10]
+    unit save_button {
+        De => "Änderungen speichern", //~ ERROR is 20 characters long, but at most 10 are allowed
+        En => "Save",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    unit greet { //~ ERROR the `mauzi!` body must begin with `enum Locale { ... }`, found `unit`
+        De => "Hallo",
+        En => "Hello",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit umlauts -> { //~ ERROR expected return type after '->', found '{'
+        De => { 3 }
+        En => { 0 }
+    }
+}
+
+fn main() {}
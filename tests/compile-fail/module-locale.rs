@@ -0,0 +1,18 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    // `stray_enum.mauzi.rs` starts with its own `enum Locale`.
+    mod stray_enum; //~ ERROR the locale definition must only appear in the top-level `mauzi!` body
+}
+
+fn main() {}
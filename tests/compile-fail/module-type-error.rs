@@ -0,0 +1,19 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    // `type_error.mauzi.rs` contains a raw body with a type error. The error
+    // can't point into the module file, so it points at the module name.
+    mod type_error; //~ ERROR mismatched types
+}
+
+fn main() {}
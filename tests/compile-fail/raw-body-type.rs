@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit umlauts -> u32 {
+        De => { 3 }
+        En => { "none" } //~ ERROR mismatched types
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit umlauts - > u32 { //~ ERROR expected '->', found '- >'
+        //~| HELP remove the space between '-' and '>'
+        De => { 3 }
+        En => { 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    #[mauzi(strict)]
+    enum Locale {
+        De,
+        En,
+    }
+
+    // In strict mode, the help can't suggest a wildcard arm.
+    unit build_info { //~ ERROR translation unit 'build_info' has arms with `#[cfg]`
+        //~| HELP add arms without `#[cfg]` for En
+        De => "Release-Build",
+        #[cfg(debug_assertions)] //~ NOTE conditional arm here
+        En => "debug build",
+    }
+}
+
+fn main() {}
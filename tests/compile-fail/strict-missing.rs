@@ -0,0 +1,23 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    #[mauzi(strict)]
+    enum Locale {
+        De,
+        En { Gb, Us },
+    }
+
+    // Without strict mode, `En(Us)` would fall back to German.
+    unit colour { //~ ERROR unit 'colour' doesn't handle all locales (required in strict mode)
+        //~| NOTE missing: En(Us)
+        De => "Farbe",
+        En(Gb) => "colour",
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    #[mauzi(strict)]
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet {
+        De => "Hallo",
+        _ => "Hello", //~ ERROR wildcard pattern '_' in unit 'greet' is not allowed in strict mode
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet(first: &str, last: &str) {
+        De => "Hallo {} {}!",
+        En => "Hello {}!", //~ ERROR has 1 positional placeholder(s), but the unit has 2
+    }
+}
+
+fn main() {}
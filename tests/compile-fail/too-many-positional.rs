@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet(name: &str) {
+        De => "Hallo {}!",
+        En => "Hello {} {}!", //~ ERROR has 2 positional placeholder(s), but the unit has 1
+    }
+}
+
+fn main() {}
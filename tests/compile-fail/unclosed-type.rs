@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit list(names: Vec<&str) { //~ ERROR unclosed '<' in type
+        De => { names.join(", ") }
+        En => { names.join(", ") }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet(name: &str, count: u32) { //~ WARN parameter 'count' of translation unit 'greet'
+        De => "Hallo {name}!",
+        En => "Hello {name}!",
+    }
+
+    // Guards and placeholders referring to the parameter by index count as
+    // usage.
+    unit emails(name: &str, count: u32) {
+        De if count == 0 => "Keine E-Mails für {0}",
+        De => "{count} E-Mails für {0}",
+        En => "{1} emails for {name}",
+    }
+}
+
+// The warnings only show up in the output of a failing compilation.
+compile_error!("the warnings above are expected");
+//~^ ERROR the warnings above are expected
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use mauzi::mauzi;
+
+
+mauzi! {
+    enum Locale {
+        De,
+        En,
+    }
+
+    unit greet {
+        _ => "Hello", //~ NOTE this pattern already matches every locale; move it to the end
+        De => "Hallo", //~ ERROR unreachable pattern 'De'
+    }
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+
+mod exhausted {
+    use mauzi::mauzi;
+
+    mauzi! {
+        enum Locale {
+            De,
+            En,
+        }
+
+        unit greet {
+            _ => "Hello", //~ NOTE this pattern already matches every locale
+            lang => "Hallo", //~ ERROR unreachable pattern 'lang'
+            //~| NOTE match is already exhausted
+        }
+    }
+}
+
+// `Pseudo` is added by `#[mauzi(pseudo)]` and doesn't have a span in the
+// macro input, so the error points at the macro invocation.
+mod pseudo {
+    use mauzi::mauzi;
+
+    mauzi! { //~ ERROR language 'Pseudo' is defined multiple times
+        #[mauzi(pseudo)]
+        enum Locale {
+            De,
+            Pseudo,
+        }
+
+        unit greet {
+            De => "Hallo",
+            Pseudo => "Hállö",
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+//! Compile-fail tests of the diagnostics emitted by `mauzi!`.
+//!
+//! Each file in `tests/compile-fail/` has to fail to compile with the errors
+//! annotated via `//~ ERROR`. Module files are loaded from
+//! `tests/fixtures/src/`.
+
+extern crate compiletest_rs as compiletest;
+
+use std::env;
+use std::path::Path;
+
+
+#[test]
+fn compile_fail() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    // `mauzi!` looks for module files relative to the crate being compiled.
+    env::set_var("CARGO_MANIFEST_DIR", root.join("tests").join("fixtures"));
+
+    let mut config = compiletest::Config::default();
+    config.mode = "compile-fail".parse().unwrap();
+    config.src_base = root.join("tests").join("compile-fail");
+    config.link_deps();
+    config.clean_rmeta();
+
+    compiletest::run_tests(&config);
+}
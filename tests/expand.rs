@@ -0,0 +1,191 @@
+//! Snapshot tests of the code generated by `mauzi!`, via `mauzi_expand!`.
+//!
+//! The expected code is stored in `tests/snapshots/`, with a line break after
+//! each `;`, `{` and `}` to get readable diffs. A missing snapshot is an
+//! error. After changing the code generation or adding a test, run the tests
+//! with `MAUZI_BLESS=1` to write the snapshots and review the diff.
+
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use mauzi::mauzi_expand;
+
+
+/// Replaces all whitespace in the generated code with single spaces. The
+/// string of a `TokenStream` contains line breaks at arbitrary positions, e.g.
+/// between `pub fn` and the method name.
+fn normalize(code: &str) -> String {
+    code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Inserts line breaks into the normalized code, which is a single line.
+fn split_lines(code: &str) -> String {
+    let mut out = String::new();
+    for c in code.chars() {
+        out.push(c);
+        if c == ';' || c == '{' || c == '}' {
+            out.push('\n');
+        }
+    }
+    out.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+fn assert_snapshot(name: &str, code: &str) {
+    let code = split_lines(code);
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    let bless = env::var("MAUZI_BLESS").map(|v| v != "" && v != "0").unwrap_or(false);
+
+    if bless {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path).unwrap().write_all(code.as_bytes()).unwrap();
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "snapshot {} of '{}' is missing (run with MAUZI_BLESS=1 to write it)",
+        path.display(),
+        name,
+    );
+
+    let mut expected = String::new();
+    File::open(&path).unwrap().read_to_string(&mut expected).unwrap();
+    for (i, (actual, expected)) in code.lines().zip(expected.lines()).enumerate() {
+        assert!(
+            actual == expected,
+            "generated code for '{}' differs from {} in line {}:\n  expected: {}\n  actual:   {}\n\
+                (run with MAUZI_BLESS=1 to update the snapshot)",
+            name,
+            path.display(),
+            i + 1,
+            expected,
+            actual,
+        );
+    }
+    assert_eq!(
+        code.lines().count(),
+        expected.lines().count(),
+        "generated code for '{}' has a different length than {}",
+        name,
+        path.display(),
+    );
+}
+
+#[test]
+fn simple_units() {
+    let code = normalize(mauzi_expand! {
+        enum Locale { De, En }
+
+        unit cat {
+            De => "Katze",
+            En => "cat",
+        }
+    });
+
+    assert!(code.contains("pub fn cat"));
+    assert!(code.contains("\"Katze\""));
+    assert!(code.contains("pub fn try_cat"));
+    // Without `#[mauzi(const)]` units, `new()` isn't a `const fn`.
+    assert!(code.contains("pub fn new"));
+    assert!(!code.contains("const fn"));
+    assert_snapshot("simple_units", &code);
+}
+
+#[test]
+fn params_and_fallback() {
+    let code = normalize(mauzi_expand! {
+        enum Locale { En { Gb, Us }, De }
+
+        unit greet(name: &str) {
+            En(Gb) => "Hi {name}!",
+            En => "Hello {name}!",
+        }
+    });
+
+    // `De` isn't handled, so `try_greet` has to check the locale.
+    assert!(code.contains("pub fn greet"));
+    assert!(code.contains("MissingTranslation"));
+    assert_snapshot("params_and_fallback", &code);
+}
+
+#[test]
+fn raw_bodies_and_return_types() {
+    let code = normalize(mauzi_expand! {
+        enum Locale { De, En }
+
+        unit umlauts -> u32 {
+            De => { 3 }
+            En => { 0 }
+        }
+    });
+
+    assert!(code.contains("-> u32"));
+    assert!(!code.contains("pub fn umlauts_fmt"));
+    assert_snapshot("raw_bodies_and_return_types", &code);
+}
+
+#[test]
+fn const_units() {
+    let code = normalize(mauzi_expand! {
+        enum Locale { De, En }
+
+        #[mauzi(const)]
+        unit yes {
+            De => "Ja",
+            En => "Yes",
+        }
+    });
+
+    assert!(code.contains("pub const fn yes"));
+    assert!(code.contains("pub const fn new"));
+    assert_snapshot("const_units", &code);
+}
+
+#[test]
+fn modules() {
+    let code = normalize(mauzi_expand! {
+        enum Locale { De, En }
+
+        // Module files are looked up in `src/`, so the module is loaded from
+        // a CSV file in `tests/fixtures/` instead.
+        mod errors from csv("../tests/fixtures/errors.csv");
+    });
+
+    // Submodules are fields of the `Dict` with their own type.
+    assert!(code.contains("pub errors"));
+    assert!(code.contains("errors___this_is_a_bad_solution___Dict"));
+    assert!(code.contains("pub fn not_found"));
+    assert_snapshot("modules", &code);
+}
+
+#[test]
+fn pub_crate() {
+    let code = normalize(mauzi_expand! {
+        #[mauzi(pub_crate)]
+        enum Locale { De, En }
+
+        unit cat {
+            De => "Katze",
+            En => "cat",
+        }
+    });
+
+    // The generated types and functions are only visible in the crate. The
+    // methods of `Dict` are still `pub`, the type restricts their visibility.
+    assert!(code.contains("pub ( crate ) fn new ( locale"));
+    assert!(code.contains("pub ( crate ) struct Dict"));
+    assert!(code.contains("pub ( crate ) enum Locale"));
+    assert!(!code.contains("pub enum"));
+    assert_snapshot("pub_crate", &code);
+}
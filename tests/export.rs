@@ -0,0 +1,183 @@
+//! Tests of the exports for translators (see `MAUZI_EXPORT_DIR`).
+//!
+//! The exports are written while compiling, so this test only checks them if
+//! `MAUZI_EXPORT_DIR` was set when it was compiled. CI compiles it on its own
+//! with the variable set, so that the exports of other test crates don't
+//! overwrite these.
+
+#![feature(proc_macro)]
+
+extern crate mauzi;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+
+pub mod dict {
+    use mauzi::mauzi;
+
+    mauzi! {
+        // Tools reading the exports can detect format changes by the schema
+        // version.
+        #[mauzi(schema_version = 3)]
+        enum Locale { De, En }
+
+        // The method is `open_file()`, but the key in the exports is
+        // `menu.file.open`.
+        #[key = "menu.file.open"]
+        unit open_file {
+            De => "Datei öffnen",
+            En => "Open file",
+        }
+
+        #[note = "shown to users after login"]
+        unit welcome_back(name: &str) {
+            De => "Willkommen zurück, {name}!",
+            En => "Welcome back, {name}!",
+        }
+
+        #[category = "checkout"]
+        unit pay_now {
+            De => "Jetzt bezahlen",
+            En => "Pay now",
+        }
+
+        #[no_translate]
+        unit brand {
+            De => "Mauzi Mail",
+            En => "Mauzi Mail",
+        }
+    }
+}
+
+pub mod errors {
+    use mauzi::mauzi;
+
+    mauzi! {
+        #[mauzi(export_name = "errors")]
+        enum Locale { De, En }
+
+        unit not_found {
+            De => "Nicht gefunden",
+            En => "Not found",
+        }
+    }
+}
+
+fn read_export(file_name: &str) -> Option<String> {
+    option_env!("MAUZI_EXPORT_DIR").map(|dir| {
+        let mut content = String::new();
+        File::open(Path::new(dir).join(file_name)).unwrap()
+            .read_to_string(&mut content).unwrap();
+        content
+    })
+}
+
+#[test]
+fn custom_key() {
+    assert_eq!(dict::new(dict::Locale::En).open_file(), "Open file");
+
+    let expected = [("mauzi.json", "\"menu.file.open\""), ("mauzi.md", "menu.file.open")];
+    for &(file_name, key) in &expected {
+        if let Some(export) = read_export(file_name) {
+            assert!(export.contains(key), "key missing in {}", file_name);
+            assert!(!export.contains("open_file"), "method name in {}", file_name);
+        }
+    }
+}
+
+#[test]
+fn export_name() {
+    assert_eq!(errors::new(errors::Locale::En).not_found(), "Not found");
+
+    // Each dictionary is exported into its own files.
+    let expected = [("errors.json", "\"not_found\""), ("errors.md", "not_found")];
+    for &(file_name, key) in &expected {
+        if let Some(export) = read_export(file_name) {
+            assert!(export.contains(key), "key missing in {}", file_name);
+            assert!(!export.contains("menu.file.open"), "other dictionary in {}", file_name);
+        }
+    }
+    if let Some(export) = read_export("mauzi.json") {
+        assert!(!export.contains("not_found"), "other dictionary in mauzi.json");
+    }
+}
+
+#[test]
+fn note() {
+    // The note is only meant for translators, so it's not part of the string.
+    assert_eq!(dict::new(dict::Locale::En).welcome_back("Lukas"), "Welcome back, Lukas!");
+
+    let expected = [
+        ("mauzi.json", "\"note\": \"shown to users after login\""),
+        ("mauzi.md", "- **Note:** shown to users after login"),
+    ];
+    for &(file_name, note) in &expected {
+        if let Some(export) = read_export(file_name) {
+            assert!(export.contains(note), "note missing in {}", file_name);
+        }
+    }
+}
+
+#[test]
+fn markdown() {
+    // Each unit is listed with its parameters and the string of the base
+    // language, which is the first language (German) by default.
+    let expected = "\n### `welcome_back`\n\n\
+        - **Parameters:** `name: &str`\n\
+        - **Note:** shown to users after login\n\
+        \n```text\nWillkommen zurück, {name}!\n```\n";
+    if let Some(export) = read_export("mauzi.md") {
+        assert!(export.contains("\n## Top level\n"), "module heading missing in mauzi.md");
+        assert!(export.contains(expected), "unit missing in mauzi.md:\n{}", export);
+        assert!(!export.contains("Welcome back"), "other language in mauzi.md");
+    }
+}
+
+#[test]
+fn no_translate() {
+    // The unit is generated as usual, but not exported.
+    assert_eq!(dict::new(dict::Locale::De).brand(), "Mauzi Mail");
+
+    for file_name in &["mauzi.json", "mauzi.md"] {
+        if let Some(export) = read_export(file_name) {
+            assert!(!export.contains("brand"), "unit in {}", file_name);
+            assert!(!export.contains("Mauzi Mail"), "string of the unit in {}", file_name);
+        }
+    }
+}
+
+#[test]
+fn schema_version() {
+    let expected = [("mauzi.json", "\"schema_version\": 3,"), ("mauzi.md", "Schema version: 3")];
+    for &(file_name, version) in &expected {
+        if let Some(export) = read_export(file_name) {
+            assert!(export.contains(version), "schema version missing in {}", file_name);
+        }
+    }
+
+    // Without `schema_version`, there is no version in the exports.
+    for file_name in &["errors.json", "errors.md"] {
+        if let Some(export) = read_export(file_name) {
+            assert!(!export.to_lowercase().contains("schema"), "version in {}", file_name);
+        }
+    }
+}
+
+#[test]
+fn category() {
+    // The category only exists in the exports, the method is `pay_now()` as
+    // usual.
+    assert_eq!(dict::new(dict::Locale::En).pay_now(), "Pay now");
+
+    let expected = [
+        ("mauzi.json", "\"category\": \"checkout\""),
+        ("mauzi.md", "- **Category:** checkout"),
+    ];
+    for &(file_name, category) in &expected {
+        if let Some(export) = read_export(file_name) {
+            assert!(export.contains(category), "category missing in {}", file_name);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+unit greet {
+    De => "Hallo",
+    En => "Hello",
+}
@@ -0,0 +1,9 @@
+enum Locale {
+    De,
+    En,
+}
+
+unit greet {
+    De => "Hallo",
+    En => "Hello",
+}
@@ -0,0 +1,5 @@
+// The raw body for `De` returns a string instead of a `u32`.
+unit umlauts -> u32 {
+    De => { "drei" }
+    En => { 0 }
+}
@@ -0,0 +1,367 @@
+//! Tests of the generated code, using the dictionaries of the `full` example
+//! (`examples/full/`).
+
+#![feature(proc_macro)]
+#![cfg_attr(feature = "try_from", feature(try_from))]
+
+extern crate mauzi;
+
+#[path = "../examples/full/dict.rs"]
+pub mod dict;
+#[path = "../examples/full/eu_dict.rs"]
+pub mod eu_dict;
+#[path = "../examples/full/html_dict.rs"]
+pub mod html_dict;
+
+use dict::{EnRegion, Language, Locale, MauziError, Role};
+
+
+#[test]
+fn display_names() {
+    let locales = [
+        Locale::De,
+        Locale::En(EnRegion::Gb),
+        Locale::En(EnRegion::Us),
+        Locale::En(EnRegion::Au),
+    ];
+
+    // Locales without a name given in the definition are named after their
+    // language tag.
+    let names = locales.iter().map(|locale| locale.display_name()).collect::<Vec<_>>();
+    assert_eq!(names, ["de", "British English", "American English", "en-AU"]);
+    assert!(!Locale::De.is_en());
+    assert!(locales[1..].iter().all(|locale| locale.is_en()));
+}
+
+#[test]
+fn units_de() {
+    let dict = dict::new(Locale::De);
+    assert_eq!(dict.greet("Ferris"), "Hallo Ferris, wie geht's dir?");
+    assert_eq!(dict.fav_color(), "Was ist deine Lieblingsfarbe?");
+    assert_eq!(dict.beta_notice(), "This feature is still in beta.");
+    assert_eq!(dict.lives(3), "3 Leben übrig");
+    assert_eq!(dict.build_info(), "Release-Build");
+    assert_eq!(dict.contact(), "Fragen? Schreib an help@example.com");
+    assert_eq!(dict.welcome_back("Ferris"), "Willkommen zurück, Ferris!");
+    assert_eq!(dict.visits("Ferris", 3), "Ferris war 3 Mal hier");
+    assert_eq!(dict.help_text(), "Benutzung: mauzi [OPTIONEN]\n  -h  Zeigt diese Hilfe an");
+    assert_eq!(dict.save_button(), "Speichern");
+    assert_eq!(dict.route("Berlin", "Paris"), "Von Berlin nach Paris");
+    assert_eq!(
+        dict.round_trip("Berlin", "Paris"),
+        "Von Berlin nach Paris und zurück nach Berlin",
+    );
+    assert_eq!((dict.post_noun(), dict.post_verb()), ("Beitrag".into(), "Veröffentlichen".into()));
+    assert_eq!(dict.new_emails(1), "Sie haben eine neue E-Mail");
+    assert_eq!(dict.new_emails(3), "Sie haben 3 neue E-Mails");
+    assert_eq!(dict.unread_emails(1), "Eine ungelesene E-Mail");
+    assert_eq!(dict.unread_emails(4), "4 ungelesene E-Mails");
+    assert_eq!(dict.new_messages(1), "Eine neue Nachricht");
+    assert_eq!(dict.new_messages(5), "5 neue Nachrichten");
+    assert_eq!(dict.favorite_foods(&["Pizza", "Sushi"]), "Meine Lieblingsessen: Pizza, Sushi");
+    assert_eq!(dict.points(42), "42 Punkte");
+    assert_eq!(dict.code_snippet("main"), "fn main() {}");
+    assert_eq!(dict.signed(&mut "Hello".to_string(), "Ferris"), "Hello -- Ferris");
+    assert_eq!(dict.locale_info(), "Deutsch");
+    assert_eq!(dict.number_of_umlauts(), 3);
+    assert_eq!(dict.age("27"), Ok("27 Jahre".to_string()));
+    assert!(dict.age("alt").is_err());
+    assert_eq!(dict.height("180"), Ok("180 cm groß".to_string()));
+    assert_eq!(dict.balance(12345.5), "Kontostand: 12,345.5 USD");
+    assert_eq!(dict.reviewed_by(Some("Anna")), "Geprüft von: Anna");
+    assert_eq!(dict.reviewed_by(None), "Geprüft von: niemandem");
+    assert_eq!(dict.countdown(3), "3... 2... 1... ");
+    assert_eq!(dict.countdown(0), "Liftoff!");
+    assert_eq!(dict.app_name(), "Mauzi");
+    assert_eq!(dict.version(), concat!("Version ", env!("CARGO_PKG_VERSION")));
+    assert_eq!(dict.warning(), "ACHTUNG!");
+    assert_eq!(dict.goodbye("Ferris"), "TSCHÜSS, FERRIS!");
+    assert_eq!(dict.farewell("Ferris"), "TSCHÜSS, FERRIS! (Mauzi)");
+    assert_eq!(
+        dict.welcome_visitor("Ferris"),
+        "Hallo Ferris, wie geht's dir? Schön, dass du da bist.",
+    );
+    assert_eq!(dict.color_word(), "Farbe");
+    assert_eq!(dict.welcome(Role::Admin), "Willkommen zurück, Chef!");
+    assert_eq!(dict.welcome(Role::User), "Willkommen zurück!");
+
+    // The method is named after the unit, not after its export key.
+    assert_eq!(dict.open_file(), "Datei öffnen");
+}
+
+#[test]
+fn units_en() {
+    let dict = dict::new(Locale::En(EnRegion::Us));
+    assert_eq!(dict.greet("Ferris"), "Hi Ferris! How are you, buddy?");
+    assert_eq!(dict.fav_color(), "What is your favorite color?");
+    assert_eq!(dict.beta_notice(), "This feature is still in beta.");
+    assert_eq!(dict.lives(3), "3 lives left");
+    let build = if cfg!(debug_assertions) { "debug build" } else { "release build" };
+    assert_eq!(dict.build_info(), build);
+    assert_eq!(dict.contact(), "@mauzi on Twitter or help@example.com");
+    assert_eq!(dict.welcome_back("Ferris"), "Welcome back, Ferris!");
+    assert_eq!(dict.visits("Ferris", 3), "Ferris visited 3 times");
+    assert_eq!(dict.help_text(), "Usage: mauzi [OPTIONS]\n  -h  Shows this help");
+    assert_eq!(dict.save_button(), "Save");
+    assert_eq!(dict.route("Berlin", "Paris"), "From Berlin to Paris");
+    assert_eq!(dict.round_trip("Berlin", "Paris"), "From Berlin to Paris and back to Berlin");
+    assert_eq!((dict.post_noun(), dict.post_verb()), ("Post".into(), "Post".into()));
+    assert_eq!(dict.new_emails(1), "You have one new email");
+    assert_eq!(dict.new_emails(3), "You have 3 new emails");
+    assert_eq!(dict.unread_emails(1), "One unread email");
+    assert_eq!(dict.unread_emails(4), "4 unread emails");
+    assert_eq!(dict.new_messages(1), "One new message");
+    assert_eq!(dict.new_messages(5), "5 new messages");
+    assert_eq!(dict.favorite_foods(&["Pizza", "Sushi"]), "My favorite foods: Pizza and Sushi");
+    assert_eq!(dict.points(42), "42 points");
+    assert_eq!(dict.code_snippet("main"), "fn main() {}");
+    assert_eq!(dict.signed(&mut "Hello".to_string(), "Ferris"), "Hello by Ferris");
+    assert_eq!(dict.locale_info(), "language: en, region: US");
+    assert_eq!(dict.number_of_umlauts(), 0);
+    assert_eq!(dict.age("27"), Ok("27 years".to_string()));
+    assert_eq!(dict.height("180"), Ok("180 cm tall".to_string()));
+    assert!(dict.height("tall").is_err());
+    assert_eq!(dict.balance(12345.5), "Balance: 12,345.5 USD");
+    assert_eq!(dict.reviewed_by(Some("Anna")), "Reviewed by: Anna");
+    assert_eq!(dict.reviewed_by(None), "Reviewed by: nobody");
+    assert_eq!(dict.version(), concat!("version ", env!("CARGO_PKG_VERSION")));
+    assert_eq!(dict.warning(), "ATTENTION!");
+    assert_eq!(dict.goodbye("Ferris"), "BYE, FERRIS!");
+    assert_eq!(dict.farewell("Ferris"), "BYE, FERRIS! (Mauzi)");
+    assert_eq!(
+        dict.welcome_visitor("Ferris"),
+        "Hi Ferris! How are you, buddy? Nice to have you here.",
+    );
+    assert_eq!(dict.color_word(), "color");
+    assert_eq!(dict.welcome(Role::Admin), "Welcome back, boss!");
+    assert_eq!(dict.welcome(Role::User), "Welcome back!");
+}
+
+#[test]
+fn regions() {
+    let (gb, au) = (dict::new(Locale::En(EnRegion::Gb)), dict::new(Locale::En(EnRegion::Au)));
+    assert_eq!(gb.greet("Ferris"), "Hi Ferris! Are you all right, mate?");
+    assert_eq!(au.greet("Ferris"), "G'day Ferris!");
+    assert_eq!(gb.fav_color(), "What is your favourite colour?");
+    assert_eq!(au.fav_color(), "What is your favourite colour?");
+    assert_eq!(gb.color_word(), "colour");
+    assert_eq!(au.locale_info(), "language: en, region: AU");
+
+    // Only British English gets tea, all other locales use the wildcard arm.
+    assert_eq!(dict::new(Locale::En(EnRegion::Gb)).drink(), "Tea");
+    assert_eq!(dict::new(Locale::En(EnRegion::Us)).drink(), "Coffee");
+    assert_eq!(dict::new(Locale::De).drink(), "Coffee");
+
+    // If only the language is known, the default region is used.
+    assert_eq!(Locale::from_language(Language::En), Locale::En(EnRegion::Gb));
+    assert_eq!(dict::new_lang(Language::En).fav_color(), "What is your favourite colour?");
+}
+
+#[test]
+fn cache() {
+    // The cached `banner` is only evaluated once per dictionary and width.
+    let cached = dict::new(Locale::De);
+    assert_eq!(cached.banner(30), "========= Willkommen =========");
+    assert_eq!(cached.banner(30), "========= Willkommen =========");
+    assert_eq!(cached.banner(20), "==== Willkommen ====");
+    assert_eq!(dict::BANNER_RENDERS.load(::std::sync::atomic::Ordering::SeqCst), 2);
+
+    let dict = dict::new(Locale::En(EnRegion::Us));
+    assert_eq!(dict.banner(30), "========== Welcome ===========");
+}
+
+#[test]
+fn closest() {
+    // Since `Au` isn't supported, the default region of English is used.
+    let supported = [Locale::De, Locale::En(EnRegion::Gb), Locale::En(EnRegion::Us)];
+    assert_eq!(Locale::En(EnRegion::Au).closest(&supported), Some(Locale::En(EnRegion::Gb)));
+    assert_eq!(Locale::En(EnRegion::Us).closest(&supported), Some(Locale::En(EnRegion::Us)));
+}
+
+#[test]
+fn closures() {
+    let dict = dict::new(Locale::En(EnRegion::Us));
+    let greet = dict.greet_fn();
+    assert_eq!(greet("Anna"), "Hi Anna! How are you, buddy?");
+    assert_eq!(greet("Ferris"), "Hi Ferris! How are you, buddy?");
+}
+
+#[test]
+fn all_locales() {
+    // The pseudo locale is included, too.
+    let all = dict::new(Locale::De).fav_color_all();
+    assert_eq!(all.iter().map(|&(locale, _)| locale).collect::<Vec<_>>(), Locale::all());
+    assert_eq!(all[0].1, "Was ist deine Lieblingsfarbe?");
+    assert_eq!(all[1].1, "What is your favourite colour?");
+    assert_eq!(all[2].1, "What is your favorite color?");
+    assert_eq!(all[3].1, "What is your favourite colour?");
+
+    // `Pseudo` shows the strings of the base language, so it's available
+    // whenever the base language is.
+    assert_eq!(
+        dict::new(Locale::De).drink_available_locales(),
+        vec![Locale::En(EnRegion::Gb), Locale::Pseudo],
+    );
+}
+
+#[test]
+fn parse_locale() {
+    assert_eq!("en-US".parse::<Locale>(), Ok(Locale::En(EnRegion::Us)));
+    assert_eq!("en_us".parse::<Locale>(), Ok(Locale::En(EnRegion::Us)));
+    assert_eq!("en".parse::<Locale>(), Ok(Locale::En(EnRegion::Gb)));
+    assert_eq!("fr".parse::<Locale>(), Err(MauziError::UnknownLocale("fr".to_string())));
+}
+
+#[test]
+fn from_env() {
+    // For an unsupported region, the default region of the language is used.
+    ::std::env::set_var("LC_ALL", "en_US.UTF-8");
+    assert_eq!(Locale::from_env(), Some(Locale::En(EnRegion::Us)));
+    ::std::env::set_var("LC_ALL", "en_NZ.UTF-8");
+    assert_eq!(Locale::from_env(), Some(Locale::En(EnRegion::Gb)));
+}
+
+#[test]
+fn try_units() {
+    // The `try_` methods don't fall back for missing translations.
+    let missing = MauziError::MissingTranslation { unit: "beta_notice", locale: Locale::De };
+    assert_eq!(dict::new(Locale::De).try_beta_notice(), Err(missing.clone()));
+    assert_eq!(missing.to_string(), "missing translation of unit 'beta_notice' for locale De");
+    assert_eq!(
+        dict::new(Locale::En(EnRegion::Us)).try_beta_notice(),
+        Ok("This feature is still in beta.".to_string()),
+    );
+}
+
+#[test]
+fn region_sets_and_dynamic_lookup() {
+    use eu_dict::{EuRegion, Locale};
+
+    // Both languages use the same region set, so they share the region type
+    // `EuRegion`.
+    let (fr, de): (fn(EuRegion) -> Locale, fn(EuRegion) -> Locale) = (Locale::Fr, Locale::De);
+    assert_eq!(eu_dict::new(fr(EuRegion::Ch)).hello(), "Salut");
+    assert_eq!(eu_dict::new(fr(EuRegion::Be)).hello(), "Bonjour");
+    assert_eq!(eu_dict::new(de(EuRegion::Ch)).hello(), "Grüezi");
+    assert_eq!(eu_dict::new(de(EuRegion::Be)).hello(), "Hallo");
+    assert_eq!(Locale::De(EuRegion::Be).as_str(), "de-BE");
+    assert_eq!(Locale::Fr(EuRegion::Ch).as_str(), "fr-CH");
+
+    let dict = eu_dict::new(Locale::De(EuRegion::Ch));
+    assert_eq!(
+        dict.render_key("welcome", &[&"Ferris", &1200]).unwrap(),
+        "Willkommen, Ferris! Du hast 1’200 Nachrichten.",
+    );
+    assert_eq!(dict.render_key("hello", &[]).unwrap(), "Grüezi");
+    assert_eq!(dict.render_key("yes", &[]), Ok("Ja".to_string()));
+
+    // `MauziError` is generic over the locale type, so it's the same for all
+    // dictionaries.
+    assert_eq!(
+        dict.render_key("welcome", &[&"Ferris"]),
+        Err(MauziError::ArgumentCount { unit: "welcome", expected: 2, given: 1 }),
+    );
+    assert_eq!(
+        dict.render_key("farewell", &[]),
+        Err(MauziError::UnknownUnit("farewell".to_string())),
+    );
+
+    // Only `const` units can be looked up without allocating.
+    assert_eq!(dict.get_str("hello"), None);
+    assert_eq!(dict.get("hello"), Some("Grüezi".to_string()));
+}
+
+#[test]
+fn numeric_codes_and_ids() {
+    assert_eq!(Locale::from_numeric(Language::En, 840), Some(Locale::En(EnRegion::Us)));
+    assert_eq!(Locale::from_numeric(Language::En, 250), None);
+    assert_eq!(Locale::from_numeric(Language::De, 276), Some(Locale::De));
+
+    // Each locale has a stable id: its index in declaration order.
+    for (i, &locale) in Locale::all().iter().enumerate() {
+        #[cfg(feature = "try_from")]
+        {
+            use std::convert::TryFrom;
+            assert_eq!(Locale::try_from(locale.id()), Ok(locale));
+        }
+        assert_eq!(locale.id() as usize, i);
+    }
+    assert_eq!(Locale::En(EnRegion::Us).id(), 2);
+}
+
+#[test]
+fn tags() {
+    assert_eq!(Locale::En(EnRegion::Us).as_str(), "en-US");
+    assert_eq!(Locale::De.as_str(), "de");
+
+    // `Display` shows the language tag, while `Debug` shows the Rust variant.
+    let locale = Locale::En(EnRegion::Us);
+    assert_eq!(locale.to_string(), "en-US");
+    assert_eq!(format!("{:?}", locale), "En(Us)");
+    assert_eq!(locale.debug_tag(), "En(Us)");
+    assert_eq!(Locale::De.debug_tag(), "De");
+
+    // Locales can be compared with language tags (`str` and `&str`),
+    // ignoring the case.
+    assert!(Locale::De == "de");
+    assert!(Locale::De == *"de");
+    assert!(Locale::En(EnRegion::Us) == "en-US");
+    assert!(Locale::En(EnRegion::Us) == "EN-us");
+    assert!(Locale::De == "DE");
+    assert!(Locale::De != "en");
+    assert!(Locale::En(EnRegion::Us) != "en-GB");
+
+    // A locale with region is only equal to its full tag.
+    assert!(Locale::En(EnRegion::Us) != "en");
+    assert!(Locale::En(EnRegion::Gb) != "en");
+}
+
+#[cfg(feature = "config_file")]
+#[test]
+fn from_config_file() {
+    use std::io::Write;
+
+    // A missing file or key just results in `None`.
+    let path = ::std::env::temp_dir().join("mauzi-test.conf");
+    ::std::fs::File::create(&path)
+        .and_then(|mut f| f.write_all(b"# app settings\nlocale = en-US\n"))
+        .unwrap();
+    assert_eq!(Locale::from_config_file(&path), Some(Locale::En(EnRegion::Us)));
+    assert_eq!(Locale::from_config_file("/does/not/exist"), None);
+    let _ = ::std::fs::remove_file(&path);
+}
+
+#[test]
+fn format_macro() {
+    // Only the interpolated name is escaped, not the markup of the string.
+    assert_eq!(
+        html_dict::new(html_dict::Locale::En).greet("<script>"),
+        "<b>Hello &lt;script&gt;!</b>",
+    );
+}
+
+#[test]
+fn pseudo_locale() {
+    // Placeholders are not changed.
+    assert_eq!(dict::new(Locale::Pseudo).lives(3), "[!!! 3 ļívéš ļéft !!!]");
+}
+
+#[test]
+fn hash() {
+    let mut cache = ::std::collections::HashMap::new();
+    for _ in 0..2 {
+        let key = (dict::new(Locale::De), 7);
+        let text = cache.entry(key).or_insert_with(|| dict::new(Locale::De).lives(7));
+        assert_eq!(*text, "7 Leben übrig");
+    }
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn global() {
+    // Initializing it a second time fails and returns the given locale.
+    assert_eq!(dict::init_global(Locale::De), Ok(()));
+    assert_eq!(dict::init_global(Locale::En(EnRegion::Us)), Err(Locale::En(EnRegion::Us)));
+    assert_eq!(dict::global().fav_color(), "Was ist deine Lieblingsfarbe?");
+    assert_eq!(dict::greet("Ferris"), "Hallo Ferris, wie geht's dir?");
+}
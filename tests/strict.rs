@@ -0,0 +1,61 @@
+//! Tests of dictionaries in strict mode, in which every unit has to handle
+//! every locale explicitly.
+
+#![feature(proc_macro)]
+#![cfg_attr(feature = "try_from", feature(try_from))]
+
+extern crate mauzi;
+
+
+pub mod dict {
+    use mauzi::mauzi;
+
+    mauzi! {
+        #[mauzi(strict)]
+        enum Locale {
+            De,
+            En { Gb, Us },
+        }
+
+        // A language arm handles all of its regions.
+        unit greet(name: &str) {
+            De => "Hallo {name}!",
+            En => "Hello {name}!",
+        }
+
+        unit colour {
+            De => "Farbe",
+            En(Gb) => "colour",
+            En(Us) => "color",
+        }
+
+        // The arms without `#[cfg]` handle all locales, so no wildcard arm
+        // is needed.
+        unit build_info {
+            #[cfg(debug_assertions)]
+            En => "debug build",
+            De => "Release-Build",
+            En => "release build",
+        }
+    }
+}
+
+use dict::{EnRegion, Locale};
+
+
+#[test]
+fn all_locales_handled() {
+    let de = dict::new(Locale::De);
+    assert_eq!(de.greet("Lukas"), "Hallo Lukas!");
+    assert_eq!(de.colour(), "Farbe");
+    assert_eq!(de.build_info(), "Release-Build");
+
+    let gb = dict::new(Locale::En(EnRegion::Gb));
+    assert_eq!(gb.greet("Lukas"), "Hello Lukas!");
+    assert_eq!(gb.colour(), "colour");
+
+    let us = dict::new(Locale::En(EnRegion::Us));
+    assert_eq!(us.colour(), "color");
+    let build = if cfg!(debug_assertions) { "debug build" } else { "release build" };
+    assert_eq!(us.build_info(), build);
+}